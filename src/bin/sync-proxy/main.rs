@@ -0,0 +1,71 @@
+// Reference proxy for the hub. It implements the proxy-sync protocol over a local store,
+// see docs/proxy-sync.md, and doubles as the conformance target of the hub.
+//
+// PORT                        port to listen on, 3000 by default
+// PROXY_STORE_FILE            json file the keys are kept in, in memory only when missing
+// PROXY_EXPIRY_INTERVAL_MS    how often the keys whose ttl is over are removed
+// PROXY_TOMBSTONE_TTL_SECS    how long the version of a removed key is kept, one day by default.
+//                             It should not be longer than the hub's TOMBSTONE_TTL_SECS
+
+use std::{net::SocketAddr, time::Duration};
+
+use dotenvy::var;
+use tokio::time::sleep;
+
+use crate::{routes::routes, store::Store};
+
+// Shared with the hub so that both apply the changes to a value the same way
+#[path = "../../models/data_op.rs"]
+mod data_op;
+// Shared with the hub so that both hash values the same way
+#[allow(dead_code)]
+#[path = "../../models/digest.rs"]
+mod digest;
+mod routes;
+mod store;
+
+#[tokio::main]
+async fn main() {
+    let store = match var("PROXY_STORE_FILE") {
+        Ok(path) => Store::file(path.into()),
+        Err(_) => Store::memory(),
+    };
+    let store = match var("PROXY_TOMBSTONE_TTL_SECS") {
+        Ok(value) => store.keep_tombstones_for(Duration::from_secs(
+            value
+                .parse()
+                .expect("Cannot parse PROXY_TOMBSTONE_TTL_SECS to number"),
+        )),
+        Err(_) => store,
+    };
+
+    let expiry_interval = Duration::from_millis(
+        var("PROXY_EXPIRY_INTERVAL_MS")
+            .map(|value| {
+                value
+                    .parse()
+                    .expect("Cannot parse PROXY_EXPIRY_INTERVAL_MS to number")
+            })
+            .unwrap_or(1_000),
+    );
+
+    // Expired keys are already hidden, this only frees them
+    let sweeper = store.clone();
+    tokio::spawn(async move {
+        loop {
+            sleep(expiry_interval).await;
+            sweeper.sweep().await;
+        }
+    });
+
+    let port = var("PORT")
+        .map(|port| port.parse().expect("Cannot parse PORT to number"))
+        .unwrap_or(3000);
+
+    let addr = SocketAddr::from(([0, 0, 0, 0], port));
+
+    axum::Server::bind(&addr)
+        .serve(routes(store).into_make_service())
+        .await
+        .expect("Server crashed")
+}
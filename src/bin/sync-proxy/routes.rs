@@ -0,0 +1,476 @@
+use std::collections::BTreeMap;
+
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    routing::{get, post},
+    Json, Router,
+};
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use crate::{
+    data_op::DataOp,
+    digest::{hash_value, Digest, DIGEST_PATH},
+    store::{now, Entry, Store},
+};
+
+// Wire format of the hub's requests, fields the proxy has no use for are ignored
+
+#[derive(Deserialize)]
+struct SetData {
+    #[serde(rename = "type")]
+    _type: String,
+    key: String,
+    value: Value,
+    ttl: Option<i64>,
+    version: Option<i64>,
+}
+
+#[derive(Deserialize)]
+struct SetMultiData {
+    #[serde(rename = "type")]
+    _type: String,
+    data: Value,
+    // Type of every key when they differ, `type` is then Multi
+    #[serde(default)]
+    types: BTreeMap<String, String>,
+    #[serde(default)]
+    versions: BTreeMap<String, i64>,
+}
+
+#[derive(Deserialize)]
+struct DeleteData {
+    key: String,
+    ttl: Option<i64>,
+    version: Option<i64>,
+}
+
+#[derive(Deserialize)]
+struct UpdateData {
+    #[serde(rename = "type")]
+    _type: String,
+    key: String,
+    version: Option<i64>,
+    #[serde(flatten)]
+    op: DataOp,
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "kind", content = "payload", rename_all = "snake_case")]
+enum Operation {
+    Set(SetData),
+    SetMulti(SetMultiData),
+    Delete(DeleteData),
+    Update(UpdateData),
+}
+
+#[derive(Deserialize)]
+struct Envelope {
+    id: String,
+    #[serde(flatten)]
+    operation: Operation,
+}
+
+fn ok() -> Response {
+    Json(json!({ "status": "ok" })).into_response()
+}
+
+async fn health() -> Response {
+    ok()
+}
+
+async fn capabilities() -> Response {
+    Json(json!({
+        "versions": ["v1", "v2"],
+        "features": ["digest", "ttl", "ops"],
+    }))
+    .into_response()
+}
+
+async fn set_data(State(store): State<Store>, Json(body): Json<SetData>) -> Response {
+    store
+        .set([(
+            body.key,
+            Entry {
+                _type: body._type,
+                value: body.value,
+                expires_at: body
+                    .ttl
+                    .filter(|ttl| *ttl > 0)
+                    .map(|ttl| now().saturating_add(ttl.saturating_mul(1000))),
+                version: body.version,
+            },
+        )])
+        .await;
+    ok()
+}
+
+async fn set_multi_data(State(store): State<Store>, Json(body): Json<SetMultiData>) -> Response {
+    let Value::Object(data) = body.data else {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(json!({ "error": "data must be an object of key to value" })),
+        )
+            .into_response();
+    };
+
+    store
+        .set(data.into_iter().map(|(key, value)| {
+            let version = body.versions.get(&key).copied();
+            let _type = body.types.get(&key).unwrap_or(&body._type).clone();
+            (
+                key,
+                Entry {
+                    _type,
+                    value,
+                    expires_at: None,
+                    version,
+                },
+            )
+        }))
+        .await;
+    ok()
+}
+
+// A delete with a ttl only schedules the key to expire, the same way the hub does
+async fn delete_data(State(store): State<Store>, Json(body): Json<DeleteData>) -> Response {
+    match body.ttl {
+        Some(ttl) if ttl > 0 => store.expire(&body.key, ttl, body.version).await,
+        _ => store.delete(&body.key, body.version).await,
+    }
+    ok()
+}
+
+// A change the current value cannot take is refused, leaving the key as it was
+async fn update_data(State(store): State<Store>, Json(body): Json<UpdateData>) -> Response {
+    match store
+        .update(&body.key, &body._type, &body.op, body.version)
+        .await
+    {
+        Ok(()) => ok(),
+        Err(error) => (StatusCode::CONFLICT, Json(json!({ "error": error }))).into_response(),
+    }
+}
+
+// Every operation of v2 comes wrapped in an envelope,
+// and the ones already applied are acknowledged again without applying them twice.
+// An operation that was refused is tried again when it is sent again
+async fn envelope(State(store): State<Store>, Json(body): Json<Envelope>) -> Response {
+    if store.seen(&body.id) {
+        return ok();
+    }
+
+    let response = match body.operation {
+        Operation::Set(operation) => set_data(State(store.clone()), Json(operation)).await,
+        Operation::SetMulti(operation) => {
+            set_multi_data(State(store.clone()), Json(operation)).await
+        }
+        Operation::Delete(operation) => delete_data(State(store.clone()), Json(operation)).await,
+        Operation::Update(operation) => update_data(State(store.clone()), Json(operation)).await,
+    };
+
+    if response.status().is_success() {
+        store.remember(&body.id);
+    }
+    response
+}
+
+async fn digest(State(store): State<Store>) -> Response {
+    Json(Digest {
+        keys: store
+            .live()
+            .into_iter()
+            .map(|(key, entry)| (key, hash_value(&entry.value)))
+            .collect(),
+    })
+    .into_response()
+}
+
+// Lets the services next to the proxy read the synced keys
+async fn get_data(State(store): State<Store>, Path(key): Path<String>) -> Response {
+    match store.get(&key) {
+        Some(Entry {
+            _type,
+            value,
+            expires_at,
+            version,
+        }) => Json(json!({
+            "key": key,
+            "type": _type,
+            "value": value,
+            "expires_at": expires_at,
+            "version": version,
+        }))
+        .into_response(),
+        None => (
+            StatusCode::NOT_FOUND,
+            Json(json!({ "error": format!("Key {key} not found") })),
+        )
+            .into_response(),
+    }
+}
+
+pub fn routes(store: Store) -> Router {
+    Router::new()
+        .route("/health", get(health))
+        .route("/proxy-sync/capabilities", get(capabilities))
+        .route(
+            "/proxy-sync/v1",
+            post(set_data).delete(delete_data).patch(update_data),
+        )
+        .route("/proxy-sync/v1/multi", post(set_multi_data))
+        .route("/proxy-sync/v2", post(envelope))
+        .route(DIGEST_PATH, get(digest))
+        .route("/data/:key", get(get_data))
+        .with_state(store)
+}
+
+#[cfg(test)]
+mod tests {
+    use axum_test_helper::TestClient;
+    use reqwest::StatusCode;
+    use serde_json::{json, Value};
+
+    use crate::{
+        digest::hash_value,
+        store::{Entry, Store},
+    };
+
+    use super::routes;
+
+    #[tokio::test]
+    async fn proxy_should_apply_sync_calls_and_report_them_in_its_digest() {
+        let test_client = TestClient::new(routes(Store::memory()));
+
+        test_client
+            .post("/proxy-sync/v1")
+            .json(&json!({ "type": "String", "key": "config.flag", "value": { "on": true } }))
+            .send()
+            .await;
+
+        test_client
+            .post("/proxy-sync/v1/multi")
+            .json(&json!({ "type": "String", "data": { "a": 1, "b": 2 } }))
+            .send()
+            .await;
+
+        let response = test_client
+            .delete("/proxy-sync/v1")
+            .json(&json!({ "type": "String", "key": "a" }))
+            .send()
+            .await;
+        assert_eq!(response.status(), StatusCode::OK);
+
+        // Still readable until its ttl is over
+        test_client
+            .delete("/proxy-sync/v1")
+            .json(&json!({ "type": "String", "key": "b", "ttl": 60 }))
+            .send()
+            .await;
+
+        let response = test_client.get("/data/b").send().await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = response.json::<Value>().await;
+        assert!(body["expires_at"].is_i64());
+
+        let response = test_client.get("/data/a").send().await;
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+        // A set with a ttl expires on its own as well
+        test_client
+            .post("/proxy-sync/v1")
+            .json(&json!({ "type": "String", "key": "c", "value": 3, "ttl": 60 }))
+            .send()
+            .await;
+
+        let body = test_client
+            .get("/data/c")
+            .send()
+            .await
+            .json::<Value>()
+            .await;
+        assert!(body["expires_at"].is_i64());
+
+        let body = test_client
+            .get("/proxy-sync/v1/digest")
+            .send()
+            .await
+            .json::<Value>()
+            .await;
+        assert_eq!(
+            body["keys"],
+            json!({
+                "b": hash_value(&json!(2)),
+                "c": hash_value(&json!(3)),
+                "config.flag": hash_value(&json!({ "on": true })),
+            })
+        );
+
+        // Keys of several types written together keep their own type
+        test_client
+            .post("/proxy-sync/v1/multi")
+            .json(&json!(
+                { "type": "Multi", "data": { "d": 4, "e": "x" }, "types": { "d": "Counter" } }
+            ))
+            .send()
+            .await;
+
+        for (key, _type) in [("d", "Counter"), ("e", "Multi")] {
+            let body = test_client
+                .get(&format!("/data/{key}"))
+                .send()
+                .await
+                .json::<Value>()
+                .await;
+            assert_eq!(body["type"], json!(_type));
+        }
+    }
+
+    #[tokio::test]
+    async fn proxy_should_apply_each_v2_operation_once() {
+        let store = Store::memory();
+        let test_client = TestClient::new(routes(store.clone()));
+
+        let envelope = |id: &str, value: i64| {
+            json!({
+                "id": id,
+                "version": "v2",
+                "timestamp": 1685100000000i64,
+                "origin": "sync-hub",
+                "kind": "set",
+                "payload": { "type": "String", "key": "counter", "value": value }
+            })
+        };
+
+        for body in [envelope("1", 1), envelope("2", 2), envelope("1", 1)] {
+            let response = test_client.post("/proxy-sync/v2").json(&body).send().await;
+            assert_eq!(response.status(), StatusCode::OK);
+        }
+
+        assert_eq!(
+            store.get("counter").map(|entry| entry.value),
+            Some(json!(2))
+        );
+
+        // A refused operation is not taken as applied, so sending it again applies it
+        let incr = json!({
+            "id": "3",
+            "version": "v2",
+            "timestamp": 1685100000000i64,
+            "origin": "sync-hub",
+            "kind": "update",
+            "payload": { "type": "Counter", "key": "hits", "op": "incr", "by": 1 }
+        });
+        store
+            .set([(
+                "hits".to_string(),
+                Entry {
+                    _type: "List".into(),
+                    value: json!([]),
+                    expires_at: None,
+                    version: None,
+                },
+            )])
+            .await;
+        let response = test_client.post("/proxy-sync/v2").json(&incr).send().await;
+        assert_eq!(response.status(), StatusCode::CONFLICT);
+
+        store.delete("hits", None).await;
+        for _ in 0..2 {
+            let response = test_client.post("/proxy-sync/v2").json(&incr).send().await;
+            assert_eq!(response.status(), StatusCode::OK);
+        }
+        assert_eq!(store.get("hits").map(|entry| entry.value), Some(json!(1)));
+    }
+
+    #[tokio::test]
+    async fn proxy_should_apply_changes_in_place() {
+        let store = Store::memory();
+        let test_client = TestClient::new(routes(store.clone()));
+
+        for by in [2, 3] {
+            let response = test_client
+                .patch("/proxy-sync/v1")
+                .json(&json!({ "type": "Counter", "key": "hits", "op": "incr", "by": by }))
+                .send()
+                .await;
+            assert_eq!(response.status(), StatusCode::OK);
+        }
+
+        // A change that does not fit the current value is refused
+        let response = test_client
+            .patch("/proxy-sync/v1")
+            .json(&json!({ "type": "List", "key": "hits", "op": "append", "value": 1 }))
+            .send()
+            .await;
+        assert_eq!(response.status(), StatusCode::CONFLICT);
+
+        assert_eq!(store.get("hits").map(|entry| entry.value), Some(json!(5)));
+    }
+
+    #[tokio::test]
+    async fn proxy_should_drop_stale_writes() {
+        let store = Store::memory();
+        let test_client = TestClient::new(routes(store.clone()));
+
+        for (value, version) in [(2, 2), (1, 1)] {
+            test_client
+                .post("/proxy-sync/v1")
+                .json(&json!({ "type": "Number", "key": "n", "value": value, "version": version }))
+                .send()
+                .await;
+        }
+        assert_eq!(store.get("n").map(|entry| entry.value), Some(json!(2)));
+
+        // A delete older than the value is dropped as well
+        test_client
+            .delete("/proxy-sync/v1")
+            .json(&json!({ "type": "Number", "key": "n", "version": 1 }))
+            .send()
+            .await;
+        assert!(store.get("n").is_some());
+
+        // Writes without a version always apply
+        test_client
+            .post("/proxy-sync/v1")
+            .json(&json!({ "type": "Number", "key": "n", "value": 0 }))
+            .send()
+            .await;
+        assert_eq!(store.get("n").map(|entry| entry.value), Some(json!(0)));
+
+        // A change sent twice at the same version only counts once
+        for _ in 0..2 {
+            test_client
+                .patch("/proxy-sync/v1")
+                .json(&json!(
+                    { "type": "Counter", "key": "hits", "op": "incr", "by": 1, "version": 3 }
+                ))
+                .send()
+                .await;
+        }
+        assert_eq!(store.get("hits").map(|entry| entry.value), Some(json!(1)));
+
+        // A deleted key is not brought back by an older write
+        test_client
+            .delete("/proxy-sync/v1")
+            .json(&json!({ "type": "Counter", "key": "hits", "version": 5 }))
+            .send()
+            .await;
+        test_client
+            .post("/proxy-sync/v1")
+            .json(&json!({ "type": "Counter", "key": "hits", "value": 7, "version": 4 }))
+            .send()
+            .await;
+        assert!(store.get("hits").is_none());
+
+        store.sweep().await;
+        test_client
+            .post("/proxy-sync/v1")
+            .json(&json!({ "type": "Counter", "key": "hits", "value": 7, "version": 4 }))
+            .send()
+            .await;
+        assert!(store.get("hits").is_none());
+    }
+}
@@ -0,0 +1,299 @@
+use std::{
+    collections::{BTreeMap, VecDeque},
+    fs,
+    path::PathBuf,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex, RwLock,
+    },
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::data_op::DataOp;
+
+pub fn now() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_millis() as i64)
+        .unwrap_or_default()
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Entry {
+    #[serde(rename = "type")]
+    pub _type: String,
+    pub value: Value,
+    // Unix timestamp in milliseconds after which the key is gone
+    pub expires_at: Option<i64>,
+    // Version the hub gave to the last write of the key, if it sent one
+    #[serde(default)]
+    pub version: Option<i64>,
+}
+
+impl Entry {
+    fn is_live(&self, now: i64) -> bool {
+        self.expires_at.is_none_or(|expires_at| expires_at > now)
+    }
+
+    // What is left of a deleted key, so that an older write does not bring it back
+    fn tombstone(_type: String, version: Option<i64>) -> Self {
+        Self {
+            _type,
+            value: Value::Null,
+            expires_at: Some(now()),
+            version,
+        }
+    }
+}
+
+// Whether a write is not newer than what the proxy holds, deleted and expired keys
+// included. A write at the same version was already applied, which matters for
+// changes such as incr that would count twice. Writes without a version always apply,
+// the hub sends those when its own state has to win
+fn is_stale(held: Option<&Entry>, version: Option<i64>) -> bool {
+    matches!(
+        (held.and_then(|entry| entry.version), version),
+        (Some(held), Some(version)) if version <= held
+    )
+}
+
+// The keys held by the proxy, in memory and optionally mirrored to a json file
+// that is rewritten after every change and read back on startup
+
+// How many operation ids are remembered to drop the v2 operations sent twice
+const SEEN_OPERATIONS: usize = 1024;
+
+// How long the version of a deleted or expired key is kept by default,
+// the same as the hub keeps its own
+const TOMBSTONE_TTL: Duration = Duration::from_secs(86_400);
+
+// The json file the keys are mirrored to. It is written outside of the lock on the keys,
+// so every change is numbered and a change that comes late is not written over a newer one.
+// Writing it blocks, so it is done on the blocking threads of tokio
+struct StoreFile {
+    path: PathBuf,
+    changes: AtomicU64,
+    written: Mutex<u64>,
+}
+
+impl StoreFile {
+    fn save(&self, change: u64, entries: &BTreeMap<String, Entry>) {
+        let mut written = self.written.lock().unwrap();
+        if change <= *written {
+            return;
+        }
+
+        // Written next to the file first, so that a crash never leaves half of it
+        let temp = self.path.with_extension("tmp");
+        let saved = serde_json::to_vec(entries)
+            .map_err(std::io::Error::from)
+            .and_then(|json| fs::write(&temp, json))
+            .and_then(|_| fs::rename(&temp, &self.path));
+
+        match saved {
+            Ok(()) => *written = change,
+            Err(e) => eprintln!("Cannot write the store file {}: {e}", self.path.display()),
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct Store {
+    entries: Arc<RwLock<BTreeMap<String, Entry>>>,
+    file: Option<Arc<StoreFile>>,
+    seen: Arc<Mutex<VecDeque<String>>>,
+    tombstone_ttl: Duration,
+}
+
+impl Store {
+    pub fn memory() -> Self {
+        Self {
+            entries: Arc::default(),
+            file: None,
+            seen: Arc::default(),
+            tombstone_ttl: TOMBSTONE_TTL,
+        }
+    }
+
+    pub fn file(path: PathBuf) -> Self {
+        let entries = match fs::read_to_string(&path) {
+            Ok(json) => serde_json::from_str(&json)
+                .unwrap_or_else(|_| panic!("Cannot parse the store file {}", path.display())),
+            Err(_) => BTreeMap::new(),
+        };
+
+        Self {
+            entries: Arc::new(RwLock::new(entries)),
+            file: Some(Arc::new(StoreFile {
+                path,
+                changes: AtomicU64::new(0),
+                written: Mutex::new(0),
+            })),
+            ..Self::memory()
+        }
+    }
+
+    pub fn keep_tombstones_for(self, tombstone_ttl: Duration) -> Self {
+        Self {
+            tombstone_ttl,
+            ..self
+        }
+    }
+
+    // Whether an operation id was already applied
+    pub fn seen(&self, id: &str) -> bool {
+        self.seen.lock().unwrap().iter().any(|seen| seen == id)
+    }
+
+    // Remembers an operation id once it is applied
+    pub fn remember(&self, id: &str) {
+        let mut seen = self.seen.lock().unwrap();
+        if seen.iter().any(|seen| seen == id) {
+            return;
+        }
+
+        if seen.len() >= SEEN_OPERATIONS {
+            seen.pop_front();
+        }
+        seen.push_back(id.into());
+    }
+
+    pub fn get(&self, key: &str) -> Option<Entry> {
+        let now = now();
+        self.entries
+            .read()
+            .unwrap()
+            .get(key)
+            .filter(|entry| entry.is_live(now))
+            .cloned()
+    }
+
+    pub fn live(&self) -> BTreeMap<String, Entry> {
+        let now = now();
+        self.entries
+            .read()
+            .unwrap()
+            .iter()
+            .filter(|(_, entry)| entry.is_live(now))
+            .map(|(key, entry)| (key.clone(), entry.clone()))
+            .collect()
+    }
+
+    // Stale writes are left out
+    pub async fn set(&self, values: impl IntoIterator<Item = (String, Entry)>) {
+        self.write(|entries| {
+            for (key, entry) in values {
+                if !is_stale(entries.get(&key), entry.version) {
+                    entries.insert(key, entry);
+                }
+            }
+        })
+        .await;
+    }
+
+    // Versioned deletes leave a tombstone behind, the others forget the key
+    pub async fn delete(&self, key: &str, version: Option<i64>) {
+        self.write(|entries| {
+            if is_stale(entries.get(key), version) {
+                return;
+            }
+            let removed = entries.remove(key);
+            if version.is_some() {
+                let _type = removed.map(|entry| entry._type).unwrap_or_default();
+                entries.insert(key.into(), Entry::tombstone(_type, version));
+            }
+        })
+        .await;
+    }
+
+    // Changes the value of a key in place, keeping its expiry
+    pub async fn update(
+        &self,
+        key: &str,
+        _type: &str,
+        op: &DataOp,
+        version: Option<i64>,
+    ) -> Result<(), String> {
+        let now = now();
+        self.write(|entries| {
+            if is_stale(entries.get(key), version) {
+                return Ok(());
+            }
+            let current = entries.get(key).filter(|entry| entry.is_live(now));
+            let expires_at = current.and_then(|entry| entry.expires_at);
+
+            if let Some(value) = op.apply(current.map(|entry| &entry.value))? {
+                let entry = Entry {
+                    _type: _type.into(),
+                    value,
+                    expires_at,
+                    version,
+                };
+                entries.insert(key.into(), entry);
+            }
+            Ok(())
+        })
+        .await
+    }
+
+    // Keeps the key readable for `ttl` more seconds
+    pub async fn expire(&self, key: &str, ttl: i64, version: Option<i64>) {
+        let now = now();
+        let expires_at = now.saturating_add(ttl.saturating_mul(1000));
+        self.write(|entries| {
+            if let Some(entry) = entries.get_mut(key).filter(|entry| entry.is_live(now)) {
+                if !is_stale(Some(entry), version) {
+                    entry.expires_at = Some(expires_at);
+                    entry.version = version;
+                }
+            }
+        })
+        .await;
+    }
+
+    // Forgets the keys whose ttl is over. The version of a key is kept
+    // for a while longer, so that older writes still in flight are dropped
+    pub async fn sweep(&self) {
+        let now = now();
+        let forget_before = now - self.tombstone_ttl.as_millis() as i64;
+        let kept = |entry: &Entry| {
+            entry.is_live(now)
+                || (entry.version.is_some()
+                    && entry
+                        .expires_at
+                        .is_some_and(|expires_at| expires_at > forget_before))
+        };
+
+        // Most sweeps find nothing, and then the file is left alone
+        if self.entries.read().unwrap().values().all(kept) {
+            return;
+        }
+        self.write(|entries| entries.retain(|_, entry| kept(entry)))
+            .await;
+    }
+
+    // The change is only acknowledged once the file has it
+    async fn write<R>(&self, change: impl FnOnce(&mut BTreeMap<String, Entry>) -> R) -> R {
+        let (result, saved) = {
+            let mut entries = self.entries.write().unwrap();
+            let result = change(&mut entries);
+            let saved = self.file.clone().map(|file| {
+                let change = file.changes.fetch_add(1, Ordering::Relaxed) + 1;
+                (file, change, entries.clone())
+            });
+            (result, saved)
+        };
+
+        if let Some((file, change, entries)) = saved {
+            let saving = tokio::task::spawn_blocking(move || file.save(change, &entries));
+            if let Err(e) = saving.await {
+                eprintln!("Cannot write the store file: {e}");
+            }
+        }
+
+        result
+    }
+}
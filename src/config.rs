@@ -0,0 +1,283 @@
+use std::{str::FromStr, time::Duration};
+
+use dotenvy::var;
+use rand::Rng;
+use reqwest::StatusCode;
+
+// Reads an optional setting from the environment, falling back to a default
+// when it is missing. A present but malformed value is a configuration mistake,
+// so it stops the server the same way a bad PORT does.
+fn env_or<T: FromStr>(name: &str, default: T) -> T {
+    match var(name) {
+        Ok(value) => value
+            .parse()
+            .unwrap_or_else(|_| panic!("Cannot parse {name} in .env")),
+        Err(_) => default,
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    pub max_attempts: u32,
+    pub base_delay: Duration,
+    pub max_delay: Duration,
+    pub jitter: Duration,
+    pub retryable_statuses: Vec<u16>,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            base_delay: Duration::from_millis(100),
+            max_delay: Duration::from_millis(2000),
+            jitter: Duration::from_millis(50),
+            retryable_statuses: vec![408, 429, 500, 502, 503, 504],
+        }
+    }
+}
+
+impl RetryPolicy {
+    pub fn from_env() -> Self {
+        let default = Self::default();
+
+        let retryable_statuses = match var("SYNC_RETRY_STATUSES") {
+            Ok(statuses) => statuses
+                .split(',')
+                .map(|status| {
+                    status
+                        .trim()
+                        .parse()
+                        .expect("Cannot parse SYNC_RETRY_STATUSES in .env")
+                })
+                .collect(),
+            Err(_) => default.retryable_statuses,
+        };
+
+        Self {
+            max_attempts: env_or("SYNC_RETRY_MAX_ATTEMPTS", default.max_attempts).max(1),
+            base_delay: Duration::from_millis(env_or(
+                "SYNC_RETRY_BASE_DELAY_MS",
+                default.base_delay.as_millis() as u64,
+            )),
+            max_delay: Duration::from_millis(env_or(
+                "SYNC_RETRY_MAX_DELAY_MS",
+                default.max_delay.as_millis() as u64,
+            )),
+            jitter: Duration::from_millis(env_or(
+                "SYNC_RETRY_JITTER_MS",
+                default.jitter.as_millis() as u64,
+            )),
+            retryable_statuses,
+        }
+    }
+
+    pub fn is_retryable(&self, status: StatusCode) -> bool {
+        self.retryable_statuses.contains(&status.as_u16())
+    }
+
+    // The delay to wait after the given (1-based) failed attempt:
+    // base * 2^(attempt - 1), capped at max_delay, plus a random jitter
+    pub fn backoff(&self, attempt: u32) -> Duration {
+        let exponential = self
+            .base_delay
+            .saturating_mul(2u32.saturating_pow(attempt.saturating_sub(1)))
+            .min(self.max_delay);
+
+        let jitter = match self.jitter.as_millis() as u64 {
+            0 => 0,
+            jitter => rand::thread_rng().gen_range(0..=jitter),
+        };
+
+        exponential + Duration::from_millis(jitter)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct SyncConfig {
+    pub retry: RetryPolicy,
+    // How many keys are sent per request when bootstrapping a new proxy
+    pub bootstrap_batch_size: usize,
+    pub breaker: BreakerConfig,
+    // Name of this hub, sent to the proxies along with every v2 operation
+    pub origin: String,
+    // How long a proxy gets to answer one request before it counts as a failed attempt
+    pub request_timeout: Duration,
+}
+
+impl SyncConfig {
+    pub fn from_env() -> Self {
+        Self {
+            retry: RetryPolicy::from_env(),
+            bootstrap_batch_size: env_or("BOOTSTRAP_BATCH_SIZE", 100).max(1),
+            breaker: BreakerConfig::from_env(),
+            origin: env_or("HUB_ORIGIN", "sync-hub".to_string()),
+            request_timeout: Duration::from_millis(env_or("SYNC_REQUEST_TIMEOUT_MS", 10_000)),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct BreakerConfig {
+    // How many failed deliveries in a row open the breaker of a proxy
+    pub failure_threshold: u32,
+    // How long an open breaker waits before letting a request through again
+    pub cool_down: Duration,
+}
+
+impl BreakerConfig {
+    pub fn from_env() -> Self {
+        Self {
+            failure_threshold: env_or("BREAKER_FAILURE_THRESHOLD", 5).max(1),
+            cool_down: Duration::from_millis(env_or("BREAKER_COOL_DOWN_MS", 30_000)),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct OutboxConfig {
+    // How often the dispatcher looks for undelivered operations
+    pub dispatch_interval: Duration,
+    // How long a claimed operation is hidden from other dispatchers
+    pub lease: Duration,
+    // How long to wait before delivering a failed operation again
+    pub retry_delay: Duration,
+    // How many attempts a delivery gets before it is moved to the dead letters
+    pub max_attempts: u32,
+}
+
+impl OutboxConfig {
+    pub fn from_env() -> Self {
+        Self {
+            dispatch_interval: Duration::from_millis(env_or("OUTBOX_DISPATCH_INTERVAL_MS", 1000)),
+            lease: Duration::from_millis(env_or("OUTBOX_LEASE_MS", 30_000)),
+            retry_delay: Duration::from_millis(env_or("OUTBOX_RETRY_DELAY_MS", 10_000)),
+            max_attempts: env_or("OUTBOX_MAX_ATTEMPTS", 10),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ReconcileConfig {
+    // How often every proxy is compared against the hub
+    pub interval: Duration,
+    // Whether the keys a proxy holds that the hub does not know are deleted
+    pub delete_unknown: bool,
+}
+
+impl ReconcileConfig {
+    pub fn from_env() -> Self {
+        Self {
+            interval: Duration::from_millis(env_or("RECONCILE_INTERVAL_MS", 300_000)),
+            delete_unknown: env_or("RECONCILE_DELETE_UNKNOWN", false),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct DataConfig {
+    // How long the version of a deleted key is kept
+    pub tombstone_ttl: Duration,
+}
+
+impl DataConfig {
+    pub fn from_env() -> Self {
+        Self {
+            tombstone_ttl: Duration::from_secs(env_or("TOMBSTONE_TTL_SECS", 86_400)),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ExpiryConfig {
+    // How often the keys that are over their ttl are deleted from the proxies
+    pub interval: Duration,
+}
+
+impl ExpiryConfig {
+    pub fn from_env() -> Self {
+        Self {
+            interval: Duration::from_millis(env_or("EXPIRY_INTERVAL_MS", 1000)),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct HealthConfig {
+    // How often every proxy is probed
+    pub interval: Duration,
+    // How long a probe may take before the proxy counts as unhealthy
+    pub timeout: Duration,
+    // How long past health checks are kept
+    pub history_ttl: Duration,
+}
+
+impl HealthConfig {
+    pub fn from_env() -> Self {
+        Self {
+            interval: Duration::from_millis(env_or("HEALTH_CHECK_INTERVAL_MS", 10_000)),
+            timeout: Duration::from_millis(env_or("HEALTH_CHECK_TIMEOUT_MS", 2_000)),
+            history_ttl: Duration::from_secs(env_or("HEALTH_HISTORY_TTL_SECS", 86_400)),
+        }
+    }
+}
+
+// Where the proxy registry is kept
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StoreBackend {
+    Mongo,
+    Memory,
+    Sqlite,
+}
+
+impl FromStr for StoreBackend {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.to_lowercase().as_str() {
+            "mongo" | "mongodb" => Ok(Self::Mongo),
+            "memory" => Ok(Self::Memory),
+            "sqlite" => Ok(Self::Sqlite),
+            _ => Err(format!("Unknown proxy store {value}")),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct StoreConfig {
+    pub backend: StoreBackend,
+    // Database file of the sqlite backend
+    pub sqlite_path: String,
+}
+
+impl StoreConfig {
+    pub fn from_env() -> Self {
+        Self {
+            backend: env_or("PROXY_STORE", StoreBackend::Mongo),
+            sqlite_path: env_or("PROXY_STORE_SQLITE_PATH", "proxies.db".to_string()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::RetryPolicy;
+
+    #[test]
+    fn backoff_should_grow_exponentially_up_to_max_delay() {
+        let policy = RetryPolicy {
+            max_attempts: 5,
+            base_delay: Duration::from_millis(100),
+            max_delay: Duration::from_millis(300),
+            jitter: Duration::ZERO,
+            retryable_statuses: vec![],
+        };
+
+        assert_eq!(policy.backoff(1), Duration::from_millis(100));
+        assert_eq!(policy.backoff(2), Duration::from_millis(200));
+        assert_eq!(policy.backoff(3), Duration::from_millis(300));
+        assert_eq!(policy.backoff(10), Duration::from_millis(300));
+    }
+}
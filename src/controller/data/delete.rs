@@ -1,8 +1,8 @@
 use axum::{extract::State, routing::delete, Router};
-use futures_util::{future::join_all, TryStreamExt};
 
 use crate::{
-    models::proxy::Proxy, request::data::delete::DeleteDataRequest, web::Web, Services, WebResult,
+    models::operation::SyncOperation, request::data::delete::DeleteDataRequest, web::Web, Services,
+    WebResult,
 };
 
 #[utoipa::path(
@@ -17,6 +17,7 @@ use crate::{
                 "type": "String",
                 "key": "test_str",
                 "ttl": "30", // seconds
+                "if_version": 4,
             }
         )
     ),
@@ -24,87 +25,79 @@ use crate::{
         (
             status = 200,
             description = "Delete data from all proxies success",
-            body = SuccessResponse,
+            body = DeliveryReport,
             example = json!(
                 {
-                    "code": "200",
-                    "message": "Delete data from all proxies success",
-                    "data": null,
+                    "code": "200 OK",
+                    "message": "Delete data from all proxies successfully",
+                    "data": {
+                        "delivered": 1,
+                        "failed": 0,
+                        "queued": 0,
+                        "targets": ["http://proxy1:1000"],
+                        "deliveries": [
+                            { "url": "http://proxy1:1000", "status": 200, "latency_ms": 12, "attempts": 1, "error": null, "queued": false }
+                        ],
+                        "version": 5
+                    },
                     "error": "",
                 }
             )
+        ),
+        (
+            status = 207,
+            description = "Delete data from some proxies only",
+            body = DeliveryReport
+        ),
+        (
+            status = 409,
+            description = "The key is not at the version expected by the request",
+            body = ErrorResponse
+        ),
+        (
+            status = 502,
+            description = "Delete data from all proxies failed",
+            body = DeliveryReport
         )
     )
 )]
 pub fn delete_data() -> Router<Services> {
     async fn delete_data_handler(
-        State(Services {
-            client,
-            proxy_service,
-        }): State<Services>,
+        State(Services { sync_service, .. }): State<Services>,
         req: DeleteDataRequest,
     ) -> WebResult {
-        // Get the database cursor, pointing to the list of paths in MongoDB
-        let mut proxies = proxy_service.get_proxies().await?;
-
-        // Create a tasks vector
-        let mut tasks = vec![];
-
-        // For each item in the collection
-        while let Some(Proxy { url }) = proxies.try_next().await? {
-            // Append a task into the tasks list
-            tasks.push(
-                client
-                    .delete(format!("{url}/proxy-sync/v1"))
-                    .json(&req)
-                    .send(),
-            )
-        }
-
-        // Run all tasks in parallel
-        join_all(tasks).await;
-        Ok(Web::ok("Delete data from all proxies successfully", ()))
+        // Store the request in the outbox, then send it to all proxies in parallel,
+        // keeping the outcome of each one
+        let report = sync_service.sync(SyncOperation::Delete(req)).await?;
+
+        Ok(Web::report(
+            "Delete data from all proxies successfully",
+            report,
+        ))
     }
     Router::new().route("/", delete(delete_data_handler))
 }
 
 #[cfg(test)]
 mod tests {
-    use axum_test_helper::TestClient;
-    use mongodb::Collection;
-    use reqwest::{Client, StatusCode};
+    use reqwest::StatusCode;
     use serde_json::json;
 
-    use crate::{models::proxy::Proxy, mongo::connect_mongo, service::proxy::ProxyService, Services, controller::routes};
+    use crate::test_support::{add_proxy, fake_proxy::FakeProxy, test_hub};
 
     #[tokio::test]
     async fn delete_data_should_success() {
-        let proxy_collection: Collection<Proxy> = connect_mongo().await.collection("Proxy");
-
-        let proxy_service = ProxyService::init(&proxy_collection);
+        let fake_proxy = FakeProxy::spawn().await;
 
-        let service = Services {
-            client: Client::new(),
-            proxy_service,
-        };
+        let test_client = test_hub().await;
 
-        let router = routes(service);
-
-        let test_client = TestClient::new(router);
-
-        test_client
-            .post("/proxy/create")
-            .json(&json!(
-                { "url": "http://localhost:3000" }
-            ))
-            .send()
-            .await;
+        add_proxy(&test_client, &fake_proxy.url).await;
 
         test_client.post("/sync").json(
             &json!(
                 { 
-                    "type": "String",
-                    "key": "test_str",
+                    "type": "Json",
+                    "key": "test_delete",
                     "value": {
                         "hello": "world"
                     },
@@ -116,7 +109,7 @@ mod tests {
             &json!(
                 { 
                     "type": "String",
-                    "key": "test_str",
+                    "key": "test_delete",
                 }
             )
         ).send().await;
@@ -126,7 +119,7 @@ mod tests {
         test_client
             .delete("/proxy/delete")
             .json(&json!(
-                { "url": "http://localhost:3000" }
+                { "url": fake_proxy.url }
             ))
             .send()
             .await;
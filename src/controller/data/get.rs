@@ -0,0 +1,194 @@
+use axum::{
+    extract::{Path, State},
+    routing::get,
+    Router,
+};
+
+use crate::{request::data::keys::KeysFilter, web::Web, Services, WebResult};
+
+#[utoipa::path(
+    get,
+    tag = "Sync",
+    path = "/sync/data/{key}",
+    params(
+        ("key" = String, Path, description = "The key to look up")
+    ),
+    responses(
+        (
+            status = 200,
+            description = "Current value of the key",
+            body = Data,
+            example = json!(
+                {
+                    "code": "200 OK",
+                    "message": "Get data successfully",
+                    "data": {
+                        "key": "test_str",
+                        "type": "String",
+                        "value": "hello",
+                        "ttl": 3600,
+                        "expires_at": 1685103600000i64,
+                        "remaining_ttl": 1800,
+                        "version": 4,
+                        "updated_at": 1685100000000i64,
+                        "targets": null
+                    },
+                    "error": ""
+                }
+            )
+        ),
+        (
+            status = 404,
+            description = "Data not found",
+            body = ErrorResponse,
+            example = json!(
+                {
+                    "code": "404 Not Found",
+                    "message": "Data not found",
+                    "data": null,
+                    "error": "The key provided cannot be found in the database",
+                }
+            )
+        )
+    )
+)]
+pub fn get_data() -> Router<Services> {
+    async fn get_data_handler(
+        State(Services { data_service, .. }): State<Services>,
+        Path(key): Path<String>,
+    ) -> WebResult {
+        let data = data_service.get_data(&key).await?;
+        Ok(Web::ok("Get data successfully", data))
+    }
+    // Kept apart from `/sync/keys` and the other static routes,
+    // which would otherwise shadow keys of the same name
+    Router::new().route("/data/:key", get(get_data_handler))
+}
+
+#[utoipa::path(
+    get,
+    tag = "Sync",
+    path = "/sync/keys",
+    params(KeysFilter),
+    responses(
+        (
+            status = 200,
+            description = "One page of the stored keys, in key order",
+            body = DataPage,
+            example = json!(
+                {
+                    "code": "200 OK",
+                    "message": "Get keys successfully",
+                    "data": {
+                        "items": [
+                            {
+                                "key": "test_str",
+                                "type": "String",
+                                "value": "hello",
+                                "ttl": null,
+                                "expires_at": null,
+                                "version": 1,
+                                "updated_at": 1685100000000i64,
+                        "targets": null
+                            }
+                        ],
+                        "page": 1,
+                        "limit": 20,
+                        "total": 1
+                    },
+                    "error": ""
+                }
+            )
+        ),
+        (
+            status = 400,
+            description = "Invalid input",
+            body = ErrorResponse,
+            example = json!(
+                {
+                    "code": "400 Bad Request",
+                    "message": "Invalid input",
+                    "data": null,
+                    "error": "Limit must be between 1 and 100",
+                }
+            )
+        )
+    )
+)]
+pub fn get_keys() -> Router<Services> {
+    async fn get_keys_handler(
+        State(Services { data_service, .. }): State<Services>,
+        filter: KeysFilter,
+    ) -> WebResult {
+        let keys = data_service.get_keys(&filter).await?;
+        Ok(Web::ok("Get keys successfully", keys))
+    }
+    Router::new().route("/keys", get(get_keys_handler))
+}
+
+#[cfg(test)]
+mod tests {
+    use reqwest::StatusCode;
+    use serde_json::json;
+
+    use crate::{test_support::test_hub, web::Web};
+
+    #[tokio::test]
+    async fn get_data_should_fail_test() {
+        let test_client = test_hub().await;
+
+        let response = test_client.get("/sync/data/missing_key").send().await;
+
+        assert_eq!(response.status(), StatusCode::NOT_FOUND)
+    }
+
+    #[tokio::test]
+    async fn get_data_should_return_remaining_ttl_test() {
+        let test_client = test_hub().await;
+
+        test_client
+            .post("/sync")
+            .json(&json!(
+                { "type": "String", "key": "test_ttl", "value": "hello", "ttl": 60 }
+            ))
+            .send()
+            .await;
+
+        let response = test_client.get("/sync/data/test_ttl").send().await;
+
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let Web { data, .. } = response.json().await;
+        assert_eq!(data["ttl"], 60);
+        assert!((1..=60).contains(&data["remaining_ttl"].as_i64().unwrap()));
+    }
+
+    #[tokio::test]
+    async fn get_keys_should_success_test() {
+        let test_client = test_hub().await;
+
+        let response = test_client
+            .get("/sync/keys?prefix=test_&page=1&limit=10")
+            .send()
+            .await;
+
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let Web { message, data, .. } = response.json().await;
+        assert_eq!(message, "Get keys successfully");
+        assert!(data["items"].is_array());
+        assert_eq!(data["limit"], 10);
+    }
+
+    #[tokio::test]
+    async fn get_keys_with_invalid_page_should_fail_test() {
+        let test_client = test_hub().await;
+
+        let response = test_client
+            .get(&format!("/sync/keys?page={}", u64::MAX))
+            .send()
+            .await;
+
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
+}
@@ -1,7 +1,44 @@
-use axum::{extract::State, routing::get, Router};
-use futures_util::{future::try_join_all, TryStreamExt};
+use axum::{
+    extract::State,
+    response::Response,
+    routing::{get, post},
+    Router,
+};
 
-use crate::{models::proxy::Proxy, web::Web, Services, WebResult};
+use crate::{
+    models::health::{HealthCheck, HealthStatus},
+    web::Web,
+    Services, WebResult,
+};
+
+// Proxies that were not checked yet are not counted as unhealthy
+fn health_report(checks: Vec<HealthCheck>) -> Response {
+    let unhealthy = checks
+        .iter()
+        .filter(|check| check.status == HealthStatus::Unhealthy)
+        .map(|check| check.url.clone())
+        .collect::<Vec<_>>();
+
+    match unhealthy.len() {
+        0 => Web::ok("All proxies functional", checks),
+        n if n == checks.len() => Web::bad_gateway(
+            "Request to proxies error",
+            format!(
+                "No proxy is functional. Unhealthy proxies: {}.",
+                unhealthy.join(", ")
+            ),
+            checks,
+        ),
+        n => Web::multi_status(
+            "Some proxies are not functional",
+            format!(
+                "{n} proxies are not functional. Unhealthy proxies: {}.",
+                unhealthy.join(", ")
+            ),
+            checks,
+        ),
+    }
+}
 
 #[utoipa::path(
     get,
@@ -10,89 +47,143 @@ use crate::{models::proxy::Proxy, web::Web, Services, WebResult};
     responses(
         (
             status = 200,
-            description = "All proxies functional",
-            body = SuccessResponse,
+            description = "No proxy failed its latest health check",
+            body = [HealthCheck],
             example = json!(
                 {
                     "code": "200 OK",
                     "message": "All proxies functional",
-                    "data": null,
+                    "data": [
+                        {
+                            "url": "http://proxy1:1000",
+                            "status": "healthy",
+                            "http_status": 200,
+                            "latency_ms": 3,
+                            "error": null,
+                            "checked_at": 1685100000000i64
+                        }
+                    ],
                     "error": "",
                 }
             )
         ),
         (
-            status = 500,
-            description = "Request to proxies error",
-            body = SuccessResponse,
+            status = 207,
+            description = "Some proxies are not functional",
+            body = [HealthCheck],
             example = json!(
                 {
-                    "code": "500 Internal Server Error",
+                    "code": "207 Multi-Status",
+                    "message": "Some proxies are not functional",
+                    "data": [
+                        {
+                            "url": "http://proxy1:1000",
+                            "status": "healthy",
+                            "http_status": 200,
+                            "latency_ms": 3,
+                            "error": null,
+                            "checked_at": 1685100000000i64
+                        },
+                        {
+                            "url": "http://proxy2:2000",
+                            "status": "unhealthy",
+                            "http_status": null,
+                            "latency_ms": 2001,
+                            "error": "operation timed out",
+                            "checked_at": 1685100000000i64
+                        }
+                    ],
+                    "error": "1 proxies are not functional. Unhealthy proxies: http://proxy2:2000.",
+                }
+            )
+        ),
+        (
+            status = 502,
+            description = "No proxy is functional",
+            body = [HealthCheck],
+            example = json!(
+                {
+                    "code": "502 Bad Gateway",
                     "message": "Request to proxies error",
-                    "data": null,
-                    "error": "A connection to one of the proxies could not be made",
+                    "data": [
+                        {
+                            "url": "http://proxy2:2000",
+                            "status": "unhealthy",
+                            "http_status": 503,
+                            "latency_ms": 4,
+                            "error": "Proxy responded with 503 Service Unavailable",
+                            "checked_at": 1685100000000i64
+                        }
+                    ],
+                    "error": "No proxy is functional. Unhealthy proxies: http://proxy2:2000.",
                 }
             )
         )
     )
 )]
 pub fn health() -> Router<Services> {
-    async fn health_handler(
-        State(Services {
-            client,
-            proxy_service,
-        }): State<Services>,
-    ) -> WebResult {
-        let mut proxies = proxy_service.get_proxies().await?;
-
-        let mut tasks = vec![];
-
-        while let Some(Proxy { url }) = proxies.try_next().await? {
-            tasks.push(client.get(format!("{url}/health")).send())
-        }
-
-        // The difference between join_all and try_join_all is that
-        // join_all launches all tasks in parallel and doesn't care about
-        // the task's output
-        // try_join_all does the same, but if one of the tasks returns an error,
-        // the error will be returned, regardless of the rest
-        try_join_all(tasks).await?;
-        Ok(Web::ok("All proxies functional", ()))
+    async fn health_handler(State(Services { health_service, .. }): State<Services>) -> WebResult {
+        // The latest checks of the monitor, reading them probes nothing
+        let checks = health_service.latest().await?;
+        Ok(health_report(checks))
     }
     Router::new().route("/health", get(health_handler))
 }
 
+#[utoipa::path(
+    post,
+    tag = "Sync",
+    path = "/sync/health/check",
+    responses(
+        (
+            status = 200,
+            description = "All proxies functional",
+            body = [HealthCheck]
+        ),
+        (
+            status = 207,
+            description = "Some proxies are not functional",
+            body = [HealthCheck]
+        ),
+        (
+            status = 502,
+            description = "No proxy is functional",
+            body = [HealthCheck]
+        )
+    )
+)]
+pub fn check_health() -> Router<Services> {
+    async fn check_health_handler(
+        State(Services { health_service, .. }): State<Services>,
+    ) -> WebResult {
+        // Probe right away instead of waiting for the monitor,
+        // the outcome is stored on the proxies and in their history as well
+        let checks = health_service.check_all().await?;
+        Ok(health_report(checks))
+    }
+    Router::new().route("/health/check", post(check_health_handler))
+}
+
 #[cfg(test)]
 mod tests {
-    use axum_test_helper::TestClient;
-    use mongodb::Collection;
-    use reqwest::{Client, StatusCode};
+    use reqwest::StatusCode;
     use serde_json::json;
 
     use crate::{
-        controller::routes, models::proxy::Proxy, mongo::connect_mongo,
-        service::proxy::ProxyService, web::Web, Services,
+        test_support::{fake_proxy::FakeProxy, test_hub},
+        web::Web,
     };
 
     #[tokio::test]
     async fn get_health_should_success() {
-        let proxy_collection: Collection<Proxy> = connect_mongo().await.collection("Proxy");
-
-        let proxy_service = ProxyService::init(&proxy_collection);
-
-        let service = Services {
-            client: Client::new(),
-            proxy_service,
-        };
-
-        let router = routes(service);
+        let fake_proxy = FakeProxy::spawn().await;
 
-        let test_client = TestClient::new(router);
+        let test_client = test_hub().await;
 
         test_client
             .post("/proxy/create")
             .json(&json!(
-                { "url": "http://localhost:3000" }
+                { "url": fake_proxy.url }
             ))
             .send()
             .await;
@@ -110,15 +201,59 @@ mod tests {
 
         assert_eq!(code, StatusCode::OK.to_string());
         assert_eq!(message, "All proxies functional");
-        assert_eq!(data, json!(null));
+        assert!(data.is_array());
         assert_eq!(error, "");
 
         test_client
             .delete("/proxy/delete")
             .json(&json!(
-                { "url": "http://localhost:3000" }
+                { "url": fake_proxy.url }
+            ))
+            .send()
+            .await;
+    }
+
+    #[tokio::test]
+    async fn get_health_should_not_probe_proxies() {
+        let fake_proxy = FakeProxy::spawn().await;
+
+        let test_client = test_hub().await;
+
+        test_client
+            .post("/proxy/create")
+            .json(&json!(
+                { "url": fake_proxy.url }
             ))
             .send()
             .await;
+        fake_proxy.go_down();
+
+        let probes = || {
+            fake_proxy
+                .calls()
+                .iter()
+                .filter(|call| call.path == "/health")
+                .count()
+        };
+        let before = probes();
+
+        // Not checked by the monitor yet
+        let response = test_client.get("/sync/health").send().await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let Web { data, .. } = response.json().await;
+        assert_eq!(data[0]["status"], json!("unknown"));
+        assert_eq!(probes(), before);
+
+        let response = test_client.post("/sync/health/check").send().await;
+        assert_eq!(response.status(), StatusCode::BAD_GATEWAY);
+        assert_eq!(probes(), before + 1);
+
+        // The outcome of the check is what gets reported from now on
+        let response = test_client.get("/sync/health").send().await;
+        assert_eq!(response.status(), StatusCode::BAD_GATEWAY);
+        let Web { data, .. } = response.json().await;
+        assert_eq!(data[0]["status"], json!("unhealthy"));
+        assert_eq!(data[0]["http_status"], json!(503));
+        assert_eq!(probes(), before + 1);
     }
 }
@@ -4,21 +4,32 @@ use crate::Services;
 
 use self::{
     delete::delete_data,
-    health::health,
+    get::{get_data, get_keys},
+    health::{check_health, health},
+    reconcile::reconcile,
     set::{set_data, set_multi_data},
+    update::update_data,
 };
 
 pub mod delete;
+pub mod get;
 pub mod health;
+pub mod reconcile;
 pub mod set;
+pub mod update;
 
 pub fn data_routes() -> Router<Services> {
     Router::new().nest(
         "/sync",
         Router::new()
             .merge(health())
+            .merge(check_health())
+            .merge(get_keys())
+            .merge(reconcile())
+            .merge(get_data())
             .merge(set_data())
             .merge(set_multi_data())
-            .merge(delete_data()),
+            .merge(delete_data())
+            .merge(update_data()),
     )
 }
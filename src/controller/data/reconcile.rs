@@ -0,0 +1,141 @@
+use axum::{extract::State, routing::post, Router};
+
+use crate::{request::data::reconcile::ReconcileFilter, web::Web, Services, WebResult};
+
+#[utoipa::path(
+    post,
+    tag = "Sync",
+    path = "/sync/reconcile",
+    params(ReconcileFilter),
+    responses(
+        (
+            status = 200,
+            description = "Drift of every reconciled proxy",
+            body = [DriftReport],
+            example = json!(
+                {
+                    "code": "200 OK",
+                    "message": "Reconcile proxies successfully",
+                    "data": [
+                        {
+                            "url": "http://proxy1:1000",
+                            "missing": ["test_str"],
+                            "stale": ["test_int"],
+                            "extra": [],
+                            "skipped": [],
+                            "repaired": 2,
+                            "failed": 0,
+                            "error": null
+                        },
+                        {
+                            "url": "http://proxy2:2000",
+                            "missing": [],
+                            "stale": [],
+                            "extra": [],
+                            "skipped": [],
+                            "repaired": 0,
+                            "failed": 0,
+                            "error": "Proxy responded with 404 Not Found"
+                        }
+                    ],
+                    "error": ""
+                }
+            )
+        ),
+        (
+            status = 404,
+            description = "Proxy not found",
+            body = ErrorResponse,
+            example = json!(
+                {
+                    "code": "404 Not Found",
+                    "message": "Proxy not found",
+                    "data": null,
+                    "error": "The url provided cannot be found in the database",
+                }
+            )
+        )
+    )
+)]
+pub fn reconcile() -> Router<Services> {
+    async fn reconcile_handler(
+        State(Services { sync_service, .. }): State<Services>,
+        ReconcileFilter {
+            url,
+            dry_run,
+            delete_unknown,
+        }: ReconcileFilter,
+    ) -> WebResult {
+        let reports = sync_service
+            .reconcile_all(url.as_deref(), dry_run, delete_unknown)
+            .await?;
+        Ok(Web::ok("Reconcile proxies successfully", reports))
+    }
+    Router::new().route("/reconcile", post(reconcile_handler))
+}
+
+#[cfg(test)]
+mod tests {
+    use reqwest::{Client, StatusCode};
+    use serde_json::json;
+
+    use crate::{
+        test_support::{add_proxy, fake_proxy::FakeProxy, test_hub},
+        web::Web,
+    };
+
+    #[tokio::test]
+    async fn reconcile_should_fail_test() {
+        let test_client = test_hub().await;
+
+        let response = test_client
+            .post("/sync/reconcile?url=http://invalid&dry_run=true")
+            .send()
+            .await;
+
+        assert_eq!(response.status(), StatusCode::NOT_FOUND)
+    }
+
+    #[tokio::test]
+    async fn reconcile_should_keep_unknown_keys_test() {
+        let fake_proxy = FakeProxy::spawn().await;
+
+        let test_client = test_hub().await;
+
+        add_proxy(&test_client, &fake_proxy.url).await;
+
+        // Written to the proxy without going through the hub
+        Client::new()
+            .post(format!("{}/proxy-sync/v1", fake_proxy.url))
+            .json(&json!(
+                { "type": "String", "key": "test_unknown", "value": "hello" }
+            ))
+            .send()
+            .await
+            .unwrap();
+
+        let reconcile = format!("/sync/reconcile?url={}", fake_proxy.url);
+        let response = test_client.post(&reconcile).send().await;
+
+        let Web { data, .. } = response.json().await;
+        assert!(data[0]["extra"]
+            .as_array()
+            .unwrap()
+            .contains(&json!("test_unknown")));
+        assert_eq!(fake_proxy.value("test_unknown"), Some(json!("hello")));
+
+        test_client
+            .post(&format!("{reconcile}&delete_unknown=true"))
+            .send()
+            .await;
+        assert_eq!(fake_proxy.value("test_unknown"), None);
+
+        test_client
+            .delete("/proxy/delete")
+            .json(&json!(
+                { "url": fake_proxy.url }
+            ))
+            .send()
+            .await;
+    }
+}
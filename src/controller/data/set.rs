@@ -1,15 +1,12 @@
 use axum::{extract::State, routing::post, Router};
-use futures_util::{future::join_all, TryStreamExt};
-
 
 use crate::{
-    models::proxy::Proxy,
+    models::operation::SyncOperation,
     request::data::set::{SetDataRequest, SetMultiDataRequest},
     web::Web,
     Services, WebResult,
 };
 
-
 #[utoipa::path(
     post,
     tag = "Sync",
@@ -19,11 +16,17 @@ use crate::{
         description = "Set data request model",
         example = json!(
             { 
-                "type": "String",
+                "type": "Json",
                 "key": "test_str",
                 "value": {
                     "hello": "world"
                 },
+                "ttl": 3600,
+                "if_version": 3,
+                "targets": {
+                    "include": { "regions": ["eu-west-1"], "providers": ["aws"] },
+                    "exclude": { "tags": ["canary"] }
+                }
             }
         )
     ),
@@ -31,46 +34,93 @@ use crate::{
         (
             status = 200,
             description = "Set data to all proxies success",
-            body = SuccessResponse,
+            body = DeliveryReport,
             example = json!(
                 {
                     "code": "200 OK",
                     "message": "Set data to all proxies successfully",
-                    "data": null,
+                    "data": {
+                        "delivered": 1,
+                        "failed": 0,
+                        "queued": 0,
+                        "targets": ["http://proxy1:1000"],
+                        "deliveries": [
+                            { "url": "http://proxy1:1000", "status": 200, "latency_ms": 12, "attempts": 1, "error": null, "queued": false }
+                        ],
+                        "version": 4
+                    },
                     "error": "",
                 }
             )
+        ),
+        (
+            status = 207,
+            description = "Set data to some proxies only",
+            body = DeliveryReport,
+            example = json!(
+                {
+                    "code": "207 Multi-Status",
+                    "message": "Request delivered to some proxies",
+                    "data": {
+                        "delivered": 1,
+                        "failed": 1,
+                        "queued": 0,
+                        "targets": ["http://proxy1:1000", "http://proxy2:2000"],
+                        "deliveries": [
+                            { "url": "http://proxy1:1000", "status": 200, "latency_ms": 12, "attempts": 1, "error": null, "queued": false },
+                            { "url": "http://proxy2:2000", "status": 500, "latency_ms": 8, "attempts": 3, "error": "Proxy responded with 500 Internal Server Error", "queued": false }
+                        ]
+                    },
+                    "error": "1 proxies did not receive the request. Failed proxies: http://proxy2:2000.",
+                }
+            )
+        ),
+        (
+            status = 409,
+            description = "The key is not at the version expected by the request",
+            body = ErrorResponse,
+            example = json!(
+                {
+                    "code": "409 Conflict",
+                    "message": "Version conflict",
+                    "data": null,
+                    "error": "The key is not at the version expected by the request",
+                }
+            )
+        ),
+        (
+            status = 502,
+            description = "Set data to all proxies failed",
+            body = DeliveryReport,
+            example = json!(
+                {
+                    "code": "502 Bad Gateway",
+                    "message": "Request to proxies error",
+                    "data": {
+                        "delivered": 0,
+                        "failed": 1,
+                        "queued": 0,
+                        "targets": ["http://proxy2:2000"],
+                        "deliveries": [
+                            { "url": "http://proxy2:2000", "status": null, "latency_ms": 3, "attempts": 3, "error": "error sending request", "queued": false }
+                        ]
+                    },
+                    "error": "No proxy received the request. Failed proxies: http://proxy2:2000.",
+                }
+            )
         )
     )
 )]
 pub fn set_data() -> Router<Services> {
     async fn set_data_handler(
-        State(Services {
-            client,
-            proxy_service,
-        }): State<Services>,
+        State(Services { sync_service, .. }): State<Services>,
         req: SetDataRequest,
     ) -> WebResult {
-        // Get the database cursor, pointing to the list of paths in MongoDB
-        let mut proxies = proxy_service.get_proxies().await?;
-
-        // Create a tasks vector
-        let mut tasks = vec![];
-
-        // For each item in the collection
-        while let Some(Proxy { url }) = proxies.try_next().await? {
-            // Append a task into the tasks list
-            tasks.push(
-                client
-                    .post(format!("{url}/proxy-sync/v1"))
-                    .json(&req)
-                    .send(),
-            )
-        }
+        // Store the request in the outbox, then send it to all proxies in parallel,
+        // keeping the outcome of each one
+        let report = sync_service.sync(SyncOperation::Set(req)).await?;
 
-        // Run all tasks in parallel
-        join_all(tasks).await;
-        Ok(Web::ok("Set data to all proxies successfully", ()))
+        Ok(Web::report("Set data to all proxies successfully", report))
     }
     Router::new().route("/", post(set_data_handler))
 }
@@ -88,9 +138,13 @@ pub fn set_data() -> Router<Services> {
                 "data": {
                     "hello1": "world1",
                     "hello2": "world2",
+                    "hits": 0,
                     "someObject": {
                         "inner_key": "outer_key",
                     }
+                },
+                "types": {
+                    "hits": "Counter"
                 }
             }
         )
@@ -99,87 +153,217 @@ pub fn set_data() -> Router<Services> {
         (
             status = 200,
             description = "Set multi data to all proxies success",
-            body = SuccessResponse,
+            body = DeliveryReport,
             example = json!(
                 {
-                    "code": "200",
+                    "code": "200 OK",
                     "message": "Set multi data to all proxies successfully",
-                    "data": null,
+                    "data": {
+                        "delivered": 1,
+                        "failed": 0,
+                        "queued": 0,
+                        "targets": ["http://proxy1:1000"],
+                        "deliveries": [
+                            { "url": "http://proxy1:1000", "status": 200, "latency_ms": 12, "attempts": 1, "error": null, "queued": false }
+                        ]
+                    },
                     "error": "",
                 }
             )
+        ),
+        (
+            status = 207,
+            description = "Set multi data to some proxies only",
+            body = DeliveryReport
+        ),
+        (
+            status = 502,
+            description = "Set multi data to all proxies failed",
+            body = DeliveryReport
         )
     )
 )]
 pub fn set_multi_data() -> Router<Services> {  
     async fn set_multi_data_handler(
-        State(Services {
-            client,
-            proxy_service,
-        }): State<Services>,
+        State(Services { sync_service, .. }): State<Services>,
         req: SetMultiDataRequest,
     ) -> WebResult {
-        // Get the database cursor, pointing to the list of paths in MongoDB
-        let mut proxies = proxy_service.get_proxies().await?;
-
-        // Create a tasks vector
-        let mut tasks = vec![];
-
-        // For each item in the collection
-        while let Some(Proxy { url }) = proxies.try_next().await? {
-            // Append a task into the tasks list
-            tasks.push(
-                client
-                    .post(format!("{url}/proxy-sync/v1/multi"))
-                    .json(&req)
-                    .send(),
-            )
-        }
-
-        // Run all tasks in parallel
-        join_all(tasks).await;
-        Ok(Web::ok("Set multi data to all proxies successfully", ()))
+        // Store the request in the outbox, then send it to all proxies in parallel,
+        // keeping the outcome of each one
+        let report = sync_service.sync(SyncOperation::SetMulti(req)).await?;
+
+        Ok(Web::report(
+            "Set multi data to all proxies successfully",
+            report,
+        ))
     }
     Router::new().route("/multi", post(set_multi_data_handler))
 }
 
 #[cfg(test)]
 mod tests {
-    use axum_test_helper::TestClient;
-    use mongodb::Collection;
-    use reqwest::{Client, StatusCode};
+    use std::time::Duration;
+
+    use reqwest::StatusCode;
     use serde_json::json;
 
-    use crate::{models::proxy::Proxy, mongo::connect_mongo, service::proxy::ProxyService, Services, controller::routes, web::Web};
+    use crate::{
+        test_support::{add_proxy, fake_proxy::FakeProxy, test_hub},
+        web::Web,
+    };
 
     #[tokio::test]
-    async fn set_data_should_success() {
-        let proxy_collection: Collection<Proxy> = connect_mongo().await.collection("Proxy");
+    async fn set_data_with_invalid_ttl_should_fail() {
+        let test_client = test_hub().await;
 
-        let proxy_service = ProxyService::init(&proxy_collection);
+        let response = test_client
+            .post("/sync")
+            .json(&json!(
+                { "type": "String", "key": "test_invalid_ttl", "value": "hello", "ttl": 0 }
+            ))
+            .send()
+            .await;
 
-        let service = Services {
-            client: Client::new(),
-            proxy_service,
-        };
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+        // Large enough to overflow the expiry timestamp
+        let response = test_client
+            .post("/sync")
+            .json(&json!(
+                { "type": "String", "key": "test_invalid_ttl", "value": "hello", "ttl": i64::MAX }
+            ))
+            .send()
+            .await;
+
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn set_data_with_mismatched_type_should_fail() {
+        let test_client = test_hub().await;
+
+        let response = test_client
+            .post("/sync")
+            .json(&json!(
+                { "type": "Counter", "key": "test_mismatched_type", "value": "hello" }
+            ))
+            .send()
+            .await;
+
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+        let Web { error, .. } = response.json().await;
+        assert!(error.contains("Value must be an integer"));
+    }
+
+    #[tokio::test]
+    async fn set_multi_data_without_object_should_fail() {
+        let test_client = test_hub().await;
+
+        let response = test_client
+            .post("/sync/multi")
+            .json(&json!(
+                { "type": "Multi", "data": ["test_multi_array"] }
+            ))
+            .send()
+            .await;
 
-        let router = routes(service);
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
 
-        let test_client = TestClient::new(router);
+        let Web { message, .. } = response.json().await;
+        assert_eq!(message, "Invalid input");
+    }
+
+    #[tokio::test]
+    async fn set_multi_data_without_key_types_should_fail() {
+        let test_client = test_hub().await;
+
+        for body in [
+            // A value that no type holds
+            json!({ "type": "Multi", "data": { "test_multi_flag": true } }),
+            // A type that does not fit the value
+            json!({
+                "type": "Multi",
+                "data": { "test_multi_set": [1, 1] },
+                "types": { "test_multi_set": "Set" }
+            }),
+            // Types along with a single type
+            json!({
+                "type": "Number",
+                "data": { "test_multi_n": 1 },
+                "types": { "test_multi_n": "Counter" }
+            }),
+        ] {
+            let response = test_client.post("/sync/multi").json(&body).send().await;
+            assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        }
+    }
+
+    #[tokio::test]
+    async fn set_data_with_stale_version_should_fail() {
+        let test_client = test_hub().await;
+
+        let response = test_client
+            .post("/sync")
+            .json(&json!(
+                { "type": "String", "key": "test_version", "value": "a" }
+            ))
+            .send()
+            .await;
+        let Web { data, .. } = response.json().await;
+        let version = data["version"].as_i64().unwrap();
+
+        // The first writer moves the key to the next version
+        let response = test_client
+            .post("/sync")
+            .json(&json!(
+                { "type": "String", "key": "test_version", "value": "b", "if_version": version }
+            ))
+            .send()
+            .await;
+        assert_eq!(response.status(), StatusCode::OK);
+
+        // The second one still expects the version it read before
+        let response = test_client
+            .post("/sync")
+            .json(&json!(
+                { "type": "String", "key": "test_version", "value": "c", "if_version": version }
+            ))
+            .send()
+            .await;
+        assert_eq!(response.status(), StatusCode::CONFLICT);
+
+        let response = test_client
+            .post("/sync")
+            .json(&json!(
+                { "type": "String", "key": "test_version", "value": "d", "if_absent": true }
+            ))
+            .send()
+            .await;
+        assert_eq!(response.status(), StatusCode::CONFLICT);
 
         test_client
-            .post("/proxy/create")
+            .delete("/sync")
             .json(&json!(
-                { "url": "http://localhost:3000" }
+                { "type": "String", "key": "test_version" }
             ))
             .send()
             .await;
+    }
+
+    #[tokio::test]
+    async fn set_data_should_success() {
+        let fake_proxy = FakeProxy::spawn().await;
+
+        let test_client = test_hub().await;
+
+        add_proxy(&test_client, &fake_proxy.url).await;
 
         let response = test_client.post("/sync").json(
             &json!(
                 { 
-                    "type": "String",
-                    "key": "test_str",
+                    "type": "Json",
+                    "key": "test_set",
                     "value": {
                         "hello": "world"
                     },
@@ -192,15 +376,16 @@ mod tests {
         let Web {code, message, data, error} = response.json().await;
         assert_eq!(code, StatusCode::OK.to_string());
         assert_eq!(message, "Set data to all proxies successfully");
-        assert_eq!(data, json!( null ));
+        assert_eq!(data["delivered"], json!(1));
+        assert_eq!(data["failed"], json!(0));
         assert_eq!(error, "");
-
+        assert_eq!(fake_proxy.value("test_set"), Some(json!({ "hello": "world" })));
 
         test_client.delete("/sync").json(
             &json!(
                 { 
                     "type": "String",
-                    "key": "test_str",
+                    "key": "test_set",
                 }
             )
         ).send().await;
@@ -208,7 +393,7 @@ mod tests {
         test_client
             .delete("/proxy/delete")
             .json(&json!(
-                { "url": "http://localhost:3000" }
+                { "url": fake_proxy.url }
             ))
             .send()
             .await;
@@ -216,33 +401,18 @@ mod tests {
 
     #[tokio::test]
     async fn set_multi_data_should_success() {
-        let proxy_collection: Collection<Proxy> = connect_mongo().await.collection("Proxy");
-
-        let proxy_service = ProxyService::init(&proxy_collection);
-
-        let service = Services {
-            client: Client::new(),
-            proxy_service,
-        };
-
-        let router = routes(service);
+        let fake_proxy = FakeProxy::spawn().await;
 
-        let test_client = TestClient::new(router);
+        let test_client = test_hub().await;
 
-        test_client
-            .post("/proxy/create")
-            .json(&json!(
-                { "url": "http://localhost:3000" }
-            ))
-            .send()
-            .await;
+        add_proxy(&test_client, &fake_proxy.url).await;
 
         let response = test_client.post("/sync/multi").json(
             &json!(
                 { 
                     "type": "Multi",
                     "data": {
-                        "test_str": {
+                        "test_set_multi": {
                             "hello": "world"
                         },
                     }
@@ -255,13 +425,14 @@ mod tests {
         let Web {code, message, data, error} = response.json().await;
         assert_eq!(code, StatusCode::OK.to_string());
         assert_eq!(message, "Set multi data to all proxies successfully");
+        assert_eq!(data["delivered"], json!(1));
         assert_eq!(error, "");
 
         test_client.delete("/sync").json(
             &json!(
                 { 
                     "type": "String",
-                    "key": "test_str",
+                    "key": "test_set_multi",
                 }
             )
         ).send().await;
@@ -269,10 +440,149 @@ mod tests {
         test_client
             .delete("/proxy/delete")
             .json(&json!(
-                { "url": "http://localhost:3000" }
+                { "url": fake_proxy.url }
             ))
             .send()
             .await;
     }
 
-}
\ No newline at end of file
+    #[tokio::test]
+    async fn set_data_with_targets_should_success() {
+        let test_client = test_hub().await;
+
+        let response = test_client
+            .post("/sync")
+            .json(&json!(
+                {
+                    "type": "String",
+                    "key": "test_targets",
+                    "value": "hello",
+                    "targets": { "include": { "urls": ["http://invalid"] } }
+                }
+            ))
+            .send()
+            .await;
+
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let Web { data, .. } = response.json().await;
+        assert_eq!(data["targets"], json!([]));
+    }
+
+    #[tokio::test]
+    async fn set_data_with_unnormalized_target_url_should_success() {
+        let fake_proxy = FakeProxy::spawn().await;
+
+        let test_client = test_hub().await;
+        add_proxy(&test_client, &fake_proxy.url).await;
+
+        let response = test_client
+            .post("/sync")
+            .json(&json!(
+                {
+                    "type": "String",
+                    "key": "test_target_url",
+                    "value": "hello",
+                    "targets": { "include": { "urls": [format!("{}/", fake_proxy.url)] } }
+                }
+            ))
+            .send()
+            .await;
+
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let Web { data, .. } = response.json().await;
+        assert_eq!(data["delivered"], json!(1));
+        assert_eq!(fake_proxy.value("test_target_url"), Some(json!("hello")));
+    }
+
+    #[tokio::test]
+    async fn set_data_with_failing_proxy_should_partially_success() {
+        let fake_proxies = FakeProxy::spawn_many(2).await;
+
+        let test_client = test_hub().await;
+
+        for fake_proxy in &fake_proxies {
+            add_proxy(&test_client, &fake_proxy.url).await;
+        }
+
+        fake_proxies[1].fail_with(StatusCode::BAD_REQUEST);
+
+        let response = test_client
+            .post("/sync")
+            .json(&json!(
+                {
+                    "type": "String",
+                    "key": "test_partial",
+                    "value": "hello"
+                }
+            ))
+            .send()
+            .await;
+
+        assert_eq!(response.status(), StatusCode::MULTI_STATUS);
+
+        let Web { data, .. } = response.json().await;
+        assert_eq!(data["delivered"], json!(1));
+        assert_eq!(data["failed"], json!(1));
+        assert_eq!(fake_proxies[0].sync_calls().len(), 1);
+        assert_eq!(fake_proxies[1].value("test_partial"), None);
+
+        test_client
+            .delete("/sync")
+            .json(&json!(
+                {
+                    "type": "String",
+                    "key": "test_partial",
+                }
+            ))
+            .send()
+            .await;
+    }
+
+    #[tokio::test]
+    async fn set_data_concurrently_should_keep_the_last_write() {
+        let fake_proxy = FakeProxy::spawn().await;
+
+        let test_client = test_hub().await;
+
+        add_proxy(&test_client, &fake_proxy.url).await;
+
+        // A slow proxy would let the second write overtake the first one if both went out at once
+        fake_proxy.delay(Duration::from_millis(100));
+
+        let set = |value: &str| {
+            test_client
+                .post("/sync")
+                .json(&json!(
+                    { "type": "String", "key": "test_order", "value": value }
+                ))
+                .send()
+        };
+        let (first, second) = tokio::join!(set("a"), set("b"));
+        assert_eq!(first.status(), StatusCode::OK);
+        assert_eq!(second.status(), StatusCode::OK);
+
+        // The proxy got the writes one after another, in the order of their versions
+        let versions = fake_proxy
+            .sync_calls()
+            .iter()
+            .map(|call| call.body["version"].as_i64().unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(versions.len(), 2);
+        assert!(versions[0] < versions[1]);
+
+        let response = test_client.get("/sync/data/test_order").send().await;
+        let Web { data, .. } = response.json().await;
+        assert_eq!(fake_proxy.value("test_order"), Some(data["value"].clone()));
+
+        fake_proxy.recover();
+        test_client
+            .delete("/sync")
+            .json(&json!(
+                { "type": "String", "key": "test_order" }
+            ))
+            .send()
+            .await;
+    }
+}
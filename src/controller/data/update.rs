@@ -0,0 +1,201 @@
+use axum::{extract::State, routing::patch, Router};
+
+use crate::{
+    models::operation::SyncOperation, request::data::update::UpdateDataRequest, web::Web, Services,
+    WebResult,
+};
+
+#[utoipa::path(
+    patch,
+    tag = "Sync",
+    path = "/sync",
+    request_body(
+        content = UpdateDataRequest,
+        description = "Update data request model, one of incr, decr, append, prepend, set_add, set_remove, hash_set and hash_delete",
+        example = json!(
+            {
+                "type": "Counter",
+                "key": "test_counter",
+                "op": "incr",
+                "by": 5
+            }
+        )
+    ),
+    responses(
+        (
+            status = 200,
+            description = "Update data on all proxies success",
+            body = DeliveryReport,
+            example = json!(
+                {
+                    "code": "200 OK",
+                    "message": "Update data on all proxies successfully",
+                    "data": {
+                        "delivered": 1,
+                        "failed": 0,
+                        "queued": 0,
+                        "targets": ["http://proxy1:1000"],
+                        "deliveries": [
+                            { "url": "http://proxy1:1000", "status": 200, "latency_ms": 12, "attempts": 1, "error": null, "queued": false }
+                        ]
+                    },
+                    "error": "",
+                }
+            )
+        ),
+        (
+            status = 207,
+            description = "Update data on some proxies only",
+            body = DeliveryReport
+        ),
+        (
+            status = 400,
+            description = "The operation does not apply to the type given",
+            body = ErrorResponse
+        ),
+        (
+            status = 409,
+            description = "The key holds a value of another type",
+            body = ErrorResponse,
+            example = json!(
+                {
+                    "code": "409 Conflict",
+                    "message": "Wrong data type",
+                    "data": null,
+                    "error": "The key holds a value of another type",
+                }
+            )
+        ),
+        (
+            status = 502,
+            description = "Update data on all proxies failed",
+            body = DeliveryReport
+        )
+    )
+)]
+pub fn update_data() -> Router<Services> {
+    async fn update_data_handler(
+        State(Services { sync_service, .. }): State<Services>,
+        req: UpdateDataRequest,
+    ) -> WebResult {
+        // The change itself is sent to the proxies, not the value it leads to
+        let report = sync_service.sync(SyncOperation::Update(req)).await?;
+
+        Ok(Web::report(
+            "Update data on all proxies successfully",
+            report,
+        ))
+    }
+    Router::new().route("/", patch(update_data_handler))
+}
+
+#[cfg(test)]
+mod tests {
+    use reqwest::StatusCode;
+    use serde_json::json;
+
+    use crate::{
+        test_support::{add_proxy, fake_proxy::FakeProxy, test_hub},
+        web::Web,
+    };
+
+    #[tokio::test]
+    async fn update_data_should_success() {
+        let fake_proxy = FakeProxy::spawn().await;
+
+        let test_client = test_hub().await;
+
+        add_proxy(&test_client, &fake_proxy.url).await;
+
+        for by in [2, 3] {
+            let response = test_client
+                .patch("/sync")
+                .json(&json!(
+                    { "type": "Counter", "key": "test_update", "op": "incr", "by": by }
+                ))
+                .send()
+                .await;
+
+            assert_eq!(response.status(), StatusCode::OK);
+        }
+
+        let Web { data, .. } = test_client
+            .get("/sync/data/test_update")
+            .send()
+            .await
+            .json()
+            .await;
+        assert_eq!(data["value"], json!(5));
+        assert_eq!(fake_proxy.value("test_update"), Some(json!(5)));
+
+        test_client
+            .delete("/sync")
+            .json(&json!(
+                { "type": "Counter", "key": "test_update" }
+            ))
+            .send()
+            .await;
+    }
+
+    #[tokio::test]
+    async fn update_data_of_another_type_should_fail() {
+        let test_client = test_hub().await;
+
+        let response = test_client
+            .patch("/sync")
+            .json(&json!(
+                { "type": "List", "key": "test_update_type", "op": "incr", "by": 1 }
+            ))
+            .send()
+            .await;
+
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn update_data_after_set_multi_should_success() {
+        let fake_proxy = FakeProxy::spawn().await;
+
+        let test_client = test_hub().await;
+
+        add_proxy(&test_client, &fake_proxy.url).await;
+
+        // Keys of several types written together, the counter named in types
+        // and the list known by its value
+        let response = test_client
+            .post("/sync/multi")
+            .json(&json!(
+                {
+                    "type": "Multi",
+                    "data": { "test_multi_hits": 1, "test_multi_list": [] },
+                    "types": { "test_multi_hits": "Counter" }
+                }
+            ))
+            .send()
+            .await;
+        assert_eq!(response.status(), StatusCode::OK);
+
+        for change in [
+            json!({ "type": "Counter", "key": "test_multi_hits", "op": "incr", "by": 2 }),
+            json!({ "type": "List", "key": "test_multi_list", "op": "append", "value": 1 }),
+        ] {
+            let response = test_client.patch("/sync").json(&change).send().await;
+            assert_eq!(response.status(), StatusCode::OK);
+        }
+
+        for (key, _type, value) in [
+            ("test_multi_hits", "Counter", json!(3)),
+            ("test_multi_list", "List", json!([1])),
+        ] {
+            let Web { data, .. } = test_client
+                .get(&format!("/sync/data/{key}"))
+                .send()
+                .await
+                .json()
+                .await;
+            assert_eq!(data["type"], json!(_type));
+            assert_eq!(data["value"], value);
+            assert_eq!(fake_proxy.value(key), Some(value));
+        }
+    }
+}
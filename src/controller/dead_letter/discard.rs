@@ -0,0 +1,115 @@
+use axum::{
+    extract::{Path, State},
+    routing::delete,
+    Router,
+};
+use serde_json::json;
+
+use crate::{request::dead_letter::filter::DeadLetterFilter, web::Web, Services, WebResult};
+
+#[utoipa::path(
+    delete,
+    tag = "Dead letters",
+    path = "/sync/dead-letters",
+    params(DeadLetterFilter),
+    responses(
+        (
+            status = 200,
+            description = "Discarded every matching dead letter",
+            body = SuccessResponse,
+            example = json!(
+                {
+                    "code": "200 OK",
+                    "message": "Discarded dead letters successfully",
+                    "data": { "deleted": 3 },
+                    "error": ""
+                }
+            )
+        )
+    )
+)]
+pub fn discard_dead_letters() -> Router<Services> {
+    async fn discard_dead_letters_handler(
+        State(Services {
+            dead_letter_service,
+            ..
+        }): State<Services>,
+        filter: DeadLetterFilter,
+    ) -> WebResult {
+        let deleted = dead_letter_service.delete_dead_letters(&filter).await?;
+        Ok(Web::ok(
+            "Discarded dead letters successfully",
+            json!({ "deleted": deleted }),
+        ))
+    }
+    Router::new().route("/", delete(discard_dead_letters_handler))
+}
+
+#[utoipa::path(
+    delete,
+    tag = "Dead letters",
+    path = "/sync/dead-letters/{id}",
+    params(
+        ("id" = String, Path, description = "Dead letter id")
+    ),
+    responses(
+        (
+            status = 200,
+            description = "Discarded the dead letter",
+            body = SuccessResponse,
+            example = json!(
+                {
+                    "code": "200 OK",
+                    "message": "Discarded dead letter successfully",
+                    "data": null,
+                    "error": ""
+                }
+            )
+        ),
+        (
+            status = 404,
+            description = "Dead letter not found",
+            body = ErrorResponse,
+            example = json!(
+                {
+                    "code": "404 Not Found",
+                    "message": "Dead letter not found",
+                    "data": null,
+                    "error": "The id provided cannot be found in the dead letters",
+                }
+            )
+        )
+    )
+)]
+pub fn discard_dead_letter() -> Router<Services> {
+    async fn discard_dead_letter_handler(
+        State(Services {
+            dead_letter_service,
+            ..
+        }): State<Services>,
+        Path(id): Path<String>,
+    ) -> WebResult {
+        dead_letter_service.delete_dead_letter(&id).await?;
+        Ok(Web::ok("Discarded dead letter successfully", ()))
+    }
+    Router::new().route("/:id", delete(discard_dead_letter_handler))
+}
+
+#[cfg(test)]
+mod tests {
+    use reqwest::StatusCode;
+
+    use crate::test_support::test_hub;
+
+    #[tokio::test]
+    async fn discard_dead_letter_should_fail_test() {
+        let test_client = test_hub().await;
+
+        let response = test_client
+            .delete("/sync/dead-letters/invalid")
+            .send()
+            .await;
+
+        assert_eq!(response.status(), StatusCode::NOT_FOUND)
+    }
+}
@@ -0,0 +1,86 @@
+use axum::{extract::State, routing::get, Router};
+
+use crate::{request::dead_letter::filter::DeadLetterFilter, web::Web, Services, WebResult};
+
+#[utoipa::path(
+    get,
+    tag = "Dead letters",
+    path = "/sync/dead-letters",
+    params(DeadLetterFilter),
+    responses(
+        (
+            status = 200,
+            description = "List of deliveries that ran out of retries",
+            body = [DeadLetter],
+            example = json!(
+                {
+                    "code": "200 OK",
+                    "message": "Get dead letters successfully",
+                    "data": [
+                        {
+                            "_id": "6470a8c3f1d2a9b1c0e4d5f6",
+                            "url": "http://proxy2:2000",
+                            "operation": {
+                                "kind": "set",
+                                "payload": { "type": "String", "key": "test_str", "value": "hello" }
+                            },
+                            "keys": ["test_str"],
+                            "type": "String",
+                            "attempts": 10,
+                            "error": "Proxy responded with 503 Service Unavailable",
+                            "failed_at": 1685100000000i64
+                        }
+                    ],
+                    "error": ""
+                }
+            )
+        )
+    )
+)]
+pub fn get_dead_letters() -> Router<Services> {
+    async fn get_dead_letters_handler(
+        State(Services {
+            dead_letter_service,
+            ..
+        }): State<Services>,
+        filter: DeadLetterFilter,
+    ) -> WebResult {
+        let dead_letters = dead_letter_service.get_dead_letters(&filter).await?;
+        Ok(Web::ok("Get dead letters successfully", dead_letters))
+    }
+    Router::new().route("/", get(get_dead_letters_handler))
+}
+
+#[cfg(test)]
+mod tests {
+    use reqwest::StatusCode;
+
+    use crate::{
+        test_support::{fake_proxy::FakeProxy, test_hub},
+        web::Web,
+    };
+
+    #[tokio::test]
+    async fn get_dead_letters_should_success_test() {
+        let fake_proxy = FakeProxy::spawn().await;
+
+        let test_client = test_hub().await;
+
+        let response = test_client
+            .get(&format!("/sync/dead-letters?url={}&from=0", fake_proxy.url))
+            .send()
+            .await;
+
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let Web {
+            code,
+            message,
+            data,
+            ..
+        } = response.json().await;
+        assert_eq!(code, StatusCode::OK.to_string());
+        assert_eq!(message, "Get dead letters successfully");
+        assert!(data.is_array());
+    }
+}
@@ -0,0 +1,25 @@
+pub mod discard;
+pub mod get;
+pub mod replay;
+
+use axum::Router;
+
+use crate::Services;
+
+use self::{
+    discard::{discard_dead_letter, discard_dead_letters},
+    get::get_dead_letters,
+    replay::{replay_dead_letter, replay_dead_letters},
+};
+
+pub fn dead_letter_routes() -> Router<Services> {
+    Router::new().nest(
+        "/sync/dead-letters",
+        Router::new()
+            .merge(get_dead_letters())
+            .merge(replay_dead_letters())
+            .merge(replay_dead_letter())
+            .merge(discard_dead_letters())
+            .merge(discard_dead_letter()),
+    )
+}
@@ -0,0 +1,194 @@
+use axum::{
+    extract::{Path, State},
+    routing::post,
+    Router,
+};
+
+use crate::{request::dead_letter::filter::DeadLetterFilter, web::Web, Services, WebResult};
+
+#[utoipa::path(
+    post,
+    tag = "Dead letters",
+    path = "/sync/dead-letters/replay",
+    params(DeadLetterFilter),
+    responses(
+        (
+            status = 200,
+            description = "Replayed every matching dead letter",
+            body = DeliveryReport,
+            example = json!(
+                {
+                    "code": "200 OK",
+                    "message": "Replayed dead letters successfully",
+                    "data": {
+                        "delivered": 1,
+                        "failed": 0,
+                        "queued": 0,
+                        "targets": ["http://proxy2:2000"],
+                        "deliveries": [
+                            { "url": "http://proxy2:2000", "status": 200, "latency_ms": 12, "attempts": 1, "error": null, "queued": false }
+                        ]
+                    },
+                    "error": ""
+                }
+            )
+        ),
+        (
+            status = 207,
+            description = "Replayed some matching dead letters only",
+            body = DeliveryReport
+        ),
+        (
+            status = 502,
+            description = "Replaying the matching dead letters failed",
+            body = DeliveryReport
+        )
+    )
+)]
+pub fn replay_dead_letters() -> Router<Services> {
+    async fn replay_dead_letters_handler(
+        State(Services {
+            sync_service,
+            dead_letter_service,
+            ..
+        }): State<Services>,
+        filter: DeadLetterFilter,
+    ) -> WebResult {
+        let dead_letters = dead_letter_service.get_dead_letters(&filter).await?;
+        let report = sync_service.replay(dead_letters).await?;
+        Ok(Web::report("Replayed dead letters successfully", report))
+    }
+    Router::new().route("/replay", post(replay_dead_letters_handler))
+}
+
+#[utoipa::path(
+    post,
+    tag = "Dead letters",
+    path = "/sync/dead-letters/{id}/replay",
+    params(
+        ("id" = String, Path, description = "Dead letter id")
+    ),
+    responses(
+        (
+            status = 200,
+            description = "Replayed the dead letter",
+            body = DeliveryReport
+        ),
+        (
+            status = 502,
+            description = "Replaying the dead letter failed",
+            body = DeliveryReport
+        ),
+        (
+            status = 404,
+            description = "Dead letter not found",
+            body = ErrorResponse,
+            example = json!(
+                {
+                    "code": "404 Not Found",
+                    "message": "Dead letter not found",
+                    "data": null,
+                    "error": "The id provided cannot be found in the dead letters",
+                }
+            )
+        )
+    )
+)]
+pub fn replay_dead_letter() -> Router<Services> {
+    async fn replay_dead_letter_handler(
+        State(Services {
+            sync_service,
+            dead_letter_service,
+            ..
+        }): State<Services>,
+        Path(id): Path<String>,
+    ) -> WebResult {
+        let dead_letter = dead_letter_service.get_dead_letter(&id).await?;
+        let report = sync_service.replay(vec![dead_letter]).await?;
+        Ok(Web::report("Replayed dead letter successfully", report))
+    }
+    Router::new().route("/:id/replay", post(replay_dead_letter_handler))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use reqwest::StatusCode;
+    use serde_json::json;
+
+    use crate::{
+        config::{OutboxConfig, RetryPolicy, SyncConfig},
+        test_support::{add_proxy, fake_proxy::FakeProxy, test_hub, test_hub_with},
+        web::Web,
+    };
+
+    #[tokio::test]
+    async fn replay_dead_letter_should_fail_test() {
+        let test_client = test_hub().await;
+
+        let response = test_client
+            .post("/sync/dead-letters/invalid/replay")
+            .send()
+            .await;
+
+        assert_eq!(response.status(), StatusCode::NOT_FOUND)
+    }
+
+    #[tokio::test]
+    async fn replay_dead_letters_should_success_test() {
+        let fake_proxy = FakeProxy::spawn().await;
+
+        // Every delivery round makes one attempt, and the second round gives up
+        let (test_client, services) = test_hub_with(
+            &SyncConfig {
+                retry: RetryPolicy {
+                    max_attempts: 1,
+                    ..Default::default()
+                },
+                ..SyncConfig::from_env()
+            },
+            &OutboxConfig {
+                retry_delay: Duration::ZERO,
+                max_attempts: 2,
+                ..OutboxConfig::from_env()
+            },
+        )
+        .await;
+
+        add_proxy(&test_client, &fake_proxy.url).await;
+        fake_proxy.fail_times(StatusCode::INTERNAL_SERVER_ERROR, 2);
+
+        let response = test_client
+            .post("/sync")
+            .json(&json!(
+                { "type": "String", "key": "test_dead_letter", "value": "hello" }
+            ))
+            .send()
+            .await;
+        assert_eq!(response.status(), StatusCode::BAD_GATEWAY);
+
+        // The dispatcher uses up the last attempt, and moves the write to the dead letters
+        assert_eq!(services.sync_service.dispatch_due().await.unwrap(), 1);
+        assert_eq!(services.sync_service.dispatch_due().await.unwrap(), 0);
+
+        let dead_letters = format!("/sync/dead-letters?url={}", fake_proxy.url);
+        let Web { data, .. } = test_client.get(&dead_letters).send().await.json().await;
+        assert_eq!(data[0]["keys"], json!(["test_dead_letter"]));
+        assert_eq!(data[0]["attempts"], json!(2));
+        assert_eq!(fake_proxy.value("test_dead_letter"), None);
+
+        let response = test_client
+            .post(&format!("/sync/dead-letters/replay?url={}", fake_proxy.url))
+            .send()
+            .await;
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let Web { data, .. } = response.json().await;
+        assert_eq!(data["delivered"], json!(1));
+        assert_eq!(fake_proxy.value("test_dead_letter"), Some(json!("hello")));
+
+        let Web { data, .. } = test_client.get(&dead_letters).send().await.json().await;
+        assert_eq!(data, json!([]));
+    }
+}
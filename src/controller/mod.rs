@@ -1,4 +1,5 @@
 pub mod data;
+pub mod dead_letter;
 pub mod proxy;
 
 use axum::Router;
@@ -7,10 +8,20 @@ use utoipa_swagger_ui::SwaggerUi;
 
 use crate::Services;
 
-use self::{data::data_routes, proxy::proxy_routes};
+use self::{data::data_routes, dead_letter::dead_letter_routes, proxy::proxy_routes};
 use crate::{
-    models::{error::*, proxy::Proxy, success::*},
-    request::{proxy::{add::*, delete::*}, data::{set::*, delete::*}},
+    models::{
+        data::{Data, DataPage, DataType},
+        data_op::DataOp,
+        dead_letter::DeadLetter,
+        delivery::*,
+        digest::{Digest, DriftReport},
+        error::*,
+        health::{HealthCheck, HealthStatus},
+        protocol::ProtocolVersion, proxy::{Bootstrap, BootstrapStatus, Breaker, BreakerState, Provider, Proxy, ProxyMode, ProxyRemoval, Subscription},
+        success::*,
+    },
+    request::{proxy::{add::*, bootstrap::*, breaker::*, delete::*, mode::*, update::*}, data::{set::*, delete::*, precondition::*, target::*, update::*}},
 };
 
 #[derive(OpenApi)]
@@ -22,14 +33,50 @@ use crate::{
     components(schemas(
         // Proxy models
         Proxy,
+        Provider,
+        Subscription,
+        ProxyMode,
+        ProxyRemoval,
+        ProtocolVersion,
+        Bootstrap,
+        BootstrapStatus,
+        HealthStatus,
+        Breaker,
+        BreakerState,
         AddProxyRequest,
+        BootstrapProxyRequest,
+        ResetBreakerRequest,
+        UpdateProxyRequest,
+        SetProxyModeRequest,
         DeleteProxyRequest,
 
         // Data sync models
         SetDataRequest,
         SetMultiDataRequest,
         DeleteDataRequest,
+        UpdateDataRequest,
+        DataOp,
+        Precondition,
+        TargetSelector,
+        ProxyMatcher,
+        Data,
+        DataPage,
+        DataType,
+
+        // Reconciliation models
+        Digest,
+        DriftReport,
         
+        // Health models
+        HealthCheck,
+
+        // Dead letter models
+        DeadLetter,
+
+        // Delivery report models
+        Delivery,
+        DeliveryReport,
+
         // General Reponses
         SuccessResponse,
         ErrorResponse
@@ -37,18 +84,37 @@ use crate::{
     paths(
         // Sync paths
         data::health::health,
+        data::health::check_health,
+        data::get::get_data,
+        data::get::get_keys,
+        data::reconcile::reconcile,
         data::set::set_data,
         data::set::set_multi_data,
         data::delete::delete_data,
+        data::update::update_data,
+
+        // Dead letter paths
+        dead_letter::get::get_dead_letters,
+        dead_letter::replay::replay_dead_letters,
+        dead_letter::replay::replay_dead_letter,
+        dead_letter::discard::discard_dead_letters,
+        dead_letter::discard::discard_dead_letter,
 
         // Proxy paths
         proxy::get::get_proxies,
+        proxy::get::lookup_proxy,
         proxy::add::add_proxy,
-        proxy::delete::delete_proxy
+        proxy::bootstrap::bootstrap_proxy,
+        proxy::breaker::reset_breaker,
+        proxy::delete::delete_proxy,
+        proxy::delete::delete_proxy_by_id,
+        proxy::update::update_proxy,
+        proxy::mode::set_proxy_mode
     ),
     tags(
         (name = "Proxy", description = "API routes for managing proxies"),
-        (name = "Sync", description = "API routes for syncing data between proxies")
+        (name = "Sync", description = "API routes for syncing data between proxies"),
+        (name = "Dead letters", description = "API routes for inspecting and replaying failed deliveries")
     )
 )]
 struct ApiDoc;
@@ -56,6 +122,7 @@ struct ApiDoc;
 pub fn routes(service: Services) -> Router {
     Router::new()
         .merge(data_routes())
+        .merge(dead_letter_routes())
         .merge(proxy_routes())
         .merge(SwaggerUi::new("/docs").url("/api-doc/openapi.json", ApiDoc::openapi()))
         .with_state(service)
@@ -1,6 +1,9 @@
 use axum::{extract::State, routing::post, Router};
 
-use crate::{error::Error, request::proxy::add::AddProxyRequest, web::Web, Services, WebResult};
+use crate::{
+    error::Error, models::proxy::Proxy, request::proxy::add::AddProxyRequest, web::Web, Services,
+    WebResult,
+};
 
 #[utoipa::path(
     post,
@@ -10,7 +13,16 @@ use crate::{error::Error, request::proxy::add::AddProxyRequest, web::Web, Servic
         content = AddProxyRequest,
         description = "Add proxy request",
         example = json!(
-            { "url": "http://proxy3:3000" }
+            {
+                "url": "http://proxy3:3000",
+                "name": "eu-west edge",
+                "provider": "aws",
+                "region": "eu-west-1",
+                "environment": "production",
+                "tags": ["eu", "edge"],
+                "metadata": { "team": "platform" },
+                "subscriptions": [{ "pattern": "config.", "types": [] }, { "pattern": "user:*:profile", "types": ["String"] }]
+            }
         )
     ),
     responses(
@@ -23,7 +35,29 @@ use crate::{error::Error, request::proxy::add::AddProxyRequest, web::Web, Servic
                     "code": "200 OK",
                     "message": "New proxy created",
                     "data": {
-                        "url": "http://proxy3:3000"
+                        "_id": "6470a8c3f1d2a9b1c0e4d5f7",
+                        "url": "http://proxy3:3000",
+                        "name": "eu-west edge",
+                        "provider": "aws",
+                        "region": "eu-west-1",
+                        "environment": "production",
+                        "tags": ["eu", "edge"],
+                        "metadata": { "team": "platform" },
+                        "subscriptions": [{ "pattern": "config.", "types": [] }, { "pattern": "user:*:profile", "types": ["String"] }],
+                        "mode": "enabled",
+                        "protocol": "v2",
+                        "features": ["ops"],
+                        "bootstrap": {
+                            "status": "pending",
+                            "sent": 0,
+                            "total": 0,
+                            "error": null
+                        },
+                        "status": "unknown",
+                        "last_seen": null,
+                        "consecutive_failures": 0,
+                        "last_error": null,
+                        "breaker": { "state": "closed", "failures": 0, "opened_at": null }
                     },
                     "error": ""
                 }
@@ -49,14 +83,28 @@ pub fn add_proxy() -> Router<Services> {
         State(Services {
             client,
             proxy_service,
+            sync_service,
+            ..
         }): State<Services>,
-        AddProxyRequest { url }: AddProxyRequest,
+        request: AddProxyRequest,
     ) -> WebResult {
+        let url = request.url.clone();
+
         // Test connection before adding to the proxies list
         match client.get(format!("{url}/health")).send().await {
             // Connection successful
             Ok(_) => {
-                let new_proxy = proxy_service.add_proxy(&url).await?;
+                let (protocol, features) = sync_service.negotiate(&url).await;
+                let proxy = Proxy {
+                    protocol,
+                    features,
+                    ..request.into()
+                };
+                let new_proxy = proxy_service.add_proxy(proxy).await?;
+
+                // Send the current state to the new proxy before it receives writes directly
+                sync_service.spawn_bootstrap(&url);
+
                 Ok(Web::created("New proxy created", new_proxy))
             }
             // Connection failed
@@ -68,35 +116,24 @@ pub fn add_proxy() -> Router<Services> {
 
 #[cfg(test)]
 mod tests {
-    use axum_test_helper::TestClient;
-    use mongodb::Collection;
-    use reqwest::{Client, StatusCode};
+    use reqwest::StatusCode;
     use serde_json::json;
 
     use crate::{
-        controller::routes, models::proxy::Proxy, mongo::connect_mongo,
-        service::proxy::ProxyService, web::Web, Services,
+        test_support::{fake_proxy::FakeProxy, test_hub},
+        web::Web,
     };
 
     #[tokio::test]
     async fn add_proxy_should_success_test() {
-        let proxy_collection: Collection<Proxy> = connect_mongo().await.collection("Proxy");
-
-        let proxy_service = ProxyService::init(&proxy_collection);
-
-        let service = Services {
-            client: Client::new(),
-            proxy_service,
-        };
+        let fake_proxy = FakeProxy::spawn().await;
 
-        let router = routes(service);
-
-        let test_client = TestClient::new(router);
+        let test_client = test_hub().await;
 
         let response = test_client
             .post("/proxy/create")
             .json(&json!(
-                { "url": "http://localhost:3000" }
+                { "url": fake_proxy.url }
             ))
             .send()
             .await;
@@ -115,7 +152,7 @@ mod tests {
         test_client
             .delete("/proxy/delete")
             .json(&json!(
-                { "url": "http://localhost:3000" }
+                { "url": fake_proxy.url }
             ))
             .send()
             .await;
@@ -123,26 +160,87 @@ mod tests {
 
     #[tokio::test]
     async fn add_proxy_should_fail_test() {
-        let proxy_collection: Collection<Proxy> = connect_mongo().await.collection("Proxy");
+        let test_client = test_hub().await;
 
-        let proxy_service = ProxyService::init(&proxy_collection);
+        let response = test_client
+            .post("/proxy/create")
+            .json(&json!(
+                { "url": "http://invalid" }
+            ))
+            .send()
+            .await;
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
 
-        let service = Services {
-            client: Client::new(),
-            proxy_service,
-        };
+    #[tokio::test]
+    async fn add_proxy_with_invalid_subscriptions_should_fail_test() {
+        let fake_proxy = FakeProxy::spawn().await;
+
+        let test_client = test_hub().await;
+
+        let response = test_client
+            .post("/proxy/create")
+            .json(&json!(
+                {
+                    "url": fake_proxy.url,
+                    "subscriptions": [{ "pattern": "config.", "types": [""] }]
+                }
+            ))
+            .send()
+            .await;
+
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+        // Unknown types are rejected while the body is read
+        let Web { message, .. } = response.json().await;
+        assert_eq!(message, "Invalid request body");
+
+        let response = test_client
+            .post("/proxy/create")
+            .json(&json!(
+                {
+                    "url": fake_proxy.url,
+                    "subscriptions": [{ "pattern": "a".repeat(257), "types": ["String"] }]
+                }
+            ))
+            .send()
+            .await;
+
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+        let Web { message, .. } = response.json().await;
+        assert_eq!(message, "Invalid input");
+    }
 
-        let router = routes(service);
+    #[tokio::test]
+    async fn add_proxy_with_another_spelling_should_fail_test() {
+        let fake_proxy = FakeProxy::spawn().await;
 
-        let test_client = TestClient::new(router);
+        let test_client = test_hub().await;
+
+        test_client
+            .post("/proxy/create")
+            .json(&json!(
+                { "url": fake_proxy.url }
+            ))
+            .send()
+            .await;
 
         let response = test_client
             .post("/proxy/create")
             .json(&json!(
-                { "url": "http://invalid" }
+                { "url": format!("{}/", fake_proxy.url.to_uppercase()) }
             ))
             .send()
             .await;
         assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+        test_client
+            .delete("/proxy/delete")
+            .json(&json!(
+                { "url": fake_proxy.url }
+            ))
+            .send()
+            .await;
     }
 }
@@ -0,0 +1,161 @@
+use axum::{extract::State, routing::post, Router};
+
+use crate::{
+    error::Error, request::proxy::bootstrap::BootstrapProxyRequest, web::Web, Services,
+    WebResult,
+};
+
+#[utoipa::path(
+    post,
+    tag = "Proxy",
+    path = "/proxy/bootstrap",
+    request_body(
+        content = BootstrapProxyRequest,
+        description = "Bootstrap proxy request",
+        example = json!(
+            { "url": "http://proxy3:3000" }
+        )
+    ),
+    responses(
+        (
+            status = 200,
+            description = "Bootstrap restarted",
+            body = Proxy,
+            example = json!(
+                {
+                    "code": "200 OK",
+                    "message": "Bootstrap started",
+                    "data": {
+                        "_id": "6470a8c3f1d2a9b1c0e4d5f7",
+                        "url": "http://proxy3:3000",
+                        "bootstrap": {
+                            "status": "failed",
+                            "sent": 200,
+                            "total": 350,
+                            "error": "Proxy responded with 503 Service Unavailable"
+                        },
+                        "status": "healthy",
+                        "last_seen": 1685100000000i64,
+                        "consecutive_failures": 0,
+                        "last_error": null,
+                        "breaker": { "state": "closed", "failures": 0, "opened_at": null }
+                    },
+                    "error": ""
+                }
+            )
+        ),
+        (
+            status = 404,
+            description = "Proxy not found",
+            body = ErrorResponse,
+            example = json!(
+                {
+                    "code": "404 Not Found",
+                    "message": "Proxy not found",
+                    "data": null,
+                    "error": "The url provided cannot be found in the database",
+                }
+            )
+        ),
+        (
+            status = 409,
+            description = "Bootstrap already running",
+            body = ErrorResponse,
+            example = json!(
+                {
+                    "code": "409 Conflict",
+                    "message": "Bootstrap in progress",
+                    "data": null,
+                    "error": "This proxy is already being bootstrapped, please wait for it to finish",
+                }
+            )
+        )
+    )
+)]
+pub fn bootstrap_proxy() -> Router<Services> {
+    async fn bootstrap_proxy_handler(
+        State(Services {
+            proxy_service,
+            sync_service,
+            ..
+        }): State<Services>,
+        BootstrapProxyRequest { url }: BootstrapProxyRequest,
+    ) -> WebResult {
+        let proxy = proxy_service.get_proxy(&url).await?;
+
+        // Claimed in one step, so that concurrent requests do not both start a bootstrap
+        if !proxy_service.claim_bootstrap(&url).await? {
+            return Err(Error::BootstrapInProgress);
+        }
+
+        // Progress can be followed from the proxy list
+        sync_service.spawn_bootstrap(&url);
+
+        Ok(Web::ok("Bootstrap started", proxy))
+    }
+    Router::new().route("/bootstrap", post(bootstrap_proxy_handler))
+}
+
+#[cfg(test)]
+mod tests {
+    use reqwest::{Method, StatusCode};
+    use serde_json::json;
+
+    use crate::{
+        test_support::{add_proxy, fake_proxy::FakeProxy, test_hub},
+        web::Web,
+    };
+
+    #[tokio::test]
+    async fn bootstrap_proxy_should_fail_test() {
+        let test_client = test_hub().await;
+
+        let response = test_client
+            .post("/proxy/bootstrap")
+            .json(&json!(
+                { "url": "http://invalid" }
+            ))
+            .send()
+            .await;
+
+        assert_eq!(response.status(), StatusCode::NOT_FOUND)
+    }
+
+    #[tokio::test]
+    async fn bootstrap_proxy_should_success_test() {
+        let fake_proxy = FakeProxy::spawn().await;
+
+        let test_client = test_hub().await;
+
+        for (key, value) in [("test_bootstrap_a", "a"), ("test_bootstrap_b", "b")] {
+            test_client
+                .post("/sync")
+                .json(&json!(
+                    { "type": "String", "key": key, "value": value }
+                ))
+                .send()
+                .await;
+        }
+
+        // Only returns once the bootstrap completed
+        add_proxy(&test_client, &fake_proxy.url).await;
+
+        let calls = fake_proxy.sync_calls();
+        assert_eq!(calls.len(), 1);
+        assert_eq!(calls[0].method, Method::POST);
+        assert_eq!(calls[0].path, "/proxy-sync/v1/multi");
+        assert_eq!(fake_proxy.value("test_bootstrap_a"), Some(json!("a")));
+        assert_eq!(fake_proxy.value("test_bootstrap_b"), Some(json!("b")));
+
+        let Web { data, .. } = test_client
+            .get(&format!("/proxy/lookup?url={}", fake_proxy.url))
+            .send()
+            .await
+            .json()
+            .await;
+        assert_eq!(
+            data["bootstrap"],
+            json!({ "status": "completed", "sent": 2, "total": 2, "error": null })
+        );
+    }
+}
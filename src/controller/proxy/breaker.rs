@@ -0,0 +1,155 @@
+use axum::{extract::State, routing::post, Router};
+
+use crate::{request::proxy::breaker::ResetBreakerRequest, web::Web, Services, WebResult};
+
+#[utoipa::path(
+    post,
+    tag = "Proxy",
+    path = "/proxy/breaker/reset",
+    request_body(
+        content = ResetBreakerRequest,
+        description = "Reset breaker request",
+        example = json!(
+            { "url": "http://proxy2:2000" }
+        )
+    ),
+    responses(
+        (
+            status = 200,
+            description = "Breaker closed",
+            body = Proxy,
+            example = json!(
+                {
+                    "code": "200 OK",
+                    "message": "Breaker reset successfully",
+                    "data": {
+                        "_id": "6470a8c3f1d2a9b1c0e4d5f9",
+                        "url": "http://proxy2:2000",
+                        "bootstrap": { "status": "completed", "sent": 350, "total": 350, "error": null },
+                        "status": "healthy",
+                        "last_seen": 1685100000000i64,
+                        "consecutive_failures": 0,
+                        "last_error": null,
+                        "breaker": { "state": "closed", "failures": 0, "opened_at": null }
+                    },
+                    "error": ""
+                }
+            )
+        ),
+        (
+            status = 404,
+            description = "Proxy not found",
+            body = ErrorResponse,
+            example = json!(
+                {
+                    "code": "404 Not Found",
+                    "message": "Proxy not found",
+                    "data": null,
+                    "error": "The url provided cannot be found in the database",
+                }
+            )
+        )
+    )
+)]
+pub fn reset_breaker() -> Router<Services> {
+    async fn reset_breaker_handler(
+        State(Services { proxy_service, .. }): State<Services>,
+        ResetBreakerRequest { url }: ResetBreakerRequest,
+    ) -> WebResult {
+        // The writes queued while the breaker was open are sent by the outbox dispatcher
+        let proxy = proxy_service.reset_breaker(&url).await?;
+        Ok(Web::ok("Breaker reset successfully", proxy))
+    }
+    Router::new().route("/breaker/reset", post(reset_breaker_handler))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use reqwest::StatusCode;
+    use serde_json::json;
+    use tokio::time::sleep;
+
+    use crate::{
+        config::{BreakerConfig, OutboxConfig, RetryPolicy, SyncConfig},
+        test_support::{add_proxy, fake_proxy::FakeProxy, test_hub, test_hub_with},
+        web::Web,
+    };
+
+    #[tokio::test]
+    async fn reset_breaker_should_fail_test() {
+        let test_client = test_hub().await;
+
+        let response = test_client
+            .post("/proxy/breaker/reset")
+            .json(&json!(
+                { "url": "http://invalid" }
+            ))
+            .send()
+            .await;
+
+        assert_eq!(response.status(), StatusCode::NOT_FOUND)
+    }
+
+    #[tokio::test]
+    async fn open_breaker_should_queue_writes_test() {
+        let fake_proxy = FakeProxy::spawn().await;
+
+        let breaker = BreakerConfig {
+            failure_threshold: 2,
+            cool_down: Duration::from_millis(200),
+        };
+        let (test_client, _) = test_hub_with(
+            &SyncConfig {
+                retry: RetryPolicy {
+                    max_attempts: 1,
+                    ..Default::default()
+                },
+                breaker: breaker.clone(),
+                ..SyncConfig::from_env()
+            },
+            &OutboxConfig::from_env(),
+        )
+        .await;
+
+        add_proxy(&test_client, &fake_proxy.url).await;
+        fake_proxy.fail_with(StatusCode::INTERNAL_SERVER_ERROR);
+
+        let set = |key: &'static str| {
+            test_client
+                .post("/sync")
+                .json(&json!(
+                    { "type": "String", "key": key, "value": "hello" }
+                ))
+                .send()
+        };
+
+        // Every failed delivery counts, up to the threshold
+        for key in ["test_breaker_a", "test_breaker_b"] {
+            assert_eq!(set(key).await.status(), StatusCode::BAD_GATEWAY);
+        }
+        let Web { data, .. } = test_client
+            .get(&format!("/proxy/lookup?url={}", fake_proxy.url))
+            .send()
+            .await
+            .json()
+            .await;
+        assert_eq!(data["breaker"]["state"], json!("open"));
+
+        // An open breaker keeps the writes in the outbox without trying the proxy
+        let Web { data, .. } = set("test_breaker_c").await.json().await;
+        assert_eq!(data["queued"], json!(1));
+        assert_eq!(fake_proxy.sync_calls().len(), 2);
+
+        // Once the cool down is over, only one of the writes gets to probe the proxy
+        sleep(breaker.cool_down).await;
+        let (first, second) = tokio::join!(set("test_breaker_d"), set("test_breaker_e"));
+        let queued = [first.json::<Web>().await, second.json().await]
+            .iter()
+            .map(|Web { data, .. }| data["queued"].as_u64().unwrap())
+            .sum::<u64>();
+        assert_eq!(queued, 1);
+        assert_eq!(fake_proxy.sync_calls().len(), 3);
+    }
+}
@@ -1,11 +1,45 @@
-use axum::{extract::State, routing::delete, Router};
+use axum::{
+    extract::{Path, State},
+    routing::delete,
+    Router,
+};
+
+use crate::{
+    error::Error,
+    models::proxy::{Proxy, ProxyRemoval},
+    request::proxy::delete::{DeleteProxyQuery, DeleteProxyRequest},
+    web::Web,
+    Services, WebResult,
+};
+
+// Cleans up after a deleted proxy if asked to
+async fn remove(
+    Services {
+        sync_service,
+        health_service,
+        ..
+    }: Services,
+    proxy: Proxy,
+    cascade: bool,
+) -> Result<ProxyRemoval, Error> {
+    let mut removal = ProxyRemoval::new(proxy);
+
+    if cascade {
+        let (deliveries, dead_letters) = sync_service.purge_proxy(&removal.proxy.url).await?;
+        removal.cascade = true;
+        removal.deliveries = deliveries;
+        removal.dead_letters = dead_letters;
+        removal.health_checks = health_service.delete_history(&removal.proxy.url).await?;
+    }
 
-use crate::{request::proxy::delete::DeleteProxyRequest, web::Web, Services, WebResult};
+    Ok(removal)
+}
 
 #[utoipa::path(
     delete,
     tag = "Proxy",
     path = "/proxy/delete",
+    params(DeleteProxyQuery),
     request_body(
         content = DeleteProxyRequest,
         description = "Delete proxy request",
@@ -17,12 +51,37 @@ use crate::{request::proxy::delete::DeleteProxyRequest, web::Web, Services, WebR
         (
             status = 200,
             description = "Delete proxy success",
-            body = SuccessResponse,
+            body = ProxyRemoval,
             example = json!(
                 {
                     "code": "200 OK",
                     "message": "Deleted proxy successfully",
-                    "data": null,
+                    "data": {
+                        "proxy": {
+                            "_id": "6470a8c3f1d2a9b1c0e4d5f7",
+                            "url": "http://proxy3:3000",
+                            "name": null,
+                            "provider": null,
+                            "region": null,
+                            "environment": null,
+                            "tags": [],
+                            "metadata": {},
+                            "subscriptions": [],
+                            "mode": "enabled",
+                            "protocol": "v2",
+                            "features": ["ops"],
+                            "bootstrap": { "status": "completed", "sent": 350, "total": 350, "error": null },
+                            "status": "unhealthy",
+                            "last_seen": 1685100000000i64,
+                            "consecutive_failures": 12,
+                            "last_error": "error sending request for url (http://proxy3:3000/health)",
+                            "breaker": { "state": "open", "failures": 5, "opened_at": 1685100000000i64 }
+                        },
+                        "cascade": true,
+                        "deliveries": 4,
+                        "dead_letters": 2,
+                        "health_checks": 120
+                    },
                     "error": "",
                 }
             )
@@ -34,7 +93,7 @@ use crate::{request::proxy::delete::DeleteProxyRequest, web::Web, Services, WebR
             example = json!(
                 {
                     "code": "404 Not Found",
-                    "message": "Not found",
+                    "message": "Proxy not found",
                     "data": null,
                     "error": "The url provided cannot be found in the database",
                 }
@@ -44,75 +103,132 @@ use crate::{request::proxy::delete::DeleteProxyRequest, web::Web, Services, WebR
 )]
 pub fn delete_proxy() -> Router<Services> {
     async fn delete_proxy_handler(
-        State(Services { proxy_service, .. }): State<Services>,
+        State(services): State<Services>,
+        DeleteProxyQuery { cascade }: DeleteProxyQuery,
         DeleteProxyRequest { url }: DeleteProxyRequest,
     ) -> WebResult {
-        proxy_service.delete_proxy(&url).await?;
-        Ok(Web::ok("Deleted proxy successfully", ()))
+        let proxy = services.proxy_service.delete_proxy(&url).await?;
+        let removal = remove(services, proxy, cascade).await?;
+        Ok(Web::ok("Deleted proxy successfully", removal))
     }
     Router::new().route("/delete", delete(delete_proxy_handler))
 }
 
+#[utoipa::path(
+    delete,
+    tag = "Proxy",
+    path = "/proxy/{id}",
+    params(
+        ("id" = String, Path, description = "Id of the proxy"),
+        DeleteProxyQuery
+    ),
+    responses(
+        (
+            status = 200,
+            description = "Delete proxy success",
+            body = ProxyRemoval,
+            example = json!(
+                {
+                    "code": "200 OK",
+                    "message": "Deleted proxy successfully",
+                    "data": {
+                        "proxy": {
+                            "_id": "6470a8c3f1d2a9b1c0e4d5f7",
+                            "url": "http://proxy3:3000",
+                            "name": null,
+                            "provider": null,
+                            "region": null,
+                            "environment": null,
+                            "tags": [],
+                            "metadata": {},
+                            "subscriptions": [],
+                            "mode": "maintenance",
+                            "protocol": "v2",
+                            "features": ["ops"],
+                            "bootstrap": { "status": "completed", "sent": 350, "total": 350, "error": null },
+                            "status": "healthy",
+                            "last_seen": 1685100000000i64,
+                            "consecutive_failures": 0,
+                            "last_error": null,
+                            "breaker": { "state": "closed", "failures": 0, "opened_at": null }
+                        },
+                        "cascade": false,
+                        "deliveries": 0,
+                        "dead_letters": 0,
+                        "health_checks": 0
+                    },
+                    "error": "",
+                }
+            )
+        ),
+        (
+            status = 404,
+            description = "Proxy not found",
+            body = ErrorResponse,
+            example = json!(
+                {
+                    "code": "404 Not Found",
+                    "message": "Proxy not found",
+                    "data": null,
+                    "error": "The url provided cannot be found in the database",
+                }
+            )
+        ),
+    )
+)]
+pub fn delete_proxy_by_id() -> Router<Services> {
+    async fn delete_proxy_by_id_handler(
+        State(services): State<Services>,
+        Path(id): Path<String>,
+        DeleteProxyQuery { cascade }: DeleteProxyQuery,
+    ) -> WebResult {
+        let proxy = services.proxy_service.delete_proxy_by_id(&id).await?;
+        let removal = remove(services, proxy, cascade).await?;
+        Ok(Web::ok("Deleted proxy successfully", removal))
+    }
+    Router::new().route("/:id", delete(delete_proxy_by_id_handler))
+}
+
 #[cfg(test)]
 mod tests {
-    use axum_test_helper::TestClient;
-    use mongodb::Collection;
-    use reqwest::{Client, StatusCode};
-    use serde_json::json;
+    use reqwest::StatusCode;
+    use serde_json::{json, Value};
 
-    use crate::{
-        controller::routes, models::proxy::Proxy, mongo::connect_mongo,
-        service::proxy::ProxyService, Services,
-    };
+    use crate::test_support::{fake_proxy::FakeProxy, test_hub};
 
     #[tokio::test]
     async fn delete_proxy_should_success_test() {
-        let proxy_collection: Collection<Proxy> = connect_mongo().await.collection("Proxy");
-
-        let proxy_service = ProxyService::init(&proxy_collection);
-
-        let service = Services {
-            client: Client::new(),
-            proxy_service,
-        };
+        let fake_proxy = FakeProxy::spawn().await;
 
-        let router = routes(service);
-
-        let test_client = TestClient::new(router);
+        let test_client = test_hub().await;
 
         test_client
             .post("/proxy/create")
             .json(&json!(
-                { "url": "http://localhost:3000" }
+                { "url": fake_proxy.url }
             ))
             .send()
             .await;
 
         let response = test_client
-            .delete("/proxy/delete")
+            .delete("/proxy/delete?cascade=true")
             .json(&json!(
-                { "url": "http://localhost:3000" }
+                { "url": fake_proxy.url }
             ))
             .send()
             .await;
 
-        assert_eq!(response.status(), StatusCode::OK)
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let body = response.json::<Value>().await;
+
+        assert_eq!(body["data"]["proxy"]["url"], fake_proxy.url.as_str());
+        assert_eq!(body["data"]["cascade"], true)
     }
 
     #[tokio::test]
     async fn delete_proxy_should_fail_test() {
-        let proxy_collection: Collection<Proxy> = connect_mongo().await.collection("Proxy");
-
-        let proxy_service = ProxyService::init(&proxy_collection);
-
-        let service = Services {
-            client: Client::new(),
-            proxy_service,
-        };
-
-        let router = routes(service);
-
-        let test_client = TestClient::new(router);
+        let test_client = test_hub().await;
 
         let response = test_client
             .delete("/proxy/delete")
@@ -122,6 +238,6 @@ mod tests {
             .send()
             .await;
 
-        assert_eq!(response.status(), StatusCode::OK)
+        assert_eq!(response.status(), StatusCode::NOT_FOUND)
     }
 }
@@ -1,12 +1,16 @@
 use axum::{extract::State, routing::get, Router};
-use futures_util::TryStreamExt;
 
-use crate::{web::Web, Services, WebResult};
+use crate::{
+    request::proxy::{filter::ProxyFilter, lookup::LookupProxyQuery},
+    web::Web,
+    Services, WebResult,
+};
 
 #[utoipa::path(
     get,
     tag = "Proxy",
     path = "/proxy",
+    params(ProxyFilter),
     responses(
         (
             status = 200,
@@ -17,8 +21,46 @@ use crate::{web::Web, Services, WebResult};
                     "code": "200 OK",
                     "message": "Get all proxies successfully",
                     "data": [
-                        { "url": "http://proxy1:1000" },
-                        { "url": "http://proxy2:2000" }
+                        {
+                            "_id": "6470a8c3f1d2a9b1c0e4d5f8",
+                            "url": "http://proxy1:1000",
+                            "name": "eu-west edge",
+                            "provider": "aws",
+                            "region": "eu-west-1",
+                            "environment": "production",
+                            "tags": ["eu", "edge"],
+                            "metadata": { "team": "platform" },
+                            "subscriptions": [{ "pattern": "config.", "types": [] }, { "pattern": "user:*:profile", "types": ["String"] }],
+                            "mode": "enabled",
+                            "protocol": "v2",
+                            "features": ["ops"],
+                            "bootstrap": { "status": "completed", "sent": 350, "total": 350, "error": null },
+                            "status": "healthy",
+                            "last_seen": 1685100000000i64,
+                            "consecutive_failures": 0,
+                            "last_error": null,
+                            "breaker": { "state": "closed", "failures": 0, "opened_at": null }
+                        },
+                        {
+                            "_id": "6470a8c3f1d2a9b1c0e4d5f9",
+                            "url": "http://proxy2:2000",
+                            "name": "us-central edge",
+                            "provider": "gcp",
+                            "region": "us-central1",
+                            "environment": "production",
+                            "tags": ["us", "edge"],
+                            "metadata": {},
+                            "subscriptions": [],
+                            "mode": "enabled",
+                            "protocol": "v2",
+                            "features": ["ops"],
+                            "bootstrap": { "status": "completed", "sent": 350, "total": 350, "error": null },
+                            "status": "unhealthy",
+                            "last_seen": 1685099940000i64,
+                            "consecutive_failures": 6,
+                            "last_error": "operation timed out",
+                            "breaker": { "state": "open", "failures": 5, "opened_at": 1685099950000i64 }
+                        }
                     ],
                     "error": ""
                 }
@@ -29,49 +71,99 @@ use crate::{web::Web, Services, WebResult};
 pub fn get_proxies() -> Router<Services> {
     async fn get_proxies_handler(
         State(Services { proxy_service, .. }): State<Services>,
+        filter: ProxyFilter,
     ) -> WebResult {
-        let proxies = proxy_service
-            .get_proxies()
-            .await?
-            .try_collect::<Vec<_>>()
-            .await?;
+        let proxies = proxy_service.find_proxies(&filter).await?;
 
         Ok(Web::ok("Get all proxies successfully", proxies))
     }
     Router::new().route("/", get(get_proxies_handler))
 }
 
+#[utoipa::path(
+    get,
+    tag = "Proxy",
+    path = "/proxy/lookup",
+    params(LookupProxyQuery),
+    responses(
+        (
+            status = 200,
+            description = "The proxy registered with this url",
+            body = Proxy,
+            example = json!(
+                {
+                    "code": "200 OK",
+                    "message": "Get proxy successfully",
+                    "data": {
+                        "_id": "6470a8c3f1d2a9b1c0e4d5f7",
+                        "url": "http://proxy3:3000",
+                        "name": null,
+                        "provider": null,
+                        "region": null,
+                        "environment": null,
+                        "tags": [],
+                        "metadata": {},
+                        "subscriptions": [],
+                        "mode": "enabled",
+                        "protocol": "v2",
+                        "features": ["ops"],
+                        "bootstrap": { "status": "completed", "sent": 350, "total": 350, "error": null },
+                        "status": "healthy",
+                        "last_seen": 1685100000000i64,
+                        "consecutive_failures": 0,
+                        "last_error": null,
+                        "breaker": { "state": "closed", "failures": 0, "opened_at": null }
+                    },
+                    "error": ""
+                }
+            )
+        ),
+        (
+            status = 404,
+            description = "Proxy not found",
+            body = ErrorResponse,
+            example = json!(
+                {
+                    "code": "404 Not Found",
+                    "message": "Proxy not found",
+                    "data": null,
+                    "error": "The url provided cannot be found in the database",
+                }
+            )
+        )
+    )
+)]
+pub fn lookup_proxy() -> Router<Services> {
+    async fn lookup_proxy_handler(
+        State(Services { proxy_service, .. }): State<Services>,
+        LookupProxyQuery { url }: LookupProxyQuery,
+    ) -> WebResult {
+        let proxy = proxy_service.get_proxy(&url).await?;
+        Ok(Web::ok("Get proxy successfully", proxy))
+    }
+    Router::new().route("/lookup", get(lookup_proxy_handler))
+}
+
 #[cfg(test)]
 mod tests {
-    use axum_test_helper::TestClient;
-    use mongodb::Collection;
-    use reqwest::{Client, StatusCode};
+    use reqwest::StatusCode;
     use serde_json::json;
 
     use crate::{
-        controller::routes, models::proxy::Proxy, mongo::connect_mongo,
-        service::proxy::ProxyService, web::Web, Services,
+        test_support::{fake_proxy::FakeProxy, test_hub},
+        web::Web,
     };
 
     #[tokio::test]
     async fn get_proxies_should_success_test() {
-        let proxy_collection: Collection<Proxy> = connect_mongo().await.collection("Proxy");
-
-        let proxy_service = ProxyService::init(&proxy_collection);
-
-        let service = Services {
-            client: Client::new(),
-            proxy_service,
-        };
-
-        let router = routes(service);
+        let fake_proxy = FakeProxy::spawn().await;
 
-        let test_client = TestClient::new(router);
+        let test_client = test_hub().await;
 
         test_client
             .post("/proxy/create")
             .json(&json!(
-                { "url": "http://localhost:3000" }
+                { "url": fake_proxy.url }
             ))
             .send()
             .await;
@@ -92,9 +184,72 @@ mod tests {
         test_client
             .delete("/proxy/delete")
             .json(&json!(
-                { "url": "http://localhost:3000" }
+                { "url": fake_proxy.url }
             ))
             .send()
             .await;
     }
+
+    #[tokio::test]
+    async fn get_proxies_with_filter_should_success_test() {
+        let fake_proxy = FakeProxy::spawn().await;
+
+        let test_client = test_hub().await;
+
+        test_client
+            .post("/proxy/create")
+            .json(&json!(
+                {
+                    "url": fake_proxy.url,
+                    "provider": "aws",
+                    "region": "eu-west-1",
+                    "tags": ["eu", "edge"]
+                }
+            ))
+            .send()
+            .await;
+
+        let response = test_client
+            .get("/proxy?provider=aws&region=eu-west-1&tags=eu")
+            .send()
+            .await;
+
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let Web { data, .. } = response.json().await;
+        assert!(data
+            .as_array()
+            .unwrap()
+            .iter()
+            .any(|proxy| proxy["url"] == fake_proxy.url.as_str()));
+
+        let response = test_client.get("/proxy?provider=gcp").send().await;
+
+        let Web { data, .. } = response.json().await;
+        assert!(!data
+            .as_array()
+            .unwrap()
+            .iter()
+            .any(|proxy| proxy["url"] == fake_proxy.url.as_str()));
+
+        test_client
+            .delete("/proxy/delete")
+            .json(&json!(
+                { "url": fake_proxy.url }
+            ))
+            .send()
+            .await;
+    }
+
+    #[tokio::test]
+    async fn lookup_proxy_should_fail_test() {
+        let test_client = test_hub().await;
+
+        let response = test_client
+            .get("/proxy/lookup?url=http://invalid")
+            .send()
+            .await;
+
+        assert_eq!(response.status(), StatusCode::NOT_FOUND)
+    }
 }
@@ -1,19 +1,37 @@
 pub mod add;
+pub mod bootstrap;
+pub mod breaker;
 pub mod delete;
 pub mod get;
+pub mod mode;
+pub mod update;
 
 use axum::Router;
 
 use crate::Services;
 
-use self::{add::add_proxy, delete::delete_proxy, get::get_proxies};
+use self::{
+    add::add_proxy,
+    bootstrap::bootstrap_proxy,
+    breaker::reset_breaker,
+    delete::{delete_proxy, delete_proxy_by_id},
+    get::{get_proxies, lookup_proxy},
+    mode::set_proxy_mode,
+    update::update_proxy,
+};
 
 pub fn proxy_routes() -> Router<Services> {
     Router::new().nest(
         "/proxy",
         Router::new()
             .merge(get_proxies())
+            .merge(lookup_proxy())
             .merge(add_proxy())
-            .merge(delete_proxy()),
+            .merge(bootstrap_proxy())
+            .merge(reset_breaker())
+            .merge(delete_proxy())
+            .merge(delete_proxy_by_id())
+            .merge(update_proxy())
+            .merge(set_proxy_mode()),
     )
 }
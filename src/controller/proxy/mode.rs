@@ -0,0 +1,228 @@
+use axum::{
+    extract::{Path, State},
+    routing::put,
+    Router,
+};
+
+use crate::{
+    error::Error,
+    models::proxy::ProxyMode,
+    request::proxy::{mode::SetProxyModeRequest, update::UpdateProxyRequest},
+    web::Web,
+    Services, WebResult,
+};
+
+#[utoipa::path(
+    put,
+    tag = "Proxy",
+    path = "/proxy/{id}/mode",
+    params(
+        ("id" = String, Path, description = "Id of the proxy")
+    ),
+    request_body(
+        content = SetProxyModeRequest,
+        description = "Set proxy mode request",
+        example = json!(
+            { "mode": "maintenance" }
+        )
+    ),
+    responses(
+        (
+            status = 200,
+            description = "Mode changed. Enabling a proxy replays its buffered writes first",
+            body = Proxy,
+            example = json!(
+                {
+                    "code": "200 OK",
+                    "message": "Proxy mode changed successfully",
+                    "data": {
+                        "_id": "6470a8c3f1d2a9b1c0e4d5f7",
+                        "url": "http://proxy3:3000",
+                        "name": "eu-west edge",
+                        "provider": "aws",
+                        "region": "eu-west-1",
+                        "environment": "production",
+                        "tags": ["eu", "edge"],
+                        "metadata": { "team": "platform" },
+                        "subscriptions": [],
+                        "mode": "replaying",
+                        "protocol": "v2",
+                        "features": ["ops"],
+                        "bootstrap": { "status": "completed", "sent": 350, "total": 350, "error": null },
+                        "status": "healthy",
+                        "last_seen": 1685100000000i64,
+                        "consecutive_failures": 0,
+                        "last_error": null,
+                        "breaker": { "state": "closed", "failures": 0, "opened_at": null }
+                    },
+                    "error": ""
+                }
+            )
+        ),
+        (
+            status = 404,
+            description = "Proxy not found",
+            body = ErrorResponse,
+            example = json!(
+                {
+                    "code": "404 Not Found",
+                    "message": "Proxy not found",
+                    "data": null,
+                    "error": "The url provided cannot be found in the database",
+                }
+            )
+        ),
+        (
+            status = 409,
+            description = "Replay already running",
+            body = ErrorResponse,
+            example = json!(
+                {
+                    "code": "409 Conflict",
+                    "message": "Replay in progress",
+                    "data": null,
+                    "error": "The buffered writes of this proxy are already being replayed, please wait for it to finish",
+                }
+            )
+        )
+    )
+)]
+pub fn set_proxy_mode() -> Router<Services> {
+    async fn set_proxy_mode_handler(
+        State(Services {
+            proxy_service,
+            sync_service,
+            ..
+        }): State<Services>,
+        Path(id): Path<String>,
+        SetProxyModeRequest { mode }: SetProxyModeRequest,
+    ) -> WebResult {
+        let proxy = match mode {
+            ProxyMode::Enabled => {
+                let proxy = proxy_service.get_proxy_by_id(&id).await?;
+
+                match proxy.mode {
+                    ProxyMode::Enabled => proxy,
+                    ProxyMode::Replaying => return Err(Error::ReplayInProgress),
+                    from => {
+                        // Only one caller gets to start the replay
+                        if !proxy_service
+                            .transition(&proxy.url, from, ProxyMode::Replaying)
+                            .await?
+                        {
+                            return Err(Error::ReplayInProgress);
+                        }
+
+                        // The proxy may have been upgraded while it was down
+                        let (protocol, features) = sync_service.negotiate(&proxy.url).await;
+                        let update = UpdateProxyRequest {
+                            protocol: Some(protocol),
+                            features: Some(features),
+                            ..Default::default()
+                        };
+                        let proxy = proxy_service.update_proxy(&id, &update).await?;
+
+                        sync_service.spawn_replay(proxy.clone());
+                        proxy
+                    }
+                }
+            }
+            // Writes for the proxy are buffered in the outbox from now on
+            mode => proxy_service.set_mode(&id, mode).await?,
+        };
+
+        Ok(Web::ok("Proxy mode changed successfully", proxy))
+    }
+    Router::new().route("/:id/mode", put(set_proxy_mode_handler))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use reqwest::StatusCode;
+    use serde_json::json;
+    use tokio::time::sleep;
+
+    use crate::{
+        test_support::{add_proxy, fake_proxy::FakeProxy, test_hub},
+        web::Web,
+    };
+
+    #[tokio::test]
+    async fn set_proxy_mode_should_fail_test() {
+        let test_client = test_hub().await;
+
+        let response = test_client
+            .put("/proxy/6470a8c3f1d2a9b1c0e4d5f7/mode")
+            .json(&json!(
+                { "mode": "replaying" }
+            ))
+            .send()
+            .await;
+
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST)
+    }
+
+    #[tokio::test]
+    async fn enable_proxy_should_replay_buffered_writes_test() {
+        let fake_proxy = FakeProxy::spawn().await;
+
+        let test_client = test_hub().await;
+
+        add_proxy(&test_client, &fake_proxy.url).await;
+
+        let lookup = format!("/proxy/lookup?url={}", fake_proxy.url);
+        let Web { data, .. } = test_client.get(&lookup).send().await.json().await;
+        let mode = format!("/proxy/{}/mode", data["_id"].as_str().unwrap());
+
+        let response = test_client
+            .put(&mode)
+            .json(&json!(
+                { "mode": "disabled" }
+            ))
+            .send()
+            .await;
+        assert_eq!(response.status(), StatusCode::OK);
+
+        for value in [1, 2] {
+            test_client
+                .post("/sync")
+                .json(&json!(
+                    { "type": "Number", "key": "test_mode", "value": value }
+                ))
+                .send()
+                .await;
+        }
+        assert!(fake_proxy.sync_calls().is_empty());
+
+        let response = test_client
+            .put(&mode)
+            .json(&json!(
+                { "mode": "enabled" }
+            ))
+            .send()
+            .await;
+        assert_eq!(response.status(), StatusCode::OK);
+
+        // The proxy is enabled again once its buffered writes are replayed
+        let mut enabled = false;
+        for _ in 0..100 {
+            let Web { data, .. } = test_client.get(&lookup).send().await.json().await;
+            if data["mode"] == "enabled" {
+                enabled = true;
+                break;
+            }
+            sleep(Duration::from_millis(10)).await;
+        }
+        assert!(enabled);
+
+        let values = fake_proxy
+            .sync_calls()
+            .iter()
+            .map(|call| call.body["value"].clone())
+            .collect::<Vec<_>>();
+        assert_eq!(values, vec![json!(1), json!(2)]);
+        assert_eq!(fake_proxy.value("test_mode"), Some(json!(2)));
+    }
+}
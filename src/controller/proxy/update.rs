@@ -0,0 +1,202 @@
+use axum::{
+    extract::{Path, State},
+    routing::patch,
+    Router,
+};
+
+use crate::{
+    error::Error, models::proxy::BreakerState, request::proxy::update::UpdateProxyRequest,
+    web::Web, Services, WebResult,
+};
+
+#[utoipa::path(
+    patch,
+    tag = "Proxy",
+    path = "/proxy/{id}",
+    params(
+        ("id" = String, Path, description = "Id of the proxy")
+    ),
+    request_body(
+        content = UpdateProxyRequest,
+        description = "Update proxy request, only the given fields are changed. Name, provider, region and environment are cleared when sent as null",
+        example = json!(
+            {
+                "url": "http://proxy3:3001",
+                "environment": null,
+                "tags": ["eu", "edge", "canary"],
+                "subscriptions": [{ "pattern": "config.", "types": [] }]
+            }
+        )
+    ),
+    responses(
+        (
+            status = 200,
+            description = "Proxy updated",
+            body = Proxy,
+            example = json!(
+                {
+                    "code": "200 OK",
+                    "message": "Proxy updated successfully",
+                    "data": {
+                        "_id": "6470a8c3f1d2a9b1c0e4d5f7",
+                        "url": "http://proxy3:3001",
+                        "name": "eu-west edge",
+                        "provider": "aws",
+                        "region": "eu-west-1",
+                        "environment": "production",
+                        "tags": ["eu", "edge", "canary"],
+                        "metadata": { "team": "platform" },
+                        "subscriptions": [{ "pattern": "config.", "types": [] }],
+                        "mode": "enabled",
+                        "protocol": "v2",
+                        "features": ["ops"],
+                        "bootstrap": { "status": "completed", "sent": 350, "total": 350, "error": null },
+                        "status": "healthy",
+                        "last_seen": 1685100000000i64,
+                        "consecutive_failures": 0,
+                        "last_error": null,
+                        "breaker": { "state": "closed", "failures": 0, "opened_at": null }
+                    },
+                    "error": ""
+                }
+            )
+        ),
+        (
+            status = 400,
+            description = "New url unreachable or already registered",
+            body = ErrorResponse,
+            example = json!(
+                {
+                    "code": "400 Bad Request",
+                    "message": "Request to one proxy error",
+                    "data": null,
+                    "error": "The proxy provided is unreachable"
+                }
+            )
+        ),
+        (
+            status = 404,
+            description = "Proxy not found",
+            body = ErrorResponse,
+            example = json!(
+                {
+                    "code": "404 Not Found",
+                    "message": "Proxy not found",
+                    "data": null,
+                    "error": "The url provided cannot be found in the database",
+                }
+            )
+        )
+    )
+)]
+pub fn update_proxy() -> Router<Services> {
+    async fn update_proxy_handler(
+        State(Services {
+            client,
+            proxy_service,
+            sync_service,
+            ..
+        }): State<Services>,
+        Path(id): Path<String>,
+        mut request: UpdateProxyRequest,
+    ) -> WebResult {
+        let proxy = proxy_service.get_proxy_by_id(&id).await?;
+
+        if let Some(url) = request.url.as_ref().filter(|url| **url != proxy.url) {
+            // Same checks as when adding a proxy
+            client
+                .get(format!("{url}/health"))
+                .send()
+                .await
+                .map_err(|_| Error::CannotReachProxy)?;
+
+            if proxy_service.get_proxy(url).await.is_ok() {
+                return Err(Error::ProxyAlreadyExists);
+            }
+
+            let (protocol, features) = sync_service.negotiate(url).await;
+            request.protocol = Some(protocol);
+            request.features = Some(features);
+        }
+
+        // Writes still queued for the old url follow the proxy
+        let updated = sync_service.update_proxy(&id, &request).await?;
+
+        // Send the keys the proxy is now meant to hold, and remove the others
+        if request.changes_routing()
+            && updated.is_ready()
+            && updated.breaker.state == BreakerState::Closed
+        {
+            sync_service.spawn_reconcile(updated.clone());
+        }
+
+        Ok(Web::ok("Proxy updated successfully", updated))
+    }
+    Router::new().route("/:id", patch(update_proxy_handler))
+}
+
+#[cfg(test)]
+mod tests {
+    use reqwest::StatusCode;
+    use serde_json::json;
+
+    use crate::{
+        test_support::{fake_proxy::FakeProxy, test_hub},
+        web::Web,
+    };
+
+    #[tokio::test]
+    async fn update_proxy_should_fail_test() {
+        let test_client = test_hub().await;
+
+        let response = test_client
+            .patch("/proxy/6470a8c3f1d2a9b1c0e4d5f7")
+            .json(&json!(
+                { "name": "renamed" }
+            ))
+            .send()
+            .await;
+
+        assert_eq!(response.status(), StatusCode::NOT_FOUND)
+    }
+
+    #[tokio::test]
+    async fn update_proxy_with_null_should_clear_the_field_test() {
+        let fake_proxy = FakeProxy::spawn().await;
+
+        let test_client = test_hub().await;
+
+        let response = test_client
+            .post("/proxy/create")
+            .json(&json!(
+                { "url": fake_proxy.url, "name": "edge", "region": "eu-west-1" }
+            ))
+            .send()
+            .await;
+        let Web { data, .. } = response.json().await;
+        let path = format!("/proxy/{}", data["_id"].as_str().unwrap());
+
+        let response = test_client
+            .patch(&path)
+            .json(&json!(
+                { "name": "" }
+            ))
+            .send()
+            .await;
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+        // A missing field is left alone, a null one is cleared
+        let response = test_client
+            .patch(&path)
+            .json(&json!(
+                { "name": null }
+            ))
+            .send()
+            .await;
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let Web { data, .. } = response.json().await;
+        assert_eq!(data["name"], json!(null));
+        assert_eq!(data["region"], "eu-west-1");
+    }
+}
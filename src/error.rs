@@ -1,4 +1,7 @@
-use axum::{extract::rejection::JsonRejection, response::IntoResponse};
+use axum::{
+    extract::rejection::{JsonRejection, QueryRejection},
+    response::IntoResponse,
+};
 use thiserror::Error;
 use validator::ValidationErrors;
 
@@ -12,9 +15,15 @@ pub enum Error {
     #[error("Json request parse error")]
     Json(#[from] JsonRejection),
 
+    #[error("Query string parse error")]
+    QueryString(#[from] QueryRejection),
+
     #[error("Database query error")]
     Query(#[from] mongodb::error::Error),
 
+    #[error("SQLite query error")]
+    Sqlite(#[from] rusqlite::Error),
+
     #[error("Request error")]
     CannotReachProxies(#[from] reqwest::Error),
 
@@ -33,6 +42,24 @@ pub enum Error {
     #[error("Cannot delete proxy")]
     CannotDeleteProxy,
 
+    #[error("Bootstrap in progress")]
+    BootstrapInProgress,
+
+    #[error("Replay in progress")]
+    ReplayInProgress,
+
+    #[error("Dead letter not found")]
+    DeadLetterNotFound,
+
+    #[error("Data not found")]
+    DataNotFound,
+
+    #[error("Wrong data type")]
+    WrongDataType,
+
+    #[error("Version conflict")]
+    VersionConflict,
+
     #[error("Invalid input")]
     InvalidInput(#[from] ValidationErrors),
 }
@@ -45,7 +72,11 @@ impl IntoResponse for Error {
                 "Invalid request body",
                 "The request body sent to the server was incorrect.",
             ),
-            Error::Query(_) => Web::bad_request(
+            Error::QueryString(_) => Web::bad_request(
+                "Invalid query string",
+                "The query parameters sent to the server were incorrect.",
+            ),
+            Error::Query(_) | Error::Sqlite(_) => Web::bad_request(
                 "Database query error",
                 "The information provided could not be queried.",
             ),
@@ -73,6 +104,30 @@ impl IntoResponse for Error {
                 "Proxy not found",
                 "The url provided cannot be found in the database",
             ),
+            Error::BootstrapInProgress => Web::conflict(
+                "Bootstrap in progress",
+                "This proxy is already being bootstrapped, please wait for it to finish",
+            ),
+            Error::ReplayInProgress => Web::conflict(
+                "Replay in progress",
+                "The buffered writes of this proxy are already being replayed, please wait for it to finish",
+            ),
+            Error::DeadLetterNotFound => Web::not_found(
+                "Dead letter not found",
+                "The id provided cannot be found in the dead letters",
+            ),
+            Error::DataNotFound => Web::not_found(
+                "Data not found",
+                "The key provided cannot be found in the database",
+            ),
+            Error::WrongDataType => Web::conflict(
+                "Wrong data type",
+                "The key holds a value of another type",
+            ),
+            Error::VersionConflict => Web::conflict(
+                "Version conflict",
+                "The key is not at the version expected by the request",
+            ),
             Error::InvalidInput(e) => {
                 Web::bad_request("Invalid input", extract_validation_error(&e))
             }
@@ -0,0 +1,26 @@
+use mongodb::bson::{doc, oid::ObjectId, Document};
+use serde::{Deserialize, Deserializer};
+
+// Reads an `_id` stored either as an ObjectId, like the documents inserted
+// before ids were generated by the service, or as its hex string
+pub fn deserialize_id<'de, D: Deserializer<'de>>(deserializer: D) -> Result<String, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Id {
+        ObjectId(ObjectId),
+        Hex(String),
+    }
+
+    Ok(match Id::deserialize(deserializer)? {
+        Id::ObjectId(id) => id.to_hex(),
+        Id::Hex(id) => id,
+    })
+}
+
+// Matches a document by id, whichever way its `_id` is stored
+pub fn id_filter(id: &str) -> Document {
+    match ObjectId::parse_str(id) {
+        Ok(object_id) => doc! {"_id": {"$in": [object_id, id]}},
+        Err(_) => doc! {"_id": id},
+    }
+}
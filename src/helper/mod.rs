@@ -1 +1,7 @@
+pub mod id;
+pub mod mongo;
+pub mod nullable;
+pub mod ordering;
+pub mod pattern;
+pub mod url;
 pub mod validation;
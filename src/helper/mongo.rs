@@ -0,0 +1,10 @@
+use mongodb::error::{Error, ErrorKind, WriteFailure};
+
+// Whether a write was refused by a unique index
+pub fn is_duplicate_key(error: &Error) -> bool {
+    match error.kind.as_ref() {
+        ErrorKind::Write(WriteFailure::WriteError(e)) => e.code == 11000,
+        ErrorKind::Command(e) => e.code == 11000,
+        _ => false,
+    }
+}
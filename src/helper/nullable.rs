@@ -0,0 +1,11 @@
+use serde::{Deserialize, Deserializer};
+
+// Tells a field sent as `null` apart from a missing one. Used along with
+// `#[serde(default)]`, a missing field stays None and a `null` one is Some(None)
+pub fn deserialize_nullable<'de, T, D>(deserializer: D) -> Result<Option<Option<T>>, D::Error>
+where
+    T: Deserialize<'de>,
+    D: Deserializer<'de>,
+{
+    Option::<T>::deserialize(deserializer).map(Some)
+}
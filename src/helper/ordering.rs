@@ -0,0 +1,147 @@
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+};
+
+use futures_util::{
+    future::{join_all, Shared},
+    FutureExt,
+};
+use tokio::sync::oneshot;
+
+// Resolves once the turn that holds it is over
+type Done = Shared<oneshot::Receiver<()>>;
+
+// A key on a lane
+type Tail = (String, String);
+
+// Lines up the writes of each key on a lane (a proxy, or the hub itself) so that
+// they go through it one after another, in the order they entered. Writes of
+// other keys, or on other lanes, do not wait for each other
+#[derive(Clone, Default)]
+pub struct KeyQueues {
+    // The last turn taken for every lane and key, with the id of that turn
+    tails: Arc<Mutex<HashMap<Tail, (u64, Done)>>>,
+    next_id: Arc<AtomicU64>,
+}
+
+impl KeyQueues {
+    // Takes a place behind the turns already taken for these keys on the lane.
+    // The place is taken right away, waiting for it is up to the caller
+    pub fn enter(&self, lane: &str, keys: &[String]) -> Turn {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let (done, receiver) = oneshot::channel();
+        let receiver = receiver.shared();
+
+        let mut tails = self.tails.lock().unwrap();
+        let ahead = keys
+            .iter()
+            .filter_map(|key| {
+                tails
+                    .insert((lane.into(), key.clone()), (id, receiver.clone()))
+                    .map(|(_, done)| done)
+            })
+            .collect();
+
+        Turn {
+            queues: self.clone(),
+            lane: lane.into(),
+            keys: keys.to_vec(),
+            id,
+            ahead,
+            _done: done,
+        }
+    }
+}
+
+// A place in the queues of some keys, given up when dropped
+pub struct Turn {
+    queues: KeyQueues,
+    lane: String,
+    keys: Vec<String>,
+    id: u64,
+    ahead: Vec<Done>,
+    // Dropping it lets the next turns through, also when this one is given up halfway
+    _done: oneshot::Sender<()>,
+}
+
+impl Turn {
+    // Waits until every turn taken before this one on the same keys is over
+    pub async fn wait(&mut self) {
+        join_all(std::mem::take(&mut self.ahead)).await;
+    }
+}
+
+impl Drop for Turn {
+    fn drop(&mut self) {
+        // Keys that nobody queued behind are forgotten, so that the queues do not grow
+        let mut tails = self.queues.tails.lock().unwrap();
+        for key in &self.keys {
+            let tail = (self.lane.clone(), key.clone());
+            if tails.get(&tail).is_some_and(|(id, _)| *id == self.id) {
+                tails.remove(&tail);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        sync::{Arc, Mutex},
+        time::Duration,
+    };
+
+    use tokio::time::{sleep, timeout};
+
+    use super::KeyQueues;
+
+    #[tokio::test]
+    async fn key_queues_should_keep_the_order_of_each_key() {
+        let queues = KeyQueues::default();
+        let order = Arc::new(Mutex::new(vec![]));
+
+        let keys = |keys: &[&str]| keys.iter().map(|key| key.to_string()).collect::<Vec<_>>();
+        // The first turn is the slowest, so only the queue can keep `b` behind it
+        let turns = [
+            ("a", keys(&["k1"]), 50),
+            ("b", keys(&["k1", "k2"]), 0),
+            ("c", keys(&["k3"]), 0),
+        ];
+
+        let tasks = turns.map(|(name, keys, delay)| {
+            let mut turn = queues.enter("proxy", &keys);
+            let order = order.clone();
+            tokio::spawn(async move {
+                turn.wait().await;
+                sleep(Duration::from_millis(delay)).await;
+                order.lock().unwrap().push(name);
+            })
+        });
+        for task in tasks {
+            task.await.unwrap();
+        }
+
+        // `c` shares no key with `a`, so it did not wait for it
+        assert_eq!(*order.lock().unwrap(), vec!["c", "a", "b"]);
+        assert!(queues.tails.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn key_queues_should_keep_lanes_apart() {
+        let queues = KeyQueues::default();
+        let keys = vec!["k1".to_string()];
+
+        let _first = queues.enter("proxy-1", &keys);
+        let mut other_lane = queues.enter("proxy-2", &keys);
+        let mut same_lane = queues.enter("proxy-1", &keys);
+
+        other_lane.wait().await;
+        assert!(timeout(Duration::from_millis(20), same_lane.wait())
+            .await
+            .is_err());
+    }
+}
@@ -0,0 +1,60 @@
+// Matches a key against a subscription pattern.
+// Patterns with `*` (any run of characters) or `?` (any one character) are globs
+// that must match the whole key, other patterns are plain key prefixes
+pub fn matches_pattern(pattern: &str, key: &str) -> bool {
+    if !pattern.contains(['*', '?']) {
+        return key.starts_with(pattern);
+    }
+
+    let pattern = pattern.chars().collect::<Vec<_>>();
+    let key = key.chars().collect::<Vec<_>>();
+
+    // Position of the last `*` in the pattern, and of the key when it was reached
+    let mut star = None;
+    let (mut p, mut k) = (0, 0);
+
+    while k < key.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                star = Some((p, k));
+                p += 1;
+            }
+            Some('?') => {
+                p += 1;
+                k += 1;
+            }
+            Some(c) if *c == key[k] => {
+                p += 1;
+                k += 1;
+            }
+            // Let the last `*` swallow one more character
+            _ => match star {
+                Some((star_p, star_k)) => {
+                    star = Some((star_p, star_k + 1));
+                    p = star_p + 1;
+                    k = star_k + 1;
+                }
+                None => return false,
+            },
+        }
+    }
+
+    pattern[p..].iter().all(|c| *c == '*')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::matches_pattern;
+
+    #[test]
+    fn matches_pattern_should_handle_prefixes_and_globs() {
+        assert!(matches_pattern("config.", "config.flags"));
+        assert!(!matches_pattern("config.", "session.1"));
+        assert!(matches_pattern("user:*:profile", "user:42:profile"));
+        assert!(!matches_pattern("user:*:profile", "user:42:settings"));
+        assert!(matches_pattern("flag_?", "flag_a"));
+        assert!(!matches_pattern("flag_?", "flag_ab"));
+        assert!(matches_pattern("*", ""));
+        assert!(matches_pattern("a*b*c", "aXbYbZc"));
+    }
+}
@@ -0,0 +1,29 @@
+use reqwest::Url;
+
+// Gives every spelling of a proxy url the same form: lowercase scheme and host,
+// no default port and no trailing slash. Urls that cannot be parsed are left
+// as they are, for the validators to reject
+pub fn normalize_url(url: &str) -> String {
+    match Url::parse(url.trim()) {
+        Ok(url) => url.as_str().trim_end_matches('/').into(),
+        Err(_) => url.into(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::normalize_url;
+
+    #[test]
+    fn normalize_url_should_unify_spellings() {
+        assert_eq!(normalize_url("http://proxy:3000/"), "http://proxy:3000");
+        assert_eq!(normalize_url("HTTP://Proxy:3000"), "http://proxy:3000");
+        assert_eq!(normalize_url("http://proxy:80/"), "http://proxy");
+        assert_eq!(normalize_url("https://proxy:443"), "https://proxy");
+        assert_eq!(
+            normalize_url("http://proxy:3000/edge/"),
+            "http://proxy:3000/edge"
+        );
+        assert_eq!(normalize_url("not a url"), "not a url");
+    }
+}
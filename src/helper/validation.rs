@@ -1,4 +1,13 @@
-use validator::ValidationErrors;
+use validator::{ValidationError, ValidationErrors};
+
+use crate::error::Error;
+
+// Reports a check made by hand the same way as the derived validations
+pub fn invalid(field: &'static str, error: ValidationError) -> Error {
+    let mut errors = ValidationErrors::new();
+    errors.add(field, error);
+    errors.into()
+}
 
 pub fn extract_validation_error(e: &ValidationErrors) -> String {
     let mut message = "".to_string();
@@ -6,21 +6,34 @@ use axum::response::Response;
 use controller::routes;
 use dotenvy::var;
 use error::Error;
-use models::proxy::Proxy;
-use mongodb::Collection;
+use mongodb::Database;
 use reqwest::Client;
-use service::proxy::ProxyService;
+use service::{
+    data::DataService, dead_letter::DeadLetterService, health::HealthService,
+    outbox::OutboxService, proxy::ProxyService, sync::SyncService,
+};
+use store::Stores;
 
-use crate::mongo::connect_mongo;
+use crate::{
+    config::{
+        ExpiryConfig, HealthConfig, OutboxConfig, ReconcileConfig, StoreConfig, SyncConfig,
+    },
+    mongo::connect_mongo,
+};
 pub mod controller;
 
+mod config;
 mod error;
 mod helper;
 mod models;
 mod mongo;
 mod request;
 mod service;
+mod store;
+#[cfg(test)]
+mod test_support;
 mod web;
+mod worker;
 
 type WebResult = std::result::Result<Response, Error>;
 
@@ -28,18 +41,106 @@ type WebResult = std::result::Result<Response, Error>;
 pub struct Services {
     pub client: Client,
     pub proxy_service: ProxyService,
+    pub sync_service: SyncService,
+    pub data_service: DataService,
+    pub dead_letter_service: DeadLetterService,
+    pub health_service: HealthService,
+}
+
+impl Services {
+    pub async fn init(database: &Database, client: Client) -> Self {
+        let stores = Stores::init(&StoreConfig::from_env(), database).await;
+        Self::with_stores(&stores, client)
+    }
+
+    // Same as init, with the stores given by the caller
+    pub fn with_stores(stores: &Stores, client: Client) -> Self {
+        Self::with_config(
+            stores,
+            client,
+            &SyncConfig::from_env(),
+            &OutboxConfig::from_env(),
+        )
+    }
+
+    // Same as with_stores, with the sync and outbox settings given by the caller
+    pub fn with_config(
+        stores: &Stores,
+        client: Client,
+        sync_config: &SyncConfig,
+        outbox_config: &OutboxConfig,
+    ) -> Self {
+        let proxy_service = ProxyService::init(&stores.proxies);
+        let data_service = DataService::init(&stores.data);
+        let outbox_service = OutboxService::init(&stores.outbox, outbox_config);
+        let dead_letter_service = DeadLetterService::init(&stores.dead_letters);
+        let health_service = HealthService::init(
+            &client,
+            &HealthConfig::from_env(),
+            &stores.health,
+            &proxy_service,
+        );
+        let sync_service = SyncService::init(
+            &client,
+            sync_config,
+            &proxy_service,
+            &data_service,
+            &outbox_service,
+            &dead_letter_service,
+        );
+
+        Self {
+            client,
+            proxy_service,
+            sync_service,
+            data_service,
+            dead_letter_service,
+            health_service,
+        }
+    }
 }
 
 #[tokio::main]
 async fn main() {
-    let proxy_collection: Collection<Proxy> = connect_mongo().await.collection("Proxy");
+    let database = connect_mongo().await;
+
+    // A proxy that hangs would otherwise hold its writes, and never trip its breaker
+    let client = Client::builder()
+        .timeout(SyncConfig::from_env().request_timeout)
+        .build()
+        .expect("Cannot build the HTTP client");
+
+    let service = Services::init(&database, client).await;
+
+    // Deliver the sync operations that are still pending, in the background
+    tokio::spawn(worker::outbox::dispatch(
+        service.sync_service.clone(),
+        OutboxConfig::from_env(),
+    ));
+
+    // Finish the bootstraps interrupted by a restart
+    tokio::spawn(worker::bootstrap::resume(
+        service.proxy_service.clone(),
+        service.sync_service.clone(),
+    ));
+
+    // Keep the health of every proxy up to date
+    tokio::spawn(worker::health::monitor(
+        service.health_service.clone(),
+        HealthConfig::from_env(),
+    ));
 
-    let proxy_service = ProxyService::init(&proxy_collection);
+    // Delete the expired keys from every proxy
+    tokio::spawn(worker::expiry::expire(
+        service.sync_service.clone(),
+        ExpiryConfig::from_env(),
+    ));
 
-    let service = Services {
-        client: Client::new(),
-        proxy_service,
-    };
+    // Repair the proxies that drifted from the hub
+    tokio::spawn(worker::reconcile::reconcile(
+        service.sync_service.clone(),
+        ReconcileConfig::from_env(),
+    ));
 
     let router = routes(service);
 
@@ -0,0 +1,178 @@
+use std::collections::HashSet;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use utoipa::ToSchema;
+use validator::ValidationError;
+
+use crate::{models::proxy::Proxy, request::data::target::TargetSelector};
+
+// This model is used to keep the authoritative copy of the synced data in the mongodb database
+
+#[derive(Clone, Serialize, Deserialize, ToSchema)]
+pub struct Data {
+    pub key: String,
+    #[serde(rename = "type")]
+    pub _type: DataType,
+    pub value: Value,
+    // Time to live in seconds, if the key is set to expire
+    #[serde(default)]
+    pub ttl: Option<i64>,
+    // Unix timestamp in milliseconds at which the key expires
+    #[serde(default)]
+    pub expires_at: Option<i64>,
+    // Seconds left before the key expires, only filled in when the key is read
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub remaining_ttl: Option<i64>,
+    // Goes up by one with every write to the key
+    #[serde(default)]
+    pub version: i64,
+    // Unix timestamp in milliseconds
+    pub updated_at: i64,
+    // The proxies this key was last written to, all of them if not set
+    #[serde(default)]
+    pub targets: Option<TargetSelector>,
+}
+
+impl Data {
+    // Whether the key is meant to be held by the proxy
+    pub fn routes_to(&self, proxy: &Proxy) -> bool {
+        self.targets
+            .as_ref()
+            .is_none_or(|targets| targets.matches(proxy))
+            && proxy.wants(&self.key, self._type)
+    }
+
+    // Fills in the seconds left before the key expires, rounded up
+    pub fn with_remaining_ttl(mut self, now: i64) -> Self {
+        self.remaining_ttl = self
+            .expires_at
+            .map(|expires_at| (expires_at - now + 999).div_euclid(1000).max(0));
+        self
+    }
+}
+
+// The kinds of value a key can hold
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, ToSchema)]
+pub enum DataType {
+    String,
+    Number,
+    // A JSON object
+    Json,
+    List,
+    // A list without duplicates
+    Set,
+    // A JSON object of scalar fields
+    Hash,
+    // An integer
+    Counter,
+    // Keys of any type written together, only used by bulk writes
+    Multi,
+}
+
+impl DataType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DataType::String => "String",
+            DataType::Number => "Number",
+            DataType::Json => "Json",
+            DataType::List => "List",
+            DataType::Set => "Set",
+            DataType::Hash => "Hash",
+            DataType::Counter => "Counter",
+            DataType::Multi => "Multi",
+        }
+    }
+
+    // Whether the value can be held by a key of this type
+    pub fn check(&self, value: &Value) -> Result<(), ValidationError> {
+        let (valid, message) = match self {
+            DataType::String => (value.is_string(), "Value must be a string"),
+            DataType::Number => (value.is_number(), "Value must be a number"),
+            DataType::Json => (value.is_object(), "Value must be a JSON object"),
+            DataType::List => (value.is_array(), "Value must be a list"),
+            DataType::Set => (
+                value.as_array().is_some_and(|items| {
+                    let mut seen = HashSet::new();
+                    items.iter().all(|item| seen.insert(item.to_string()))
+                }),
+                "Value must be a list without duplicates",
+            ),
+            DataType::Hash => (
+                value.as_object().is_some_and(|fields| {
+                    fields
+                        .values()
+                        .all(|field| !field.is_object() && !field.is_array())
+                }),
+                "Value must be an object of scalar fields",
+            ),
+            DataType::Counter => (value.is_i64(), "Value must be an integer"),
+            DataType::Multi => (true, ""),
+        };
+
+        if valid {
+            return Ok(());
+        }
+
+        let mut error = ValidationError::new("value");
+        error.message = Some(message.into());
+        Err(error)
+    }
+
+    // The type a value is stored with when none is given. Counters, sets and hashes
+    // look like numbers, lists and objects, so they have to be asked for
+    pub fn of(value: &Value) -> Result<Self, ValidationError> {
+        match value {
+            Value::String(_) => Ok(DataType::String),
+            Value::Number(_) => Ok(DataType::Number),
+            Value::Object(_) => Ok(DataType::Json),
+            Value::Array(_) => Ok(DataType::List),
+            Value::Bool(_) | Value::Null => {
+                let mut error = ValidationError::new("value");
+                error.message = Some("Value must be a string, number, object or list".into());
+                Err(error)
+            }
+        }
+    }
+}
+
+// One page of the key listing
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct DataPage {
+    pub items: Vec<Data>,
+    pub page: u64,
+    pub limit: u64,
+    pub total: u64,
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::DataType;
+
+    #[test]
+    fn data_type_should_check_values() {
+        assert!(DataType::String.check(&json!("hello")).is_ok());
+        assert!(DataType::String
+            .check(&json!({ "hello": "world" }))
+            .is_err());
+        assert!(DataType::Counter.check(&json!(3)).is_ok());
+        assert!(DataType::Counter.check(&json!(3.5)).is_err());
+        assert!(DataType::Set.check(&json!([1, 2])).is_ok());
+        assert!(DataType::Set.check(&json!([1, 1])).is_err());
+        assert!(DataType::Hash.check(&json!({ "a": 1, "b": "x" })).is_ok());
+        assert!(DataType::Hash.check(&json!({ "a": [1] })).is_err());
+        assert!(DataType::Multi.check(&json!(null)).is_ok());
+    }
+
+    #[test]
+    fn data_type_should_follow_values() {
+        assert_eq!(DataType::of(&json!("hello")).unwrap(), DataType::String);
+        assert_eq!(DataType::of(&json!(3)).unwrap(), DataType::Number);
+        assert_eq!(DataType::of(&json!({ "a": 1 })).unwrap(), DataType::Json);
+        assert_eq!(DataType::of(&json!([1])).unwrap(), DataType::List);
+        assert!(DataType::of(&json!(null)).is_err());
+    }
+}
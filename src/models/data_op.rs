@@ -0,0 +1,136 @@
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use utoipa::ToSchema;
+
+// A change made to the value of a key in place. It is sent to the proxies as is,
+// instead of the whole new value, so that concurrent changes add up
+// rather than the last one winning
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub enum DataOp {
+    // Adds to a counter, which starts from 0
+    Incr { by: i64 },
+    // Takes away from a counter, which starts from 0
+    Decr { by: i64 },
+    // Adds an item at the end of a list
+    Append { value: Value },
+    // Adds an item at the start of a list
+    Prepend { value: Value },
+    // Adds a member to a set, unless it is already in it
+    SetAdd { member: Value },
+    SetRemove { member: Value },
+    // Sets one field of a hash
+    HashSet { field: String, value: Value },
+    HashDelete { field: String },
+}
+
+impl DataOp {
+    // The value of the key once changed, or `None` if the key still does not exist.
+    // Fails when the current value cannot take this change
+    pub fn apply(&self, current: Option<&Value>) -> Result<Option<Value>, String> {
+        let mismatch = || Err(format!("Cannot apply {} to {current:?}", self.name()));
+
+        let value = match (self, current) {
+            (DataOp::Incr { by }, None) => Value::from(*by),
+            (DataOp::Decr { by }, None) => Value::from(-by),
+            (DataOp::Incr { by }, Some(Value::Number(n))) if n.is_i64() => {
+                Value::from(n.as_i64().unwrap_or_default().saturating_add(*by))
+            }
+            (DataOp::Decr { by }, Some(Value::Number(n))) if n.is_i64() => {
+                Value::from(n.as_i64().unwrap_or_default().saturating_sub(*by))
+            }
+            (DataOp::Append { value } | DataOp::Prepend { value }, None) => {
+                Value::Array(vec![value.clone()])
+            }
+            (DataOp::Append { value }, Some(Value::Array(items))) => {
+                let mut items = items.clone();
+                items.push(value.clone());
+                Value::Array(items)
+            }
+            (DataOp::Prepend { value }, Some(Value::Array(items))) => {
+                let mut items = items.clone();
+                items.insert(0, value.clone());
+                Value::Array(items)
+            }
+            (DataOp::SetAdd { member }, None) => Value::Array(vec![member.clone()]),
+            (DataOp::SetAdd { member }, Some(Value::Array(members))) => {
+                let mut members = members.clone();
+                if !members.contains(member) {
+                    members.push(member.clone());
+                }
+                Value::Array(members)
+            }
+            (DataOp::SetRemove { member }, Some(Value::Array(members))) => {
+                Value::Array(members.iter().filter(|m| *m != member).cloned().collect())
+            }
+            (DataOp::HashSet { field, value }, None) => {
+                Value::Object(Map::from_iter([(field.clone(), value.clone())]))
+            }
+            (DataOp::HashSet { field, value }, Some(Value::Object(fields))) => {
+                let mut fields = fields.clone();
+                fields.insert(field.clone(), value.clone());
+                Value::Object(fields)
+            }
+            (DataOp::HashDelete { field }, Some(Value::Object(fields))) => {
+                let mut fields = fields.clone();
+                fields.remove(field);
+                Value::Object(fields)
+            }
+            // Removing from a key that does not exist leaves it that way
+            (DataOp::SetRemove { .. } | DataOp::HashDelete { .. }, None) => return Ok(None),
+            _ => return mismatch(),
+        };
+
+        Ok(Some(value))
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            DataOp::Incr { .. } => "incr",
+            DataOp::Decr { .. } => "decr",
+            DataOp::Append { .. } => "append",
+            DataOp::Prepend { .. } => "prepend",
+            DataOp::SetAdd { .. } => "set_add",
+            DataOp::SetRemove { .. } => "set_remove",
+            DataOp::HashSet { .. } => "hash_set",
+            DataOp::HashDelete { .. } => "hash_delete",
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::DataOp;
+
+    #[test]
+    fn data_op_should_change_values_in_place() {
+        let incr = DataOp::Incr { by: 2 };
+        assert_eq!(incr.apply(None), Ok(Some(json!(2))));
+        assert_eq!(incr.apply(Some(&json!(3))), Ok(Some(json!(5))));
+        assert!(incr.apply(Some(&json!("3"))).is_err());
+
+        let prepend = DataOp::Prepend { value: json!(0) };
+        assert_eq!(prepend.apply(Some(&json!([1]))), Ok(Some(json!([0, 1]))));
+
+        let set_add = DataOp::SetAdd { member: json!("a") };
+        assert_eq!(set_add.apply(Some(&json!(["a"]))), Ok(Some(json!(["a"]))));
+
+        let hash_delete = DataOp::HashDelete { field: "a".into() };
+        assert_eq!(
+            hash_delete.apply(Some(&json!({ "a": 1, "b": 2 }))),
+            Ok(Some(json!({ "b": 2 })))
+        );
+        assert_eq!(hash_delete.apply(None), Ok(None));
+    }
+
+    #[test]
+    fn data_op_should_be_tagged_by_its_name() {
+        let op =
+            serde_json::from_value::<DataOp>(json!({ "op": "hash_set", "field": "a", "value": 1 }))
+                .unwrap();
+        assert_eq!(op.name(), "hash_set");
+    }
+}
@@ -0,0 +1,48 @@
+use mongodb::bson::{oid::ObjectId, DateTime};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use super::{data::DataType, operation::SyncOperation};
+
+// This model is used to keep the deliveries that ran out of retries in the mongodb database
+
+#[derive(Clone, Serialize, Deserialize, ToSchema)]
+pub struct DeadLetter {
+    #[serde(rename = "_id")]
+    pub id: String,
+    pub url: String,
+    #[schema(value_type = Object)]
+    pub operation: SyncOperation,
+    pub keys: Vec<String>,
+    #[serde(rename = "type")]
+    pub _type: DataType,
+    pub attempts: u32,
+    pub error: Option<String>,
+    // Unix timestamp in milliseconds
+    pub failed_at: i64,
+    // Envelope id of the failed delivery, sent again when it is replayed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub delivery_id: Option<String>,
+}
+
+impl DeadLetter {
+    pub fn new(
+        url: &str,
+        operation: &SyncOperation,
+        delivery_id: &str,
+        attempts: u32,
+        error: Option<String>,
+    ) -> Self {
+        Self {
+            id: ObjectId::new().to_hex(),
+            url: url.into(),
+            keys: operation.keys(),
+            _type: operation.data_type(),
+            operation: operation.clone(),
+            attempts,
+            error,
+            failed_at: DateTime::now().timestamp_millis(),
+            delivery_id: Some(delivery_id.into()),
+        }
+    }
+}
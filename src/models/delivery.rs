@@ -0,0 +1,62 @@
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+// This model describes the outcome of sending one request to one proxy
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct Delivery {
+    pub url: String,
+    pub status: Option<u16>,
+    pub latency_ms: u64,
+    pub attempts: u32,
+    pub error: Option<String>,
+    // The proxy is not ready yet, the request waits in the outbox until it is
+    pub queued: bool,
+}
+
+impl Delivery {
+    pub fn queued(url: &str) -> Self {
+        Self {
+            url: url.into(),
+            status: None,
+            latency_ms: 0,
+            attempts: 0,
+            error: None,
+            queued: true,
+        }
+    }
+
+    pub fn is_success(&self) -> bool {
+        self.error.is_none()
+    }
+}
+
+// This model is returned in the `data` field of every fan-out response
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct DeliveryReport {
+    pub delivered: usize,
+    pub failed: usize,
+    pub queued: usize,
+    // The proxies the request was sent to
+    pub targets: Vec<String>,
+    pub deliveries: Vec<Delivery>,
+    // Version of the written key, for writes of a single key
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version: Option<i64>,
+}
+
+impl From<Vec<Delivery>> for DeliveryReport {
+    fn from(deliveries: Vec<Delivery>) -> Self {
+        let queued = deliveries.iter().filter(|d| d.queued).count();
+        let failed = deliveries.iter().filter(|d| !d.is_success()).count();
+        Self {
+            delivered: deliveries.len() - queued - failed,
+            failed,
+            queued,
+            targets: deliveries.iter().map(|d| d.url.clone()).collect(),
+            deliveries,
+            version: None,
+        }
+    }
+}
@@ -0,0 +1,81 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sha2::{Digest as _, Sha256};
+use utoipa::ToSchema;
+
+// Proxies answer `GET {url}/proxy-sync/v1/digest` with the hash of every key they hold
+pub const DIGEST_PATH: &str = "/proxy-sync/v1/digest";
+
+#[derive(Default, Serialize, Deserialize, ToSchema)]
+pub struct Digest {
+    // Key to hash of its value, see `hash_value`
+    pub keys: BTreeMap<String, String>,
+}
+
+// The hash of a value in a digest is the lowercase hex sha256
+// of its compact json, with the keys of every object sorted
+pub fn hash_value(value: &Value) -> String {
+    let json = canonical(value).to_string();
+    format!("{:x}", Sha256::digest(json.as_bytes()))
+}
+
+fn canonical(value: &Value) -> Value {
+    match value {
+        Value::Object(map) => {
+            let mut entries = map.iter().collect::<Vec<_>>();
+            entries.sort_by_key(|(key, _)| *key);
+            Value::Object(
+                entries
+                    .into_iter()
+                    .map(|(key, value)| (key.clone(), canonical(value)))
+                    .collect(),
+            )
+        }
+        Value::Array(items) => Value::Array(items.iter().map(canonical).collect()),
+        value => value.clone(),
+    }
+}
+
+// This model describes how far one proxy has drifted from the hub
+
+#[derive(Debug, Default, Serialize, Deserialize, ToSchema)]
+pub struct DriftReport {
+    pub url: String,
+    // Keys held by the hub that the proxy does not have
+    pub missing: Vec<String>,
+    // Keys whose value on the proxy differs from the hub
+    pub stale: Vec<String>,
+    // Keys held by the proxy that the hub does not have for it. The ones the hub
+    // does not know at all are only deleted when asked to
+    pub extra: Vec<String>,
+    // Drifted keys left alone because a write for them is still in the outbox
+    pub skipped: Vec<String>,
+    pub repaired: usize,
+    pub failed: usize,
+    // Set when the digest of the proxy could not be fetched
+    pub error: Option<String>,
+}
+
+impl DriftReport {
+    pub fn has_drift(&self) -> bool {
+        !(self.missing.is_empty() && self.stale.is_empty() && self.extra.is_empty())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::hash_value;
+
+    #[test]
+    fn hash_value_should_ignore_key_order() {
+        assert_eq!(
+            hash_value(&json!({"a": 1, "b": {"c": [1, 2], "d": null}})),
+            hash_value(&json!({"b": {"d": null, "c": [1, 2]}, "a": 1}))
+        );
+        assert_ne!(hash_value(&json!([1, 2])), hash_value(&json!([2, 1])));
+    }
+}
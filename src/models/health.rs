@@ -0,0 +1,50 @@
+use mongodb::bson::DateTime;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum HealthStatus {
+    // The proxy has not been checked yet
+    #[default]
+    Unknown,
+    Healthy,
+    Unhealthy,
+}
+
+// This model describes the outcome of probing `{url}/health` once
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct HealthCheck {
+    pub url: String,
+    pub status: HealthStatus,
+    pub http_status: Option<u16>,
+    pub latency_ms: u64,
+    pub error: Option<String>,
+    // Unix timestamp in milliseconds
+    pub checked_at: i64,
+}
+
+impl HealthCheck {
+    // Stands for a proxy that has not been checked yet
+    pub fn unknown(url: &str) -> Self {
+        Self {
+            url: url.into(),
+            status: HealthStatus::Unknown,
+            http_status: None,
+            latency_ms: 0,
+            error: None,
+            checked_at: 0,
+        }
+    }
+}
+
+// This model is used to keep the past health checks in the mongodb database,
+// the date is only there for the expiry index
+
+#[derive(Serialize, Deserialize)]
+pub struct HealthRecord {
+    #[serde(flatten)]
+    pub check: HealthCheck,
+    pub created_at: DateTime,
+}
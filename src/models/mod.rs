@@ -1,3 +1,12 @@
+pub mod data;
+pub mod data_op;
+pub mod dead_letter;
+pub mod delivery;
+pub mod digest;
 pub mod error;
+pub mod health;
+pub mod operation;
+pub mod outbox;
+pub mod protocol;
 pub mod proxy;
 pub mod success;
@@ -0,0 +1,220 @@
+use std::collections::BTreeMap;
+
+use reqwest::Method;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    models::{
+        data::{Data, DataType},
+        protocol::{ProtocolVersion, OPS_FEATURE},
+        proxy::Proxy,
+    },
+    request::data::{
+        delete::DeleteDataRequest,
+        precondition::Precondition,
+        set::{SetDataRequest, SetMultiDataRequest},
+        target::TargetSelector,
+        update::UpdateDataRequest,
+    },
+};
+
+// This model represents one write that has to be sent to the proxies
+
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", content = "payload", rename_all = "snake_case")]
+pub enum SyncOperation {
+    Set(SetDataRequest),
+    SetMulti(SetMultiDataRequest),
+    Delete(DeleteDataRequest),
+    Update(UpdateDataRequest),
+}
+
+impl SyncOperation {
+    pub fn method(&self, version: ProtocolVersion) -> Method {
+        match (version, self) {
+            (ProtocolVersion::V1, SyncOperation::Delete(_)) => Method::DELETE,
+            (ProtocolVersion::V1, SyncOperation::Update(_)) => Method::PATCH,
+            _ => Method::POST,
+        }
+    }
+
+    pub fn path(&self, version: ProtocolVersion) -> &'static str {
+        match (version, self) {
+            (ProtocolVersion::V1, SyncOperation::SetMulti(_)) => "/proxy-sync/v1/multi",
+            (ProtocolVersion::V1, _) => "/proxy-sync/v1",
+            (ProtocolVersion::V2, _) => "/proxy-sync/v2",
+        }
+    }
+
+    // The keys touched by this operation
+    pub fn keys(&self) -> Vec<String> {
+        match self {
+            SyncOperation::Set(SetDataRequest { key, .. })
+            | SyncOperation::Delete(DeleteDataRequest { key, .. })
+            | SyncOperation::Update(UpdateDataRequest { key, .. }) => vec![key.clone()],
+            SyncOperation::SetMulti(SetMultiDataRequest { data, .. }) => data
+                .as_object()
+                .map(|data| data.keys().cloned().collect())
+                .unwrap_or_default(),
+        }
+    }
+
+    // The data type sent along with this operation
+    pub fn data_type(&self) -> DataType {
+        match self {
+            SyncOperation::Set(SetDataRequest { _type, .. })
+            | SyncOperation::SetMulti(SetMultiDataRequest { _type, .. })
+            | SyncOperation::Delete(DeleteDataRequest { _type, .. })
+            | SyncOperation::Update(UpdateDataRequest { _type, .. }) => *_type,
+        }
+    }
+
+    // Takes the target selector out of the operation, so that it is not forwarded to the proxies
+    pub fn take_targets(&mut self) -> Option<TargetSelector> {
+        match self {
+            SyncOperation::Set(SetDataRequest { targets, .. })
+            | SyncOperation::SetMulti(SetMultiDataRequest { targets, .. })
+            | SyncOperation::Delete(DeleteDataRequest { targets, .. })
+            | SyncOperation::Update(UpdateDataRequest { targets, .. }) => targets.take(),
+        }
+    }
+
+    // Takes the precondition out of the operation, the proxies only get the outcome of it
+    pub fn take_precondition(&mut self) -> Precondition {
+        match self {
+            SyncOperation::Set(SetDataRequest { precondition, .. })
+            | SyncOperation::Delete(DeleteDataRequest { precondition, .. }) => {
+                std::mem::take(precondition)
+            }
+            SyncOperation::SetMulti(_) | SyncOperation::Update(_) => Precondition::default(),
+        }
+    }
+
+    // Sets the versions the hub gave to the written keys, and the new value
+    // of changed keys, replacing whatever the client sent in their place
+    pub fn stamp(&mut self, written: &BTreeMap<String, Data>) {
+        match self {
+            SyncOperation::Set(SetDataRequest { key, version, .. })
+            | SyncOperation::Delete(DeleteDataRequest { key, version, .. }) => {
+                *version = written.get(key).map(|data| data.version)
+            }
+            SyncOperation::Update(UpdateDataRequest {
+                key,
+                version,
+                result,
+                ..
+            }) => {
+                *version = written.get(key).map(|data| data.version);
+                *result = written.get(key).map(|data| data.value.clone());
+            }
+            SyncOperation::SetMulti(SetMultiDataRequest { versions, .. }) => {
+                *versions = (!written.is_empty()).then(|| {
+                    written
+                        .iter()
+                        .map(|(key, data)| (key.clone(), data.version))
+                        .collect()
+                })
+            }
+        }
+    }
+
+    // The part of this operation the proxy subscribed to, if any
+    pub fn for_proxy(&self, proxy: &Proxy) -> Option<SyncOperation> {
+        match self {
+            SyncOperation::SetMulti(
+                request @ SetMultiDataRequest {
+                    _type,
+                    data,
+                    types,
+                    versions,
+                    targets,
+                },
+            ) => {
+                let data = data
+                    .as_object()?
+                    .iter()
+                    .filter(|(key, _)| proxy.wants(key, request.type_of(key)))
+                    .map(|(key, value)| (key.clone(), value.clone()))
+                    .collect::<serde_json::Map<_, _>>();
+
+                let types = types
+                    .iter()
+                    .filter(|(key, _)| data.contains_key(*key))
+                    .map(|(key, _type)| (key.clone(), *_type))
+                    .collect();
+
+                let versions = versions.as_ref().map(|versions| {
+                    versions
+                        .iter()
+                        .filter(|(key, _)| data.contains_key(*key))
+                        .map(|(key, version)| (key.clone(), *version))
+                        .collect()
+                });
+
+                (!data.is_empty()).then(|| {
+                    SyncOperation::SetMulti(SetMultiDataRequest {
+                        _type: *_type,
+                        data: data.into(),
+                        types,
+                        versions,
+                        targets: targets.clone(),
+                    })
+                })
+            }
+            SyncOperation::Update(request) if !proxy.wants(&request.key, request._type) => None,
+            // Proxies that apply the change themselves do not need the new value,
+            // the others are sent only that
+            SyncOperation::Update(request) if proxy.supports(OPS_FEATURE) => {
+                Some(SyncOperation::Update(UpdateDataRequest {
+                    result: None,
+                    ..request.clone()
+                }))
+            }
+            SyncOperation::Update(request) => request.to_set().map(SyncOperation::Set),
+            operation => operation
+                .keys()
+                .iter()
+                .all(|key| proxy.wants(key, operation.data_type()))
+                .then(|| operation.clone()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use crate::models::{data::DataType, data_op::DataOp, protocol::OPS_FEATURE, proxy::Proxy};
+
+    use super::{SyncOperation, UpdateDataRequest};
+
+    #[test]
+    fn update_should_be_sent_as_set_to_proxies_without_ops() {
+        let operation = SyncOperation::Update(UpdateDataRequest {
+            _type: DataType::Counter,
+            key: "stats.hits".into(),
+            op: DataOp::Incr { by: 5 },
+            version: Some(3),
+            result: Some(json!(8)),
+            targets: None,
+        });
+
+        let mut proxy = Proxy::new("http://localhost:3001");
+        assert_eq!(
+            serde_json::to_value(operation.for_proxy(&proxy)).unwrap(),
+            json!({
+                "kind": "set",
+                "payload": { "type": "Counter", "key": "stats.hits", "value": 8, "version": 3 }
+            })
+        );
+
+        proxy.features = vec![OPS_FEATURE.into()];
+        assert_eq!(
+            serde_json::to_value(operation.for_proxy(&proxy)).unwrap(),
+            json!({
+                "kind": "update",
+                "payload": { "type": "Counter", "key": "stats.hits", "op": "incr", "by": 5, "version": 3 }
+            })
+        );
+    }
+}
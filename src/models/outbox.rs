@@ -0,0 +1,69 @@
+use mongodb::bson::{oid::ObjectId, DateTime};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use super::operation::SyncOperation;
+
+// This model is used to persist every sync operation in the mongodb database
+// until all of its target proxies have acknowledged it
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct OutboxEntry {
+    #[serde(rename = "_id", skip_serializing_if = "Option::is_none")]
+    pub id: Option<ObjectId>,
+    pub operation: SyncOperation,
+    pub deliveries: Vec<OutboxDelivery>,
+    pub created_at: DateTime,
+    // The dispatcher leaves the entry alone until this time,
+    // which doubles as a lease while someone is delivering it
+    pub next_attempt_at: DateTime,
+    // Written before the hub applies the write, with no delivery yet, and released once
+    // the write is applied and its proxies are known. An entry still held once its lease
+    // is over belongs to a write that was interrupted halfway
+    #[serde(default)]
+    pub held: bool,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct OutboxDelivery {
+    pub url: String,
+    pub acked: bool,
+    pub attempts: u32,
+    pub last_error: Option<String>,
+}
+
+impl OutboxDelivery {
+    pub fn pending(url: &str) -> Self {
+        Self {
+            url: url.into(),
+            acked: false,
+            attempts: 0,
+            last_error: None,
+        }
+    }
+}
+
+// Identifies the delivery of an entry to one proxy. It stays the same on every attempt,
+// from the outbox or from the dead letters, so that proxies can tell redeliveries apart
+pub fn delivery_id(entry: &ObjectId, url: &str) -> String {
+    let hash = Sha256::digest(format!("{}{url}", entry.to_hex()));
+    format!("{hash:x}")[..24].to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use mongodb::bson::oid::ObjectId;
+
+    use super::delivery_id;
+
+    #[test]
+    fn delivery_id_should_be_stable_per_proxy() {
+        let entry = ObjectId::new();
+
+        let id = delivery_id(&entry, "http://proxy1:3000");
+        assert_eq!(id.len(), 24);
+        assert_eq!(id, delivery_id(&entry, "http://proxy1:3000"));
+        assert_ne!(id, delivery_id(&entry, "http://proxy2:3000"));
+        assert_ne!(id, delivery_id(&ObjectId::new(), "http://proxy1:3000"));
+    }
+}
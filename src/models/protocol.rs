@@ -0,0 +1,132 @@
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use super::operation::SyncOperation;
+
+// Proxies list the protocol versions they speak at `GET {url}/proxy-sync/capabilities`.
+// Proxies without that endpoint only speak v1
+pub const CAPABILITIES_PATH: &str = "/proxy-sync/capabilities";
+
+// Feature of proxies that apply `PATCH` changes to their own copy of a key.
+// The others are sent the new value of the key instead
+pub const OPS_FEATURE: &str = "ops";
+
+// Ordered from oldest to newest
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, ToSchema,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum ProtocolVersion {
+    // One endpoint per operation, with the client request as body
+    #[default]
+    V1,
+    // One endpoint taking every operation wrapped in an `Envelope`
+    V2,
+}
+
+impl ProtocolVersion {
+    // The versions this hub speaks
+    pub const SUPPORTED: [ProtocolVersion; 2] = [ProtocolVersion::V1, ProtocolVersion::V2];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ProtocolVersion::V1 => "v1",
+            ProtocolVersion::V2 => "v2",
+        }
+    }
+
+    // The newest version both sides speak
+    pub fn negotiate(capabilities: &Capabilities) -> Self {
+        Self::SUPPORTED
+            .into_iter()
+            .filter(|version| capabilities.versions.iter().any(|v| v == version.as_str()))
+            .max()
+            .unwrap_or_default()
+    }
+}
+
+#[derive(Default, Serialize, Deserialize, ToSchema)]
+pub struct Capabilities {
+    // Versions unknown to the hub are ignored
+    pub versions: Vec<String>,
+    #[serde(default)]
+    pub features: Vec<String>,
+}
+
+// Body of every v2 request
+#[derive(Serialize, Deserialize)]
+pub struct Envelope {
+    // Kept across the retries of a delivery, so that proxies can drop duplicates
+    pub id: String,
+    pub version: ProtocolVersion,
+    // Unix timestamp in milliseconds at which the hub sent the operation
+    pub timestamp: i64,
+    // Name of the hub that sent the operation
+    pub origin: String,
+    // `kind` and `payload` of the operation
+    #[serde(flatten)]
+    pub operation: SyncOperation,
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use crate::{
+        models::{data::DataType, operation::SyncOperation},
+        request::data::delete::DeleteDataRequest,
+    };
+
+    use super::{Capabilities, Envelope, ProtocolVersion};
+
+    #[test]
+    fn negotiate_should_pick_newest_common_version() {
+        let capabilities = |versions: &[&str]| Capabilities {
+            versions: versions.iter().map(|v| v.to_string()).collect(),
+            features: vec![],
+        };
+
+        assert_eq!(
+            ProtocolVersion::negotiate(&capabilities(&["v1", "v2", "v3"])),
+            ProtocolVersion::V2
+        );
+        assert_eq!(
+            ProtocolVersion::negotiate(&capabilities(&["v1"])),
+            ProtocolVersion::V1
+        );
+        assert_eq!(
+            ProtocolVersion::negotiate(&capabilities(&[])),
+            ProtocolVersion::V1
+        );
+    }
+
+    #[test]
+    fn envelope_should_inline_the_operation() {
+        let envelope = Envelope {
+            id: "1".into(),
+            version: ProtocolVersion::V2,
+            timestamp: 1685100000000,
+            origin: "sync-hub".into(),
+            operation: SyncOperation::Delete(DeleteDataRequest {
+                _type: DataType::String,
+                key: "config.flag".into(),
+                ttl: None,
+                precondition: Default::default(),
+                version: Some(3),
+                targets: None,
+            }),
+        };
+
+        assert_eq!(
+            serde_json::to_value(&envelope).unwrap(),
+            json!({
+                "id": "1",
+                "version": "v2",
+                "timestamp": 1685100000000i64,
+                "origin": "sync-hub",
+                "kind": "delete",
+                "payload": { "type": "String", "key": "config.flag", "version": 3 }
+            })
+        );
+    }
+}
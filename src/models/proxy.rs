@@ -1,9 +1,247 @@
+use std::collections::BTreeMap;
+
+use mongodb::bson::oid::ObjectId;
 use serde::{Deserialize, Serialize};
 use utoipa::ToSchema;
 
+use crate::helper::{id::deserialize_id, pattern::matches_pattern};
+
+use super::{
+    data::DataType,
+    health::{HealthCheck, HealthStatus},
+    protocol::ProtocolVersion,
+};
+
 // This model is used to interact with the mongodb database
 
-#[derive(Serialize, Deserialize, ToSchema)]
+#[derive(Clone, Serialize, Deserialize, ToSchema)]
 pub struct Proxy {
+    #[serde(rename = "_id", deserialize_with = "deserialize_id")]
+    pub id: String,
     pub url: String,
+    // Display name
+    #[serde(default)]
+    pub name: Option<String>,
+    #[serde(default)]
+    pub provider: Option<Provider>,
+    #[serde(default)]
+    pub region: Option<String>,
+    // Deployment environment, e.g. production or staging
+    #[serde(default)]
+    pub environment: Option<String>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(default)]
+    pub metadata: BTreeMap<String, String>,
+    // The keys this proxy receives, every key if empty
+    #[serde(default)]
+    pub subscriptions: Vec<Subscription>,
+    #[serde(default)]
+    pub mode: ProxyMode,
+    // Negotiated when the proxy is registered, v1 for proxies registered before
+    #[serde(default)]
+    pub protocol: ProtocolVersion,
+    // Optional parts of the protocol the proxy supports, negotiated along with it
+    #[serde(default)]
+    pub features: Vec<String>,
+    // Proxies registered before bootstrapping existed are considered complete
+    #[serde(default)]
+    pub bootstrap: Bootstrap,
+    // Kept up to date by the health monitor
+    #[serde(default)]
+    pub status: HealthStatus,
+    // Unix timestamp in milliseconds of the last successful health check
+    #[serde(default)]
+    pub last_seen: Option<i64>,
+    #[serde(default)]
+    pub consecutive_failures: u32,
+    #[serde(default)]
+    pub last_error: Option<String>,
+    // The latest health check, whatever its outcome
+    #[serde(default)]
+    pub last_check: Option<HealthCheck>,
+    #[serde(default)]
+    pub breaker: Breaker,
+}
+
+// What was removed along with a proxy
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct ProxyRemoval {
+    pub proxy: Proxy,
+    pub cascade: bool,
+    // Outbox entries the proxy was still waiting for
+    pub deliveries: u64,
+    pub dead_letters: u64,
+    pub health_checks: u64,
+}
+
+impl ProxyRemoval {
+    pub fn new(proxy: Proxy) -> Self {
+        Self {
+            proxy,
+            cascade: false,
+            deliveries: 0,
+            dead_letters: 0,
+            health_checks: 0,
+        }
+    }
+}
+
+impl Proxy {
+    pub fn new(url: &str) -> Self {
+        Self {
+            id: ObjectId::new().to_hex(),
+            url: url.into(),
+            name: None,
+            provider: None,
+            region: None,
+            environment: None,
+            tags: vec![],
+            metadata: BTreeMap::new(),
+            subscriptions: vec![],
+            mode: ProxyMode::Enabled,
+            protocol: ProtocolVersion::V1,
+            features: vec![],
+            bootstrap: Bootstrap::pending(),
+            status: HealthStatus::Unknown,
+            last_seen: None,
+            consecutive_failures: 0,
+            last_error: None,
+            last_check: None,
+            breaker: Breaker::default(),
+        }
+    }
+
+    // Whether the proxy advertised the given feature
+    pub fn supports(&self, feature: &str) -> bool {
+        self.features.iter().any(|f| f == feature)
+    }
+
+    // Whether the proxy subscribed to a key of the given type
+    pub fn wants(&self, key: &str, _type: DataType) -> bool {
+        self.subscriptions.is_empty()
+            || self
+                .subscriptions
+                .iter()
+                .any(|subscription| subscription.matches(key, _type))
+    }
+
+    // Whether writes can be sent to this proxy right away,
+    // instead of being queued in the outbox for later
+    pub fn is_ready(&self) -> bool {
+        self.bootstrap.status == BootstrapStatus::Completed && self.mode == ProxyMode::Enabled
+    }
+}
+
+// A set of keys a proxy wants to receive
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct Subscription {
+    // A key prefix, or a glob over the whole key when it contains `*` or `?`
+    pub pattern: String,
+    // Allowed data types, any type if empty
+    #[serde(default)]
+    pub types: Vec<DataType>,
+}
+
+impl Subscription {
+    pub fn matches(&self, key: &str, _type: DataType) -> bool {
+        matches_pattern(&self.pattern, key)
+            && (self.types.is_empty() || self.types.contains(&_type))
+    }
+}
+
+// Whether a proxy takes part in fan-out. Writes for a proxy that does not
+// are buffered in the outbox, and replayed in order once it is enabled again
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ProxyMode {
+    #[default]
+    Enabled,
+    Disabled,
+    // Like disabled, for planned downtime
+    Maintenance,
+    // The buffered writes are being sent, the proxy is enabled afterwards
+    Replaying,
+}
+
+impl ProxyMode {
+    // Whether the health monitor should probe the proxy
+    pub fn is_monitored(&self) -> bool {
+        matches!(self, ProxyMode::Enabled | ProxyMode::Replaying)
+    }
+}
+
+// The cloud a proxy runs in
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "kebab-case")]
+pub enum Provider {
+    Aws,
+    Gcp,
+    Azure,
+    OnPrem,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum BootstrapStatus {
+    Pending,
+    Running,
+    Completed,
+    Failed,
+}
+
+// Progress of sending the full key/value state to a newly registered proxy
+
+#[derive(Clone, Serialize, Deserialize, ToSchema)]
+pub struct Bootstrap {
+    pub status: BootstrapStatus,
+    pub sent: u64,
+    pub total: u64,
+    pub error: Option<String>,
+}
+
+impl Bootstrap {
+    pub fn pending() -> Self {
+        Self {
+            status: BootstrapStatus::Pending,
+            sent: 0,
+            total: 0,
+            error: None,
+        }
+    }
+}
+
+impl Default for Bootstrap {
+    fn default() -> Self {
+        Self {
+            status: BootstrapStatus::Completed,
+            ..Self::pending()
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum BreakerState {
+    // Writes are sent to the proxy
+    #[default]
+    Closed,
+    // Writes are queued in the outbox until the cool down is over
+    Open,
+    // One request is let through to find out if the proxy recovered
+    HalfOpen,
+}
+
+// Circuit breaker driven by the deliveries to a proxy
+
+#[derive(Clone, Default, Serialize, Deserialize, ToSchema)]
+pub struct Breaker {
+    pub state: BreakerState,
+    // Failed deliveries in a row
+    pub failures: u32,
+    // Unix timestamp in milliseconds at which the breaker last opened
+    pub opened_at: Option<i64>,
 }
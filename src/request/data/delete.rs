@@ -2,23 +2,46 @@ use axum::{async_trait, body::Body, extract::FromRequest, http::Request, Json};
 
 use serde::{Deserialize, Serialize};
 use utoipa::ToSchema;
+use validator::Validate;
 
-use crate::{error::Error, Services};
+use crate::{
+    error::Error,
+    models::data::DataType,
+    request::data::{precondition::Precondition, target::TargetSelector},
+    Services,
+};
 
-#[derive(Serialize, Deserialize, ToSchema)]
+#[derive(Clone, Serialize, Deserialize, Validate, ToSchema)]
 pub struct DeleteDataRequest {
     #[serde(rename = "type")]
-    pub _type: String,
+    pub _type: DataType,
     pub key: String,
+    // Seconds the key stays readable before it is deleted, or right away if not above 0
+    #[validate(range(max = 315360000, message = "Ttl cannot be more than 10 years"))]
     #[serde(skip_serializing_if = "Option::is_none")]
     pub ttl: Option<i64>,
+    #[serde(flatten)]
+    pub precondition: Precondition,
+    // Version of the key once written, set by the hub so that proxies can drop stale writes
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version: Option<i64>,
+    // Only sent to the proxies chosen here, never forwarded to them
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub targets: Option<TargetSelector>,
 }
 
 #[async_trait]
 impl FromRequest<Services, Body> for DeleteDataRequest {
     type Rejection = Error;
     async fn from_request(req: Request<Body>, state: &Services) -> Result<Self, Self::Rejection> {
-        let Json(body) = Json::<DeleteDataRequest>::from_request(req, state).await?;
+        let Json(mut body) = Json::<DeleteDataRequest>::from_request(req, state).await?;
+        body.validate()?;
+        body.precondition.check()?;
+
+        if let Some(targets) = &mut body.targets {
+            targets.normalize();
+        }
+
         Ok(body)
     }
 }
@@ -0,0 +1,76 @@
+use axum::{
+    async_trait,
+    extract::{FromRequestParts, Query},
+    http::request::Parts,
+};
+use mongodb::bson::{doc, Document, Regex};
+use serde::Deserialize;
+use utoipa::IntoParams;
+use validator::Validate;
+
+use crate::{error::Error, Services};
+
+#[derive(Deserialize, Validate, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct KeysFilter {
+    // Only keys starting with this prefix
+    pub prefix: Option<String>,
+    // Page number, starting from 1
+    #[serde(default = "default_page")]
+    #[validate(range(min = 1, max = 10000, message = "Page must be between 1 and 10000"))]
+    pub page: u64,
+    // Number of keys per page
+    #[serde(default = "default_limit")]
+    #[validate(range(min = 1, max = 100, message = "Limit must be between 1 and 100"))]
+    pub limit: u64,
+}
+
+fn default_page() -> u64 {
+    1
+}
+
+fn default_limit() -> u64 {
+    20
+}
+
+impl KeysFilter {
+    pub fn to_document(&self) -> Document {
+        let mut filter = doc! {};
+
+        if let Some(prefix) = &self.prefix {
+            filter.insert(
+                "key",
+                Regex {
+                    pattern: format!("^{}", regex::escape(prefix)),
+                    options: String::new(),
+                },
+            );
+        }
+
+        filter
+    }
+
+    // Same as the document, for the stores that are not mongodb
+    pub fn matches(&self, key: &str) -> bool {
+        self.prefix
+            .as_ref()
+            .is_none_or(|prefix| key.starts_with(prefix.as_str()))
+    }
+
+    pub fn skip(&self) -> u64 {
+        (self.page - 1) * self.limit
+    }
+}
+
+#[async_trait]
+impl FromRequestParts<Services> for KeysFilter {
+    type Rejection = Error;
+    async fn from_request_parts(
+        parts: &mut Parts,
+        state: &Services,
+    ) -> Result<Self, Self::Rejection> {
+        let Query(filter) = Query::<KeysFilter>::from_request_parts(parts, state).await?;
+        filter.validate()?;
+        Ok(filter)
+    }
+}
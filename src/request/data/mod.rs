@@ -1,2 +1,7 @@
 pub mod delete;
+pub mod keys;
+pub mod precondition;
+pub mod reconcile;
 pub mod set;
+pub mod target;
+pub mod update;
@@ -0,0 +1,39 @@
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use validator::ValidationError;
+
+use crate::{error::Error, helper::validation::invalid};
+
+// Makes a write depend on the current state of its key, so that two services
+// writing the same key notice each other instead of overwriting silently.
+// The hub checks it and never forwards it to the proxies
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, ToSchema)]
+pub struct Precondition {
+    // Only writes if the key is at this version
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub if_version: Option<i64>,
+    // Only writes if the key does not exist
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub if_absent: bool,
+}
+
+impl Precondition {
+    pub fn check(&self) -> Result<(), Error> {
+        let message = match self {
+            Precondition {
+                if_version: Some(_),
+                if_absent: true,
+            } => "If_version and if_absent cannot be used together",
+            Precondition {
+                if_version: Some(version),
+                ..
+            } if *version < 1 => "If_version must be at least 1",
+            _ => return Ok(()),
+        };
+
+        let mut error = ValidationError::new("precondition");
+        error.message = Some(message.into());
+        Err(invalid("precondition", error))
+    }
+}
@@ -0,0 +1,35 @@
+use axum::{
+    async_trait,
+    extract::{FromRequestParts, Query},
+    http::request::Parts,
+};
+use serde::Deserialize;
+use utoipa::IntoParams;
+
+use crate::{error::Error, helper::url::normalize_url, Services};
+
+#[derive(Default, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ReconcileFilter {
+    // Only reconcile this proxy
+    pub url: Option<String>,
+    // Only report the drift, without repairing it
+    #[serde(default)]
+    pub dry_run: bool,
+    // Also delete the keys a proxy holds that the hub does not know at all
+    #[serde(default)]
+    pub delete_unknown: bool,
+}
+
+#[async_trait]
+impl FromRequestParts<Services> for ReconcileFilter {
+    type Rejection = Error;
+    async fn from_request_parts(
+        parts: &mut Parts,
+        state: &Services,
+    ) -> Result<Self, Self::Rejection> {
+        let Query(mut filter) = Query::<ReconcileFilter>::from_request_parts(parts, state).await?;
+        filter.url = filter.url.as_deref().map(normalize_url);
+        Ok(filter)
+    }
+}
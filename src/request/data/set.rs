@@ -1,40 +1,132 @@
+use std::collections::BTreeMap;
+
 use axum::{async_trait, body::Body, extract::FromRequest, http::Request, Json};
 
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use utoipa::ToSchema;
+use validator::{Validate, ValidationError};
 
-use crate::{error::Error, Services};
+use crate::{
+    error::Error,
+    helper::validation::invalid,
+    models::data::DataType,
+    request::data::{precondition::Precondition, target::TargetSelector},
+    Services,
+};
 
-#[derive(Serialize, Deserialize, ToSchema)]
+#[derive(Clone, Serialize, Deserialize, Validate, ToSchema)]
 pub struct SetDataRequest {
     #[serde(rename = "type")]
-    pub _type: String,
+    pub _type: DataType,
     pub key: String,
     pub value: Value,
+    // Time to live in seconds, the hub deletes the key everywhere once it is over
+    #[validate(range(min = 1, max = 315360000, message = "Ttl must be 1 second to 10 years"))]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ttl: Option<i64>,
+    #[serde(flatten)]
+    pub precondition: Precondition,
+    // Version of the key once written, set by the hub so that proxies can drop stale writes
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version: Option<i64>,
+    // Only sent to the proxies chosen here, never forwarded to them
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub targets: Option<TargetSelector>,
 }
 
 #[async_trait]
 impl FromRequest<Services, Body> for SetDataRequest {
     type Rejection = Error;
     async fn from_request(req: Request<Body>, state: &Services) -> Result<Self, Self::Rejection> {
-        let Json(body) = Json::<SetDataRequest>::from_request(req, state).await?;
+        let Json(mut body) = Json::<SetDataRequest>::from_request(req, state).await?;
+        body.validate()?;
+        body.precondition.check()?;
+
+        if body._type == DataType::Multi {
+            let mut error = ValidationError::new("type");
+            error.message = Some("Multi is only accepted by bulk writes".into());
+            return Err(invalid("type", error));
+        }
+        body._type
+            .check(&body.value)
+            .map_err(|e| invalid("value", e))?;
+
+        if let Some(targets) = &mut body.targets {
+            targets.normalize();
+        }
+
         Ok(body)
     }
 }
 
-#[derive(Serialize, Deserialize, ToSchema)]
+#[derive(Clone, Serialize, Deserialize, ToSchema)]
 pub struct SetMultiDataRequest {
     #[serde(rename = "type")]
-    pub _type: String,
+    pub _type: DataType,
     pub data: Value,
+    // Type of every key of a Multi write. Keys left out get the type their value points to
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub types: BTreeMap<String, DataType>,
+    // Version of every key once written, set by the hub so that proxies can drop stale writes
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub versions: Option<BTreeMap<String, i64>>,
+    // Only sent to the proxies chosen here, never forwarded to them
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub targets: Option<TargetSelector>,
 }
 
 #[async_trait]
 impl FromRequest<Services, Body> for SetMultiDataRequest {
     type Rejection = Error;
     async fn from_request(req: Request<Body>, state: &Services) -> Result<Self, Self::Rejection> {
-        let Json(body) = Json::<SetMultiDataRequest>::from_request(req, state).await?;
+        let Json(mut body) = Json::<SetMultiDataRequest>::from_request(req, state).await?;
+
+        let Some(data) = body.data.as_object() else {
+            let mut error = ValidationError::new("data");
+            error.message = Some("Data must be an object of key to value".into());
+            return Err(invalid("data", error));
+        };
+
+        if body._type != DataType::Multi && !body.types.is_empty() {
+            let mut error = ValidationError::new("types");
+            error.message = Some("Types are only accepted along with Multi".into());
+            return Err(invalid("types", error));
+        }
+
+        // Every key is stored with its own type, so that later changes of the key find it
+        let mut types = BTreeMap::new();
+        for (key, value) in data {
+            let _type = match body.types.get(key) {
+                Some(_type) => *_type,
+                None if body._type == DataType::Multi => {
+                    DataType::of(value).map_err(|e| invalid("data", e))?
+                }
+                None => body._type,
+            };
+            if _type == DataType::Multi {
+                let mut error = ValidationError::new("types");
+                error.message = Some("Multi is not the type of a key".into());
+                return Err(invalid("types", error));
+            }
+            _type.check(value).map_err(|e| invalid("data", e))?;
+            types.insert(key.clone(), _type);
+        }
+        if body._type == DataType::Multi {
+            body.types = types;
+        }
+
+        if let Some(targets) = &mut body.targets {
+            targets.normalize();
+        }
+
         Ok(body)
     }
 }
+
+impl SetMultiDataRequest {
+    // The type the key is written with
+    pub fn type_of(&self, key: &str) -> DataType {
+        self.types.get(key).copied().unwrap_or(self._type)
+    }
+}
@@ -0,0 +1,81 @@
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use crate::{
+    helper::url::normalize_url,
+    models::proxy::{Provider, Proxy},
+};
+
+// Chooses the proxies a write is sent to.
+// Without a selector, writes go to every proxy
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, ToSchema)]
+pub struct TargetSelector {
+    // A proxy is chosen if it matches every criterion given here
+    #[serde(default)]
+    pub include: ProxyMatcher,
+    // A proxy is left out if it matches any criterion given here
+    #[serde(default)]
+    pub exclude: ProxyMatcher,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, ToSchema)]
+pub struct ProxyMatcher {
+    #[serde(default)]
+    pub urls: Vec<String>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(default)]
+    pub regions: Vec<String>,
+    #[serde(default)]
+    pub providers: Vec<Provider>,
+}
+
+impl TargetSelector {
+    // Gives the urls the form proxies are registered under, so that they can match
+    pub fn normalize(&mut self) {
+        for url in self
+            .include
+            .urls
+            .iter_mut()
+            .chain(self.exclude.urls.iter_mut())
+        {
+            *url = normalize_url(url);
+        }
+    }
+
+    pub fn matches(&self, proxy: &Proxy) -> bool {
+        let ProxyMatcher {
+            urls,
+            tags,
+            regions,
+            providers,
+        } = &self.include;
+
+        let included = (urls.is_empty() || urls.contains(&proxy.url))
+            && (tags.is_empty() || tags.iter().any(|tag| proxy.tags.contains(tag)))
+            && (regions.is_empty() || proxy.region.as_ref().is_some_and(|r| regions.contains(r)))
+            && (providers.is_empty()
+                || proxy
+                    .provider
+                    .as_ref()
+                    .is_some_and(|p| providers.contains(p)));
+
+        let ProxyMatcher {
+            urls,
+            tags,
+            regions,
+            providers,
+        } = &self.exclude;
+
+        let excluded = urls.contains(&proxy.url)
+            || tags.iter().any(|tag| proxy.tags.contains(tag))
+            || proxy.region.as_ref().is_some_and(|r| regions.contains(r))
+            || proxy
+                .provider
+                .as_ref()
+                .is_some_and(|p| providers.contains(p));
+
+        included && !excluded
+    }
+}
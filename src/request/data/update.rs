@@ -0,0 +1,100 @@
+use axum::{async_trait, body::Body, extract::FromRequest, http::Request, Json};
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use utoipa::ToSchema;
+use validator::ValidationError;
+
+use crate::{
+    error::Error,
+    helper::validation::invalid,
+    models::{data::DataType, data_op::DataOp},
+    request::data::{precondition::Precondition, set::SetDataRequest, target::TargetSelector},
+    Services,
+};
+
+#[derive(Clone, Serialize, Deserialize, ToSchema)]
+pub struct UpdateDataRequest {
+    #[serde(rename = "type")]
+    pub _type: DataType,
+    pub key: String,
+    #[serde(flatten)]
+    pub op: DataOp,
+    // Version of the key once written, set by the hub so that proxies can drop stale writes
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version: Option<i64>,
+    // Value of the key once changed, set by the hub for the proxies
+    // that cannot apply the change themselves
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub result: Option<Value>,
+    // Only sent to the proxies chosen here, never forwarded to them
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub targets: Option<TargetSelector>,
+}
+
+impl UpdateDataRequest {
+    // The same write as a Set of the new value, none if the change wrote nothing.
+    // The expiry of the key is left to the hub, which deletes it everywhere
+    pub fn to_set(&self) -> Option<SetDataRequest> {
+        Some(SetDataRequest {
+            _type: self._type,
+            key: self.key.clone(),
+            value: self.result.clone()?,
+            ttl: None,
+            precondition: Precondition::default(),
+            version: self.version,
+            targets: None,
+        })
+    }
+
+    fn check(&self) -> Result<(), (&'static str, &'static str)> {
+        let expected = match self.op {
+            DataOp::Incr { .. } | DataOp::Decr { .. } => DataType::Counter,
+            DataOp::Append { .. } | DataOp::Prepend { .. } => DataType::List,
+            DataOp::SetAdd { .. } | DataOp::SetRemove { .. } => DataType::Set,
+            DataOp::HashSet { .. } | DataOp::HashDelete { .. } => DataType::Hash,
+        };
+        if self._type != expected {
+            return Err(("type", "The operation does not apply to this type"));
+        }
+
+        match &self.op {
+            DataOp::Incr { by } | DataOp::Decr { by } if *by < 1 => {
+                Err(("by", "By must be at least 1"))
+            }
+            // Fields are stored as paths of the hub's document
+            DataOp::HashSet { field, .. } | DataOp::HashDelete { field }
+                if field.is_empty() || field.contains('.') || field.starts_with('$') =>
+            {
+                Err((
+                    "field",
+                    "Field cannot be empty, contain '.' or start with '$'",
+                ))
+            }
+            DataOp::HashSet { value, .. } if value.is_object() || value.is_array() => {
+                Err(("value", "Hash fields cannot be objects or lists"))
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+#[async_trait]
+impl FromRequest<Services, Body> for UpdateDataRequest {
+    type Rejection = Error;
+    async fn from_request(req: Request<Body>, state: &Services) -> Result<Self, Self::Rejection> {
+        let Json(mut body) = Json::<UpdateDataRequest>::from_request(req, state).await?;
+
+        if let Err((field, message)) = body.check() {
+            let mut error = ValidationError::new(field);
+            error.message = Some(message.into());
+            return Err(invalid(field, error));
+        }
+
+        if let Some(targets) = &mut body.targets {
+            targets.normalize();
+        }
+
+        Ok(body)
+    }
+}
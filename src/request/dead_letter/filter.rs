@@ -0,0 +1,86 @@
+use axum::{
+    async_trait,
+    extract::{FromRequestParts, Query},
+    http::request::Parts,
+};
+use mongodb::bson::{doc, Document};
+use serde::Deserialize;
+use utoipa::IntoParams;
+
+use crate::{
+    error::Error,
+    helper::url::normalize_url,
+    models::{data::DataType, dead_letter::DeadLetter},
+    Services,
+};
+
+#[derive(Default, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct DeadLetterFilter {
+    // Target proxy url
+    pub url: Option<String>,
+    // A key touched by the failed operation
+    pub key: Option<String>,
+    // Data type of the failed operation
+    #[serde(rename = "type")]
+    #[param(rename = "type")]
+    pub _type: Option<DataType>,
+    // Failed at or after this unix timestamp in milliseconds
+    pub from: Option<i64>,
+    // Failed at or before this unix timestamp in milliseconds
+    pub to: Option<i64>,
+}
+
+impl DeadLetterFilter {
+    pub fn to_document(&self) -> Document {
+        let mut filter = doc! {};
+
+        if let Some(url) = &self.url {
+            filter.insert("url", url);
+        }
+        if let Some(key) = &self.key {
+            filter.insert("keys", key);
+        }
+        if let Some(_type) = &self._type {
+            filter.insert("type", _type.as_str());
+        }
+
+        let mut failed_at = doc! {};
+        if let Some(from) = self.from {
+            failed_at.insert("$gte", from);
+        }
+        if let Some(to) = self.to {
+            failed_at.insert("$lte", to);
+        }
+        if !failed_at.is_empty() {
+            filter.insert("failed_at", failed_at);
+        }
+
+        filter
+    }
+
+    // Same as the document, for the stores that are not mongodb
+    pub fn matches(&self, dead_letter: &DeadLetter) -> bool {
+        self.url.as_ref().is_none_or(|url| *url == dead_letter.url)
+            && self
+                .key
+                .as_ref()
+                .is_none_or(|key| dead_letter.keys.contains(key))
+            && self._type.is_none_or(|_type| _type == dead_letter._type)
+            && self.from.is_none_or(|from| dead_letter.failed_at >= from)
+            && self.to.is_none_or(|to| dead_letter.failed_at <= to)
+    }
+}
+
+#[async_trait]
+impl FromRequestParts<Services> for DeadLetterFilter {
+    type Rejection = Error;
+    async fn from_request_parts(
+        parts: &mut Parts,
+        state: &Services,
+    ) -> Result<Self, Self::Rejection> {
+        let Query(mut filter) = Query::<DeadLetterFilter>::from_request_parts(parts, state).await?;
+        filter.url = filter.url.as_deref().map(normalize_url);
+        Ok(filter)
+    }
+}
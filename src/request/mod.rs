@@ -1,2 +1,3 @@
 pub mod data;
+pub mod dead_letter;
 pub mod proxy;
@@ -1,28 +1,136 @@
+use std::collections::{BTreeMap, HashSet};
+
 use axum::{async_trait, body::Body, extract::FromRequest, http::Request, Json};
 use serde::Deserialize;
 use utoipa::ToSchema;
-use validator::Validate;
+use validator::{Validate, ValidationError};
 
-use crate::{error::Error, models::proxy::Proxy, Services};
+use crate::{
+    error::Error,
+    helper::url::normalize_url,
+    models::proxy::{Provider, Proxy, Subscription},
+    Services,
+};
 
 #[derive(Deserialize, Validate, ToSchema)]
 pub struct AddProxyRequest {
     #[validate(url(message = "Proxy url is invalid"))]
     pub url: String,
+    #[validate(length(min = 1, max = 64, message = "Name must be 1 to 64 characters long"))]
+    pub name: Option<String>,
+    pub provider: Option<Provider>,
+    #[validate(length(min = 1, max = 64, message = "Region must be 1 to 64 characters long"))]
+    pub region: Option<String>,
+    #[validate(length(
+        min = 1,
+        max = 32,
+        message = "Environment must be 1 to 32 characters long"
+    ))]
+    pub environment: Option<String>,
+    #[serde(default)]
+    #[validate(custom = "validate_tags")]
+    pub tags: Vec<String>,
+    #[serde(default)]
+    #[validate(custom = "validate_metadata")]
+    pub metadata: BTreeMap<String, String>,
+    #[serde(default)]
+    #[validate(custom = "validate_subscriptions")]
+    pub subscriptions: Vec<Subscription>,
+}
+
+pub fn validate_tags(tags: &[String]) -> Result<(), ValidationError> {
+    if tags.len() > 32 {
+        return Err(error("tags", "A proxy cannot have more than 32 tags"));
+    }
+    if tags.iter().any(|tag| tag.is_empty() || tag.len() > 64) {
+        return Err(error("tags", "Tags must be 1 to 64 characters long"));
+    }
+    if tags.iter().collect::<HashSet<_>>().len() != tags.len() {
+        return Err(error("tags", "Tags must be unique"));
+    }
+    Ok(())
+}
+
+pub fn validate_metadata(metadata: &BTreeMap<String, String>) -> Result<(), ValidationError> {
+    if metadata.len() > 32 {
+        return Err(error(
+            "metadata",
+            "A proxy cannot have more than 32 metadata entries",
+        ));
+    }
+    if metadata.keys().any(|key| key.is_empty() || key.len() > 64) {
+        return Err(error(
+            "metadata",
+            "Metadata keys must be 1 to 64 characters long",
+        ));
+    }
+    if metadata.values().any(|value| value.len() > 256) {
+        return Err(error(
+            "metadata",
+            "Metadata values cannot be longer than 256 characters",
+        ));
+    }
+    Ok(())
+}
+
+pub fn validate_subscriptions(subscriptions: &[Subscription]) -> Result<(), ValidationError> {
+    if subscriptions.len() > 32 {
+        return Err(error(
+            "subscriptions",
+            "A proxy cannot have more than 32 subscriptions",
+        ));
+    }
+    if subscriptions
+        .iter()
+        .any(|subscription| subscription.pattern.len() > 256)
+    {
+        return Err(error(
+            "subscriptions",
+            "Subscription patterns cannot be longer than 256 characters",
+        ));
+    }
+    Ok(())
+}
+
+fn error(code: &'static str, message: &'static str) -> ValidationError {
+    let mut error = ValidationError::new(code);
+    error.message = Some(message.into());
+    error
 }
 
 #[async_trait]
 impl FromRequest<Services, Body> for AddProxyRequest {
     type Rejection = Error;
     async fn from_request(req: Request<Body>, state: &Services) -> Result<Self, Self::Rejection> {
-        let Json(body) = Json::<AddProxyRequest>::from_request(req, state).await?;
+        let Json(mut body) = Json::<AddProxyRequest>::from_request(req, state).await?;
         body.validate()?;
+        body.url = normalize_url(&body.url);
         Ok(body)
     }
 }
 
 impl From<AddProxyRequest> for Proxy {
-    fn from(AddProxyRequest { url }: AddProxyRequest) -> Self {
-        Self { url }
+    fn from(
+        AddProxyRequest {
+            url,
+            name,
+            provider,
+            region,
+            environment,
+            tags,
+            metadata,
+            subscriptions,
+        }: AddProxyRequest,
+    ) -> Self {
+        Self {
+            name,
+            provider,
+            region,
+            environment,
+            tags,
+            metadata,
+            subscriptions,
+            ..Self::new(&url)
+        }
     }
 }
@@ -0,0 +1,23 @@
+use axum::{async_trait, body::Body, extract::FromRequest, http::Request, Json};
+use serde::Deserialize;
+use utoipa::ToSchema;
+use validator::Validate;
+
+use crate::{error::Error, helper::url::normalize_url, Services};
+
+#[derive(Deserialize, Validate, ToSchema)]
+pub struct ResetBreakerRequest {
+    #[validate(url(message = "Proxy url is invalid"))]
+    pub url: String,
+}
+
+#[async_trait]
+impl FromRequest<Services, Body> for ResetBreakerRequest {
+    type Rejection = Error;
+    async fn from_request(req: Request<Body>, state: &Services) -> Result<Self, Self::Rejection> {
+        let Json(mut body) = Json::<ResetBreakerRequest>::from_request(req, state).await?;
+        body.validate()?;
+        body.url = normalize_url(&body.url);
+        Ok(body)
+    }
+}
@@ -1,9 +1,15 @@
-use axum::{async_trait, body::Body, extract::FromRequest, http::Request, Json};
+use axum::{
+    async_trait,
+    body::Body,
+    extract::{FromRequest, FromRequestParts, Query},
+    http::{request::Parts, Request},
+    Json,
+};
 use serde::Deserialize;
-use utoipa::ToSchema;
+use utoipa::{IntoParams, ToSchema};
 use validator::Validate;
 
-use crate::{error::Error, models::proxy::Proxy, Services};
+use crate::{error::Error, helper::url::normalize_url, models::proxy::Proxy, Services};
 
 #[derive(Deserialize, Validate, ToSchema)]
 pub struct DeleteProxyRequest {
@@ -15,14 +21,35 @@ pub struct DeleteProxyRequest {
 impl FromRequest<Services, Body> for DeleteProxyRequest {
     type Rejection = Error;
     async fn from_request(req: Request<Body>, state: &Services) -> Result<Self, Self::Rejection> {
-        let Json(body) = Json::<DeleteProxyRequest>::from_request(req, state).await?;
+        let Json(mut body) = Json::<DeleteProxyRequest>::from_request(req, state).await?;
         body.validate()?;
+        body.url = normalize_url(&body.url);
         Ok(body)
     }
 }
 
 impl From<DeleteProxyRequest> for Proxy {
     fn from(DeleteProxyRequest { url }: DeleteProxyRequest) -> Self {
-        Self { url }
+        Self::new(&url)
+    }
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct DeleteProxyQuery {
+    // Also remove the queued deliveries, dead letters and health history
+    #[serde(default)]
+    pub cascade: bool,
+}
+
+#[async_trait]
+impl FromRequestParts<Services> for DeleteProxyQuery {
+    type Rejection = Error;
+    async fn from_request_parts(
+        parts: &mut Parts,
+        state: &Services,
+    ) -> Result<Self, Self::Rejection> {
+        let Query(query) = Query::<DeleteProxyQuery>::from_request_parts(parts, state).await?;
+        Ok(query)
     }
 }
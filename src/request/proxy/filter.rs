@@ -0,0 +1,67 @@
+use axum::{
+    async_trait,
+    extract::{FromRequestParts, Query},
+    http::request::Parts,
+};
+use serde::Deserialize;
+use utoipa::IntoParams;
+
+use crate::{
+    error::Error,
+    models::proxy::{Provider, Proxy},
+    Services,
+};
+
+#[derive(Default, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ProxyFilter {
+    pub name: Option<String>,
+    pub provider: Option<Provider>,
+    pub region: Option<String>,
+    pub environment: Option<String>,
+    // Comma separated tags, the proxy must have all of them
+    pub tags: Option<String>,
+    // Comma separated `key:value` pairs, the proxy metadata must contain all of them
+    pub metadata: Option<String>,
+}
+
+impl ProxyFilter {
+    pub fn matches(&self, proxy: &Proxy) -> bool {
+        fn same(expected: &Option<String>, actual: &Option<String>) -> bool {
+            expected.is_none() || expected == actual
+        }
+
+        let has_tags = split(&self.tags).all(|tag| proxy.tags.iter().any(|t| t == tag));
+
+        let has_metadata = split(&self.metadata).all(|pair| {
+            let (key, value) = pair.split_once(':').unwrap_or((pair, ""));
+            proxy.metadata.get(key).map(String::as_str) == Some(value)
+        });
+
+        same(&self.name, &proxy.name)
+            && (self.provider.is_none() || self.provider == proxy.provider)
+            && same(&self.region, &proxy.region)
+            && same(&self.environment, &proxy.environment)
+            && has_tags
+            && has_metadata
+    }
+}
+
+fn split(list: &Option<String>) -> impl Iterator<Item = &str> {
+    list.iter()
+        .flat_map(|list| list.split(','))
+        .map(str::trim)
+        .filter(|item| !item.is_empty())
+}
+
+#[async_trait]
+impl FromRequestParts<Services> for ProxyFilter {
+    type Rejection = Error;
+    async fn from_request_parts(
+        parts: &mut Parts,
+        state: &Services,
+    ) -> Result<Self, Self::Rejection> {
+        let Query(filter) = Query::<ProxyFilter>::from_request_parts(parts, state).await?;
+        Ok(filter)
+    }
+}
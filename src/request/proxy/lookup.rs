@@ -0,0 +1,28 @@
+use axum::{
+    async_trait,
+    extract::{FromRequestParts, Query},
+    http::request::Parts,
+};
+use serde::Deserialize;
+use utoipa::IntoParams;
+
+use crate::{error::Error, helper::url::normalize_url, Services};
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct LookupProxyQuery {
+    pub url: String,
+}
+
+#[async_trait]
+impl FromRequestParts<Services> for LookupProxyQuery {
+    type Rejection = Error;
+    async fn from_request_parts(
+        parts: &mut Parts,
+        state: &Services,
+    ) -> Result<Self, Self::Rejection> {
+        let Query(mut query) = Query::<LookupProxyQuery>::from_request_parts(parts, state).await?;
+        query.url = normalize_url(&query.url);
+        Ok(query)
+    }
+}
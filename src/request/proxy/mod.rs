@@ -1,2 +1,8 @@
 pub mod add;
+pub mod bootstrap;
+pub mod breaker;
 pub mod delete;
+pub mod filter;
+pub mod lookup;
+pub mod mode;
+pub mod update;
@@ -0,0 +1,32 @@
+use axum::{async_trait, body::Body, extract::FromRequest, http::Request, Json};
+use serde::Deserialize;
+use utoipa::ToSchema;
+use validator::{Validate, ValidationError};
+
+use crate::{error::Error, models::proxy::ProxyMode, Services};
+
+#[derive(Deserialize, Validate, ToSchema)]
+pub struct SetProxyModeRequest {
+    #[validate(custom = "validate_mode")]
+    pub mode: ProxyMode,
+}
+
+// Replaying is only entered by enabling a proxy
+fn validate_mode(mode: &ProxyMode) -> Result<(), ValidationError> {
+    if *mode == ProxyMode::Replaying {
+        let mut error = ValidationError::new("mode");
+        error.message = Some("Mode must be enabled, disabled or maintenance".into());
+        return Err(error);
+    }
+    Ok(())
+}
+
+#[async_trait]
+impl FromRequest<Services, Body> for SetProxyModeRequest {
+    type Rejection = Error;
+    async fn from_request(req: Request<Body>, state: &Services) -> Result<Self, Self::Rejection> {
+        let Json(body) = Json::<SetProxyModeRequest>::from_request(req, state).await?;
+        body.validate()?;
+        Ok(body)
+    }
+}
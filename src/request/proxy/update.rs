@@ -0,0 +1,121 @@
+use std::collections::BTreeMap;
+
+use axum::{async_trait, body::Body, extract::FromRequest, http::Request, Json};
+use mongodb::bson::{to_bson, Document};
+use serde::Deserialize;
+use utoipa::ToSchema;
+use validator::Validate;
+
+use crate::{
+    error::Error,
+    helper::{nullable::deserialize_nullable, url::normalize_url},
+    models::{
+        protocol::ProtocolVersion,
+        proxy::{Provider, Proxy, Subscription},
+    },
+    request::proxy::add::{validate_metadata, validate_subscriptions, validate_tags},
+    Services,
+};
+
+// Every field is optional, only the given ones are changed.
+// The optional fields of the proxy are cleared when sent as `null`
+
+#[derive(Clone, Default, Deserialize, Validate, ToSchema)]
+pub struct UpdateProxyRequest {
+    #[validate(url(message = "Proxy url is invalid"))]
+    pub url: Option<String>,
+    #[validate(length(min = 1, max = 64, message = "Name must be 1 to 64 characters long"))]
+    #[serde(default, deserialize_with = "deserialize_nullable")]
+    pub name: Option<Option<String>>,
+    #[serde(default, deserialize_with = "deserialize_nullable")]
+    pub provider: Option<Option<Provider>>,
+    #[validate(length(min = 1, max = 64, message = "Region must be 1 to 64 characters long"))]
+    #[serde(default, deserialize_with = "deserialize_nullable")]
+    pub region: Option<Option<String>>,
+    #[validate(length(
+        min = 1,
+        max = 32,
+        message = "Environment must be 1 to 32 characters long"
+    ))]
+    #[serde(default, deserialize_with = "deserialize_nullable")]
+    pub environment: Option<Option<String>>,
+    #[validate(custom = "validate_tags")]
+    pub tags: Option<Vec<String>>,
+    #[validate(custom = "validate_metadata")]
+    pub metadata: Option<BTreeMap<String, String>>,
+    #[validate(custom = "validate_subscriptions")]
+    pub subscriptions: Option<Vec<Subscription>>,
+    // Negotiated again by the hub when the url changes
+    #[serde(skip)]
+    pub protocol: Option<ProtocolVersion>,
+    #[serde(skip)]
+    pub features: Option<Vec<String>>,
+}
+
+impl UpdateProxyRequest {
+    // The `$set` fields of the update
+    pub fn to_document(&self) -> Result<Document, Error> {
+        fn set<T: serde::Serialize>(
+            fields: &mut Document,
+            name: &str,
+            value: &Option<T>,
+        ) -> Result<(), Error> {
+            if let Some(value) = value {
+                fields.insert(name, to_bson(value).map_err(|_| Error::Generic)?);
+            }
+            Ok(())
+        }
+
+        let mut fields = Document::new();
+        set(&mut fields, "url", &self.url)?;
+        set(&mut fields, "name", &self.name)?;
+        set(&mut fields, "provider", &self.provider)?;
+        set(&mut fields, "region", &self.region)?;
+        set(&mut fields, "environment", &self.environment)?;
+        set(&mut fields, "tags", &self.tags)?;
+        set(&mut fields, "metadata", &self.metadata)?;
+        set(&mut fields, "subscriptions", &self.subscriptions)?;
+        set(&mut fields, "protocol", &self.protocol)?;
+        set(&mut fields, "features", &self.features)?;
+        Ok(fields)
+    }
+
+    // The same changes made to a proxy in memory
+    pub fn apply(&self, proxy: &mut Proxy) {
+        fn set<T: Clone>(field: &mut T, value: &Option<T>) {
+            if let Some(value) = value {
+                *field = value.clone();
+            }
+        }
+
+        set(&mut proxy.url, &self.url);
+        set(&mut proxy.name, &self.name);
+        set(&mut proxy.provider, &self.provider);
+        set(&mut proxy.region, &self.region);
+        set(&mut proxy.environment, &self.environment);
+        set(&mut proxy.tags, &self.tags);
+        set(&mut proxy.metadata, &self.metadata);
+        set(&mut proxy.subscriptions, &self.subscriptions);
+        set(&mut proxy.protocol, &self.protocol);
+        set(&mut proxy.features, &self.features);
+    }
+
+    // Whether the update can change which keys the proxy should hold
+    pub fn changes_routing(&self) -> bool {
+        self.provider.is_some()
+            || self.region.is_some()
+            || self.tags.is_some()
+            || self.subscriptions.is_some()
+    }
+}
+
+#[async_trait]
+impl FromRequest<Services, Body> for UpdateProxyRequest {
+    type Rejection = Error;
+    async fn from_request(req: Request<Body>, state: &Services) -> Result<Self, Self::Rejection> {
+        let Json(mut body) = Json::<UpdateProxyRequest>::from_request(req, state).await?;
+        body.validate()?;
+        body.url = body.url.as_deref().map(normalize_url);
+        Ok(body)
+    }
+}
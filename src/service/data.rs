@@ -0,0 +1,119 @@
+use std::{collections::BTreeMap, sync::Arc};
+
+use futures_util::stream::BoxStream;
+use mongodb::bson::DateTime;
+
+use crate::{
+    error::Error,
+    models::{
+        data::{Data, DataPage},
+        operation::SyncOperation,
+    },
+    request::data::{
+        delete::DeleteDataRequest, keys::KeysFilter, precondition::Precondition,
+        set::SetDataRequest, target::TargetSelector,
+    },
+    store::DataStore,
+};
+
+#[derive(Clone)]
+pub struct DataService {
+    store: Arc<dyn DataStore>,
+}
+
+impl DataService {
+    pub fn init(store: &Arc<dyn DataStore>) -> Self {
+        Self {
+            store: store.clone(),
+        }
+    }
+
+    // Applies a write to the hub's own copy of the data, remembering which proxies
+    // the written keys are meant for. Returns every written key as it is now
+    pub async fn apply(
+        &self,
+        operation: &SyncOperation,
+        precondition: &Precondition,
+        targets: Option<&TargetSelector>,
+    ) -> Result<BTreeMap<String, Data>, Error> {
+        let mut written = vec![];
+
+        match operation {
+            SyncOperation::Set(SetDataRequest {
+                _type,
+                key,
+                value,
+                ttl,
+                ..
+            }) => written.extend(
+                self.store
+                    .set_data(*_type, key, value, *ttl, precondition, targets)
+                    .await?,
+            ),
+            SyncOperation::SetMulti(request) => {
+                for (key, value) in request.data.as_object().into_iter().flatten() {
+                    written.extend(
+                        self.store
+                            .set_data(
+                                request.type_of(key),
+                                key,
+                                value,
+                                None,
+                                precondition,
+                                targets,
+                            )
+                            .await?,
+                    );
+                }
+            }
+            SyncOperation::Delete(DeleteDataRequest { key, ttl, .. }) => {
+                written.extend(self.store.delete_data(key, *ttl, precondition).await?)
+            }
+            SyncOperation::Update(request) => {
+                written.extend(self.store.update_data(request, targets).await?)
+            }
+        }
+
+        Ok(written
+            .into_iter()
+            .map(|data| (data.key.clone(), data))
+            .collect())
+    }
+
+    pub async fn get_data(&self, key: &str) -> Result<Data, Error> {
+        self.store
+            .get_data(key)
+            .await?
+            .map(|data| data.with_remaining_ttl(DateTime::now().timestamp_millis()))
+            .ok_or_else(|| Error::DataNotFound)
+    }
+
+    pub async fn get_keys(&self, filter: &KeysFilter) -> Result<DataPage, Error> {
+        let mut page = self.store.get_keys(filter).await?;
+
+        let now = DateTime::now().timestamp_millis();
+        page.items = page
+            .items
+            .into_iter()
+            .map(|data| data.with_remaining_ttl(now))
+            .collect();
+
+        Ok(page)
+    }
+
+    // Every live key, in key order
+    pub async fn snapshot(&self) -> Result<BoxStream<'static, Result<Data, Error>>, Error> {
+        self.store.snapshot().await
+    }
+
+    // The key that expired the longest ago, if any key is over its ttl
+    pub async fn next_expired(&self) -> Result<Option<String>, Error> {
+        self.store.next_expired().await
+    }
+
+    // Turns the key into a tombstone if it is still over its ttl. Doing it here
+    // makes sure only one worker sends its delete to the proxies
+    pub async fn take_expired(&self, key: &str) -> Result<Option<Data>, Error> {
+        self.store.take_expired(key).await
+    }
+}
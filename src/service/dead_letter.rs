@@ -0,0 +1,63 @@
+use std::sync::Arc;
+
+use crate::{
+    error::Error,
+    models::{dead_letter::DeadLetter, delivery::Delivery},
+    request::dead_letter::filter::DeadLetterFilter,
+    store::DeadLetterStore,
+};
+
+#[derive(Clone)]
+pub struct DeadLetterService {
+    store: Arc<dyn DeadLetterStore>,
+}
+
+impl DeadLetterService {
+    pub fn init(store: &Arc<dyn DeadLetterStore>) -> Self {
+        Self {
+            store: store.clone(),
+        }
+    }
+
+    pub async fn get_dead_letters(
+        &self,
+        filter: &DeadLetterFilter,
+    ) -> Result<Vec<DeadLetter>, Error> {
+        self.store.get_dead_letters(filter).await
+    }
+
+    pub async fn get_dead_letter(&self, id: &str) -> Result<DeadLetter, Error> {
+        self.store
+            .get_dead_letter(id)
+            .await?
+            .ok_or_else(|| Error::DeadLetterNotFound)
+    }
+
+    pub async fn add_dead_letter(&self, dead_letter: DeadLetter) -> Result<(), Error> {
+        self.store.add_dead_letter(dead_letter).await
+    }
+
+    pub async fn record_failure(&self, id: &str, delivery: &Delivery) -> Result<(), Error> {
+        self.store.record_failure(id, delivery).await
+    }
+
+    pub async fn delete_dead_letter(&self, id: &str) -> Result<(), Error> {
+        if !self.store.delete_dead_letter(id).await? {
+            return Err(Error::DeadLetterNotFound);
+        }
+        Ok(())
+    }
+
+    pub async fn delete_dead_letters(&self, filter: &DeadLetterFilter) -> Result<u64, Error> {
+        self.store.delete_dead_letters(filter).await
+    }
+
+    // Points the dead letters of a proxy to its new url
+    pub async fn move_dead_letters(&self, from: &str, to: &str) -> Result<(), Error> {
+        self.store.move_dead_letters(from, to).await
+    }
+
+    pub async fn delete_dead_letters_of(&self, url: &str) -> Result<u64, Error> {
+        self.store.delete_dead_letters_of(url).await
+    }
+}
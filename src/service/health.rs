@@ -0,0 +1,120 @@
+use std::{
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use futures_util::future::join_all;
+use mongodb::bson::DateTime;
+use reqwest::Client;
+
+use crate::{
+    config::HealthConfig,
+    error::Error,
+    models::health::{HealthCheck, HealthRecord, HealthStatus},
+    service::proxy::ProxyService,
+    store::HealthStore,
+};
+
+#[derive(Clone)]
+pub struct HealthService {
+    client: Client,
+    timeout: Duration,
+    store: Arc<dyn HealthStore>,
+    proxy_service: ProxyService,
+}
+
+impl HealthService {
+    pub fn init(
+        client: &Client,
+        config: &HealthConfig,
+        store: &Arc<dyn HealthStore>,
+        proxy_service: &ProxyService,
+    ) -> Self {
+        Self {
+            client: client.clone(),
+            timeout: config.timeout,
+            store: store.clone(),
+            proxy_service: proxy_service.clone(),
+        }
+    }
+
+    // Probes every proxy in parallel and stores the outcome,
+    // leaving out the disabled ones and the ones under maintenance
+    pub async fn check_all(&self) -> Result<Vec<HealthCheck>, Error> {
+        let mut proxies = self.proxy_service.get_proxies().await?;
+        proxies.retain(|proxy| proxy.mode.is_monitored());
+
+        let checks = join_all(proxies.iter().map(|proxy| self.probe(&proxy.url))).await;
+
+        for check in &checks {
+            self.proxy_service.record_health(check).await?;
+        }
+
+        if !checks.is_empty() {
+            let records = checks
+                .iter()
+                .map(|check| HealthRecord {
+                    check: check.clone(),
+                    created_at: DateTime::now(),
+                })
+                .collect();
+            self.store.add_records(records).await?;
+        }
+
+        Ok(checks)
+    }
+
+    // The latest check of every proxy, as the monitor stored it,
+    // leaving out the disabled ones and the ones under maintenance
+    pub async fn latest(&self) -> Result<Vec<HealthCheck>, Error> {
+        let mut proxies = self.proxy_service.get_proxies().await?;
+        proxies.retain(|proxy| proxy.mode.is_monitored());
+
+        Ok(proxies
+            .into_iter()
+            .map(|proxy| {
+                proxy
+                    .last_check
+                    .unwrap_or_else(|| HealthCheck::unknown(&proxy.url))
+            })
+            .collect())
+    }
+
+    pub async fn delete_history(&self, url: &str) -> Result<u64, Error> {
+        self.store.delete_history(url).await
+    }
+
+    pub async fn probe(&self, url: &str) -> HealthCheck {
+        let start = Instant::now();
+        let result = self
+            .client
+            .get(format!("{url}/health"))
+            .timeout(self.timeout)
+            .send()
+            .await;
+        let latency_ms = start.elapsed().as_millis() as u64;
+
+        let (http_status, error) = match result {
+            Ok(response) if response.status().is_success() => {
+                (Some(response.status().as_u16()), None)
+            }
+            Ok(response) => (
+                Some(response.status().as_u16()),
+                Some(format!("Proxy responded with {}", response.status())),
+            ),
+            Err(e) => (None, Some(e.to_string())),
+        };
+
+        HealthCheck {
+            url: url.into(),
+            status: match error {
+                None => HealthStatus::Healthy,
+                Some(_) => HealthStatus::Unhealthy,
+            },
+            http_status,
+            latency_ms,
+            error,
+            checked_at: DateTime::now().timestamp_millis(),
+        }
+    }
+}
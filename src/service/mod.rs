@@ -1 +1,6 @@
+pub mod data;
+pub mod dead_letter;
+pub mod health;
+pub mod outbox;
 pub mod proxy;
+pub mod sync;
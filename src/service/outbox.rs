@@ -0,0 +1,132 @@
+use std::{sync::Arc, time::Duration};
+
+use mongodb::bson::{oid::ObjectId, DateTime};
+
+use crate::{
+    config::OutboxConfig,
+    error::Error,
+    models::{
+        delivery::Delivery,
+        operation::SyncOperation,
+        outbox::{OutboxDelivery, OutboxEntry},
+    },
+    store::OutboxStore,
+};
+
+fn after(duration: Duration) -> DateTime {
+    DateTime::from_millis(DateTime::now().timestamp_millis() + duration.as_millis() as i64)
+}
+
+#[derive(Clone)]
+pub struct OutboxService {
+    store: Arc<dyn OutboxStore>,
+    config: OutboxConfig,
+}
+
+impl OutboxService {
+    pub fn init(store: &Arc<dyn OutboxStore>, config: &OutboxConfig) -> Self {
+        Self {
+            store: store.clone(),
+            config: config.clone(),
+        }
+    }
+
+    // Writes the operation down before the hub applies it, leased to the caller
+    // so that the dispatcher leaves it alone until it is released
+    pub async fn hold(&self, operation: &SyncOperation) -> Result<ObjectId, Error> {
+        let entry = OutboxEntry {
+            id: None,
+            operation: operation.clone(),
+            deliveries: vec![],
+            created_at: DateTime::now(),
+            next_attempt_at: after(self.config.lease),
+            held: true,
+        };
+
+        self.store.insert(entry).await
+    }
+
+    // Fills in a held entry with the operation as the hub applied it and the proxies
+    // it is meant for. It stays leased to the caller, who sends it right away.
+    // An entry meant for no proxy is dropped
+    pub async fn release(
+        &self,
+        id: ObjectId,
+        operation: &SyncOperation,
+        urls: &[String],
+    ) -> Result<(), Error> {
+        if urls.is_empty() {
+            return self.discard(id, &[]).await;
+        }
+
+        let deliveries = urls
+            .iter()
+            .map(|url| OutboxDelivery::pending(url))
+            .collect::<Vec<_>>();
+
+        self.store.release(id, operation, &deliveries).await
+    }
+
+    // Claims the oldest entry that is due for delivery, leasing it to the caller
+    pub async fn claim_due(&self) -> Result<Option<OutboxEntry>, Error> {
+        self.store.claim_due(after(self.config.lease)).await
+    }
+
+    // Every entry still waiting to be delivered to the given proxy, oldest first
+    pub async fn pending_for(&self, url: &str) -> Result<Vec<OutboxEntry>, Error> {
+        self.store.pending_for(url).await
+    }
+
+    // Stores the outcome of a delivery round. The entry is removed once every
+    // proxy has acknowledged it, otherwise it is released for a later retry.
+    // Deliveries that ran out of attempts are taken out of the entry and returned
+    pub async fn record(
+        &self,
+        id: ObjectId,
+        deliveries: &[Delivery],
+    ) -> Result<Vec<OutboxDelivery>, Error> {
+        if deliveries.is_empty() {
+            return Ok(vec![]);
+        }
+
+        let Some(entry) = self
+            .store
+            .record(id, deliveries, after(self.config.retry_delay))
+            .await?
+        else {
+            return Ok(vec![]);
+        };
+
+        let exhausted = entry
+            .deliveries
+            .into_iter()
+            .filter(|delivery| !delivery.acked && delivery.attempts >= self.config.max_attempts)
+            .collect::<Vec<_>>();
+
+        let urls = exhausted
+            .iter()
+            .map(|delivery| delivery.url.clone())
+            .collect::<Vec<_>>();
+        self.discard(id, &urls).await?;
+
+        Ok(exhausted)
+    }
+
+    // Drops the deliveries of an entry that can never be made,
+    // e.g. because their proxy has been removed since.
+    // An entry without deliveries left, held ones included, is dropped too
+    pub async fn discard(&self, id: ObjectId, urls: &[String]) -> Result<(), Error> {
+        self.store.discard(id, urls).await
+    }
+
+    // Points the pending deliveries of a proxy to its new url
+    pub async fn move_deliveries(&self, from: &str, to: &str) -> Result<(), Error> {
+        self.store.move_deliveries(from, to).await
+    }
+
+    // Drops every pending delivery of a removed proxy,
+    // returning the number of entries it was waiting for
+    pub async fn remove_deliveries(&self, url: &str) -> Result<u64, Error> {
+        self.store.remove_deliveries(url).await
+    }
+}
@@ -1,58 +1,137 @@
-use mongodb::{bson::doc, Collection, Cursor};
+use std::{sync::Arc, time::Duration};
 
-use crate::{error::Error, models::proxy::Proxy};
+use crate::{
+    error::Error,
+    models::{
+        health::HealthCheck,
+        proxy::{Bootstrap, Proxy, ProxyMode},
+    },
+    request::{
+        data::target::TargetSelector,
+        proxy::{filter::ProxyFilter, update::UpdateProxyRequest},
+    },
+    store::ProxyStore,
+};
 
 #[derive(Clone)]
 pub struct ProxyService {
-    collection: Collection<Proxy>,
+    store: Arc<dyn ProxyStore>,
 }
 
 impl ProxyService {
-    pub fn init(collection: &Collection<Proxy>) -> Self {
+    pub fn init(store: &Arc<dyn ProxyStore>) -> Self {
         Self {
-            collection: collection.clone(),
+            store: store.clone(),
         }
     }
 
-    pub async fn get_proxies(&self) -> Result<Cursor<Proxy>, Error> {
-        let proxies = self.collection.find(None, None).await?;
+    pub async fn get_proxies(&self) -> Result<Vec<Proxy>, Error> {
+        self.store.get_proxies().await
+    }
+
+    pub async fn find_proxies(&self, filter: &ProxyFilter) -> Result<Vec<Proxy>, Error> {
+        let mut proxies = self.get_proxies().await?;
+        proxies.retain(|proxy| filter.matches(proxy));
+        Ok(proxies)
+    }
+
+    // The proxies chosen by a target selector, every proxy without one
+    pub async fn resolve(&self, targets: Option<&TargetSelector>) -> Result<Vec<Proxy>, Error> {
+        let mut proxies = self.get_proxies().await?;
+        proxies.retain(|proxy| targets.is_none_or(|targets| targets.matches(proxy)));
         Ok(proxies)
     }
 
-    pub async fn add_proxy(&self, url: &str) -> Result<Proxy, Error> {
-        // Find if the proxy already exists
-        let exists_proxy = self
-            .collection
-            .count_documents(doc! {"url": url}, None)
+    pub async fn get_proxy(&self, url: &str) -> Result<Proxy, Error> {
+        self.store
+            .get_proxy(url)
             .await?
-            > 0;
+            .ok_or_else(|| Error::ProxyNotFound)
+    }
 
-        if exists_proxy {
-            return Err(Error::ProxyAlreadyExists);
-        }
+    pub async fn get_proxy_by_id(&self, id: &str) -> Result<Proxy, Error> {
+        self.store
+            .get_proxy_by_id(id)
+            .await?
+            .ok_or_else(|| Error::ProxyNotFound)
+    }
+
+    pub async fn add_proxy(&self, proxy: Proxy) -> Result<Proxy, Error> {
+        self.store.add_proxy(proxy).await
+    }
 
-        // Add the proxy into the database and gets its ID
-        let new_proxy_id = self
-            .collection
-            .insert_one(Proxy { url: url.into() }, None)
+    pub async fn update_proxy(
+        &self,
+        id: &str,
+        update: &UpdateProxyRequest,
+    ) -> Result<Proxy, Error> {
+        self.store
+            .update_proxy(id, update)
             .await?
-            .inserted_id
-            .as_object_id()
-            .ok_or_else(|| Error::Generic)?;
-
-        // Get the new proxy
-        let new_proxy = self
-            .collection
-            .find_one(doc! {"_id": new_proxy_id}, None)
+            .ok_or_else(|| Error::ProxyNotFound)
+    }
+
+    pub async fn set_mode(&self, id: &str, mode: ProxyMode) -> Result<Proxy, Error> {
+        self.store
+            .set_mode(id, mode)
             .await?
-            .ok_or_else(|| Error::CannotCreateProxy)?;
+            .ok_or_else(|| Error::ProxyNotFound)
+    }
 
-        Ok(new_proxy)
+    pub async fn transition(
+        &self,
+        url: &str,
+        from: ProxyMode,
+        to: ProxyMode,
+    ) -> Result<bool, Error> {
+        self.store.transition(url, from, to).await
     }
 
-    pub async fn delete_proxy(&self, url: &str) -> Result<(), Error> {
-        // Just delete the proxy
-        self.collection.delete_one(doc! {"url": url}, None).await?;
-        Ok(())
+    pub async fn delete_proxy(&self, url: &str) -> Result<Proxy, Error> {
+        self.store
+            .delete_proxy(url)
+            .await?
+            .ok_or_else(|| Error::ProxyNotFound)
+    }
+
+    pub async fn delete_proxy_by_id(&self, id: &str) -> Result<Proxy, Error> {
+        self.store
+            .delete_proxy_by_id(id)
+            .await?
+            .ok_or_else(|| Error::ProxyNotFound)
+    }
+
+    pub async fn update_bootstrap(&self, url: &str, bootstrap: &Bootstrap) -> Result<(), Error> {
+        self.store.update_bootstrap(url, bootstrap).await
+    }
+
+    pub async fn claim_bootstrap(&self, url: &str) -> Result<bool, Error> {
+        self.store.claim_bootstrap(url).await
+    }
+
+    pub async fn record_health(&self, check: &HealthCheck) -> Result<(), Error> {
+        self.store.record_health(check).await
+    }
+
+    pub async fn half_open(&self, url: &str, cool_down: Duration) -> Result<bool, Error> {
+        self.store.half_open(url, cool_down).await
+    }
+
+    pub async fn record_delivery(
+        &self,
+        url: &str,
+        success: bool,
+        failure_threshold: u32,
+    ) -> Result<(), Error> {
+        self.store
+            .record_delivery(url, success, failure_threshold)
+            .await
+    }
+
+    pub async fn reset_breaker(&self, url: &str) -> Result<Proxy, Error> {
+        self.store
+            .reset_breaker(url)
+            .await?
+            .ok_or_else(|| Error::ProxyNotFound)
     }
 }
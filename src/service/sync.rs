@@ -0,0 +1,1222 @@
+use std::{
+    collections::{BTreeMap, HashSet},
+    sync::Arc,
+    time::Instant,
+};
+
+use futures_util::{future::join_all, TryStreamExt};
+use mongodb::bson::{oid::ObjectId, DateTime};
+use reqwest::Client;
+use serde_json::Map;
+use tokio::{sync::RwLock, time::sleep};
+
+use crate::{
+    config::{BreakerConfig, RetryPolicy, SyncConfig},
+    error::Error,
+    helper::ordering::{KeyQueues, Turn},
+    models::{
+        data::{Data, DataType},
+        dead_letter::DeadLetter,
+        delivery::{Delivery, DeliveryReport},
+        digest::{hash_value, Digest, DriftReport, DIGEST_PATH},
+        operation::SyncOperation,
+        outbox::delivery_id,
+        protocol::{Capabilities, Envelope, ProtocolVersion, CAPABILITIES_PATH},
+        proxy::{Bootstrap, BootstrapStatus, BreakerState, Proxy, ProxyMode},
+    },
+    request::{
+        data::{
+            delete::DeleteDataRequest,
+            precondition::Precondition,
+            set::{SetDataRequest, SetMultiDataRequest},
+            target::TargetSelector,
+        },
+        proxy::update::UpdateProxyRequest,
+    },
+    service::{
+        data::DataService, dead_letter::DeadLetterService, outbox::OutboxService,
+        proxy::ProxyService,
+    },
+};
+
+// The lane of the hub's own copy of the data, which no proxy url can take
+const HUB_LANE: &str = "";
+
+#[derive(Clone)]
+pub struct SyncService {
+    client: Client,
+    retry: RetryPolicy,
+    bootstrap_batch_size: usize,
+    breaker: BreakerConfig,
+    origin: String,
+    proxy_service: ProxyService,
+    data_service: DataService,
+    outbox_service: OutboxService,
+    dead_letter_service: DeadLetterService,
+    key_queues: KeyQueues,
+    // Taken for writing while a proxy changes url, and for reading by the dispatcher and
+    // by writes until they are in the outbox, so that no write is queued for a url that
+    // no proxy has anymore, and the dispatcher never finds one
+    moving: Arc<RwLock<()>>,
+}
+
+impl SyncService {
+    pub fn init(
+        client: &Client,
+        config: &SyncConfig,
+        proxy_service: &ProxyService,
+        data_service: &DataService,
+        outbox_service: &OutboxService,
+        dead_letter_service: &DeadLetterService,
+    ) -> Self {
+        Self {
+            client: client.clone(),
+            retry: config.retry.clone(),
+            bootstrap_batch_size: config.bootstrap_batch_size,
+            breaker: config.breaker.clone(),
+            origin: config.origin.clone(),
+            proxy_service: proxy_service.clone(),
+            data_service: data_service.clone(),
+            outbox_service: outbox_service.clone(),
+            dead_letter_service: dead_letter_service.clone(),
+            key_queues: KeyQueues::default(),
+            moving: Arc::default(),
+        }
+    }
+
+    // Sends a write to every proxy. The write is held in the outbox, applied to
+    // the hub's own copy of the data, then released to the proxies, so whatever
+    // could not be delivered here is picked up later by the dispatcher
+    pub async fn sync(&self, mut operation: SyncOperation) -> Result<DeliveryReport, Error> {
+        let targets = operation.take_targets();
+        let precondition = operation.take_precondition();
+
+        // Writes of the same key are applied and lined up for the proxies one at a time,
+        // so that every proxy receives them in the order the hub applied them
+        let mut applied = self.key_queues.enter(HUB_LANE, &operation.keys());
+        applied.wait().await;
+
+        // Held before it is applied, so that the dispatcher brings the proxies up to date
+        // if the write does not make it to the outbox in full
+        let id = self.outbox_service.hold(&operation).await?;
+
+        // Applying before listing the proxies makes sure that a proxy registered
+        // in the meantime gets the write either from its bootstrap or from the outbox
+        let written = match self
+            .data_service
+            .apply(&operation, &precondition, targets.as_ref())
+            .await
+        {
+            Ok(written) => written,
+            Err(e) => {
+                self.outbox_service.discard(id, &[]).await?;
+                return Err(e);
+            }
+        };
+        operation.stamp(&written);
+
+        // The write is in place on the hub from here on, so the client is not told it failed
+        // when it cannot be sent now: its held entry is recovered once its lease is over
+        let mut report = match self
+            .distribute(id, &operation, targets.as_ref(), Some(applied))
+            .await
+        {
+            Ok(report) => report,
+            Err(e) => {
+                eprintln!("Cannot send the write of {:?}: {e}", operation.keys());
+                vec![].into()
+            }
+        };
+
+        // Writes of a single key report the version the key is at now
+        if let [key] = operation.keys().as_slice() {
+            report.version = written.get(key).map(|data| data.version);
+        }
+
+        Ok(report)
+    }
+
+    // Sends a write that the hub already holds to the proxies it is meant for,
+    // through the held outbox entry. The hub's turn on the keys, if any, is given up
+    // once the write is lined up for every proxy
+    async fn distribute(
+        &self,
+        id: ObjectId,
+        operation: &SyncOperation,
+        targets: Option<&TargetSelector>,
+        applied: Option<Turn>,
+    ) -> Result<DeliveryReport, Error> {
+        let moving = self.moving.read().await;
+
+        // Proxies that did not subscribe to any of the keys are left out
+        let proxies = self
+            .proxy_service
+            .resolve(targets)
+            .await?
+            .into_iter()
+            .filter(|proxy| operation.for_proxy(proxy).is_some())
+            .collect::<Vec<_>>();
+
+        let urls = proxies
+            .iter()
+            .map(|proxy| proxy.url.clone())
+            .collect::<Vec<_>>();
+
+        self.outbox_service.release(id, operation, &urls).await?;
+        drop(moving);
+
+        if proxies.is_empty() {
+            return Ok(vec![].into());
+        }
+
+        // Proxies that cannot take writes yet keep this one in the outbox until they can
+        let (ready, waiting) = self.admit(proxies).await?;
+
+        let turns = self.line_up(operation, &ready);
+        drop(applied);
+
+        let (deliveries, turns) = self.fan_out(id, operation, &ready, turns).await;
+        self.settle(id, operation, &deliveries).await?;
+        drop(turns);
+
+        Ok(deliveries
+            .into_iter()
+            .chain(waiting.iter().map(|proxy| Delivery::queued(&proxy.url)))
+            .collect::<Vec<_>>()
+            .into())
+    }
+
+    // Deletes every key that is over its ttl from the proxies it was sent to,
+    // so that proxies without expiry support or with a drifting clock converge.
+    // Returns how many keys expired
+    pub async fn expire_due(&self) -> Result<usize, Error> {
+        let mut expired = 0;
+
+        // A key is removed from the hub before its delete is queued, so a crash
+        // in between leaves it on the proxies until the next reconcile removes it
+        while let Some(key) = self.data_service.next_expired().await? {
+            // Taken like a write of the key, so that the delete is lined up
+            // for the proxies after the writes applied before it
+            let mut applied = self.key_queues.enter(HUB_LANE, std::slice::from_ref(&key));
+            applied.wait().await;
+
+            // The key may have been written again while waiting
+            let Some(data) = self.data_service.take_expired(&key).await? else {
+                continue;
+            };
+
+            let operation = SyncOperation::Delete(DeleteDataRequest {
+                _type: data._type,
+                key: data.key,
+                ttl: None,
+                precondition: Precondition::default(),
+                version: Some(data.version),
+                targets: None,
+            });
+            let id = self.outbox_service.hold(&operation).await?;
+            self.distribute(id, &operation, data.targets.as_ref(), Some(applied))
+                .await?;
+            expired += 1;
+        }
+
+        Ok(expired)
+    }
+
+    // Brings the proxies up to date with the keys of a write that was interrupted before
+    // its outbox entry was released. Whether the hub applied the write or not, they are
+    // sent what the hub holds now, which is where the write left the keys either way
+    async fn recover(&self, id: ObjectId, operation: &SyncOperation) -> Result<(), Error> {
+        for key in operation.keys() {
+            let mut applied = self.key_queues.enter(HUB_LANE, std::slice::from_ref(&key));
+            applied.wait().await;
+
+            let (current, targets) = match self.data_service.get_data(&key).await {
+                Ok(data) => (
+                    SyncOperation::Set(SetDataRequest {
+                        _type: data._type,
+                        key: data.key,
+                        value: data.value,
+                        ttl: data.remaining_ttl,
+                        precondition: Precondition::default(),
+                        version: Some(data.version),
+                        targets: None,
+                    }),
+                    data.targets,
+                ),
+                Err(Error::DataNotFound) => (
+                    SyncOperation::Delete(DeleteDataRequest {
+                        _type: operation.data_type(),
+                        key,
+                        ttl: None,
+                        precondition: Precondition::default(),
+                        version: None,
+                        targets: None,
+                    }),
+                    None,
+                ),
+                Err(e) => return Err(e),
+            };
+
+            let held = self.outbox_service.hold(&current).await?;
+            self.distribute(held, &current, targets.as_ref(), Some(applied))
+                .await?;
+        }
+
+        self.outbox_service.discard(id, &[]).await
+    }
+
+    // Delivers every outbox entry that is due, oldest first,
+    // and returns how many entries were processed
+    pub async fn dispatch_due(&self) -> Result<usize, Error> {
+        let mut processed = 0;
+
+        while let Some(entry) = self.outbox_service.claim_due().await? {
+            let id = entry.id.ok_or_else(|| Error::Generic)?;
+
+            if entry.held {
+                self.recover(id, &entry.operation).await?;
+                processed += 1;
+                continue;
+            }
+
+            let _moving = self.moving.read().await;
+
+            let pending = entry
+                .deliveries
+                .into_iter()
+                .filter(|delivery| !delivery.acked)
+                .map(|delivery| delivery.url)
+                .collect::<Vec<_>>();
+
+            let proxies = self.proxy_service.get_proxies().await?;
+
+            // Proxies that were removed in the meantime will never acknowledge,
+            // and the ones that unsubscribed from these keys do not need to
+            let removed = pending
+                .iter()
+                .filter(|url| {
+                    !proxies.iter().any(|proxy| {
+                        &proxy.url == *url && entry.operation.for_proxy(proxy).is_some()
+                    })
+                })
+                .cloned()
+                .collect::<Vec<_>>();
+            if !removed.is_empty() {
+                self.outbox_service.discard(id, &removed).await?;
+            }
+
+            // Proxies that cannot take writes yet are left for a later round
+            let (targets, _) = self
+                .admit(
+                    proxies
+                        .into_iter()
+                        .filter(|proxy| {
+                            pending.contains(&proxy.url) && !removed.contains(&proxy.url)
+                        })
+                        .collect(),
+                )
+                .await?;
+
+            let turns = self.line_up(&entry.operation, &targets);
+            let (deliveries, turns) = self.fan_out(id, &entry.operation, &targets, turns).await;
+            self.settle(id, &entry.operation, &deliveries).await?;
+            drop(turns);
+
+            processed += 1;
+        }
+
+        Ok(processed)
+    }
+
+    // Splits proxies into the ones that can receive writes right away
+    // and the ones whose writes have to wait in the outbox
+    async fn admit(&self, proxies: Vec<Proxy>) -> Result<(Vec<Proxy>, Vec<Proxy>), Error> {
+        let mut ready = vec![];
+        let mut waiting = vec![];
+
+        for proxy in proxies {
+            let admitted = proxy.is_ready()
+                && match proxy.breaker.state {
+                    BreakerState::Closed => true,
+                    // Once the cool down is over, one request is let through to probe the proxy
+                    BreakerState::Open | BreakerState::HalfOpen => {
+                        self.proxy_service
+                            .half_open(&proxy.url, self.breaker.cool_down)
+                            .await?
+                    }
+                };
+
+            if admitted {
+                ready.push(proxy);
+            } else {
+                waiting.push(proxy);
+            }
+        }
+
+        Ok((ready, waiting))
+    }
+
+    // Whether an older write of the same keys is still waiting in the outbox for the proxy.
+    // The writes lined up ahead are settled by the time this is asked, so such a write
+    // failed or could not be sent, and is retried from the outbox before this one
+    async fn is_behind(
+        &self,
+        id: ObjectId,
+        operation: &SyncOperation,
+        url: &str,
+    ) -> Result<bool, Error> {
+        let keys = operation.keys();
+
+        Ok(self
+            .outbox_service
+            .pending_for(url)
+            .await?
+            .iter()
+            .filter(|entry| entry.id.is_some_and(|entry| entry < id))
+            .any(|entry| entry.operation.keys().iter().any(|key| keys.contains(key))))
+    }
+
+    // Starts bootstrapping a proxy in the background
+    pub fn spawn_bootstrap(&self, url: &str) {
+        let sync_service = self.clone();
+        let url = url.to_string();
+
+        tokio::spawn(async move {
+            if let Err(e) = sync_service.bootstrap(&url).await {
+                eprintln!("Bootstrap of {url} failed: {e}");
+            }
+        });
+    }
+
+    // Sends the full state of the hub to a proxy in batches, then the writes that
+    // were queued for it meanwhile, and only then lets it receive writes directly
+    pub async fn bootstrap(&self, url: &str) -> Result<(), Error> {
+        let proxy = self.proxy_service.get_proxy(url).await?;
+
+        // Only the keys meant for this proxy are sent
+        let total = self
+            .data_service
+            .snapshot()
+            .await?
+            .try_fold(0, |total, data| {
+                std::future::ready(Ok(total + data.routes_to(&proxy) as u64))
+            })
+            .await?;
+
+        let mut progress = Bootstrap {
+            status: BootstrapStatus::Running,
+            sent: 0,
+            total,
+            error: None,
+        };
+        self.proxy_service.update_bootstrap(url, &progress).await?;
+
+        let mut snapshot = self.data_service.snapshot().await?;
+        let mut batch = Map::new();
+        let mut types = BTreeMap::new();
+        let mut versions = BTreeMap::new();
+
+        loop {
+            let next = snapshot.try_next().await?;
+            let done = next.is_none();
+
+            if let Some(data) = next.filter(|data| data.routes_to(&proxy)) {
+                types.insert(data.key.clone(), data._type);
+                versions.insert(data.key.clone(), data.version);
+                batch.insert(data.key, data.value);
+            }
+
+            if batch.len() >= self.bootstrap_batch_size || (done && !batch.is_empty()) {
+                let sent = batch.len() as u64;
+                // Sent with the versions of the keys, so that the proxy drops the writes
+                // queued meanwhile that the snapshot already holds
+                let operation = SyncOperation::SetMulti(SetMultiDataRequest {
+                    _type: DataType::Multi,
+                    data: std::mem::take(&mut batch).into(),
+                    types: std::mem::take(&mut types),
+                    versions: Some(std::mem::take(&mut versions)),
+                    targets: None,
+                });
+
+                let delivery = self
+                    .deliver(&operation, url, proxy.protocol, &ObjectId::new().to_hex())
+                    .await;
+                if !delivery.is_success() {
+                    progress.status = BootstrapStatus::Failed;
+                    progress.error = delivery.error;
+                    return self.proxy_service.update_bootstrap(url, &progress).await;
+                }
+
+                progress.sent += sent;
+                self.proxy_service.update_bootstrap(url, &progress).await?;
+            }
+
+            if done {
+                break;
+            }
+        }
+
+        // Deliver the writes queued while the snapshot was being sent
+        if let Some(delivery) = self.drain(&proxy).await? {
+            progress.status = BootstrapStatus::Failed;
+            progress.error = delivery.error;
+            return self.proxy_service.update_bootstrap(url, &progress).await;
+        }
+
+        progress.status = BootstrapStatus::Completed;
+        self.proxy_service.update_bootstrap(url, &progress).await?;
+
+        // Writes queued right before the proxy went live are left to the dispatcher
+        // if this last pass fails, since the proxy is already receiving writes
+        self.drain(&proxy).await?;
+
+        Ok(())
+    }
+
+    // Replays the writes buffered for a proxy in the background
+    pub fn spawn_replay(&self, proxy: Proxy) {
+        let sync_service = self.clone();
+
+        tokio::spawn(async move {
+            if let Err(e) = sync_service.replay_buffer(&proxy).await {
+                eprintln!("Replay of {} failed: {e}", proxy.url);
+            }
+        });
+    }
+
+    // Sends the writes buffered while a proxy was disabled, in order, and only
+    // then lets it receive writes directly. The proxy stays in replaying mode
+    // if a write cannot be delivered, and can be disabled and enabled to retry
+    pub async fn replay_buffer(&self, proxy: &Proxy) -> Result<(), Error> {
+        if let Some(delivery) = self.drain(proxy).await? {
+            eprintln!(
+                "Replay of {} stopped: {}",
+                proxy.url,
+                delivery.error.unwrap_or_default()
+            );
+            return Ok(());
+        }
+
+        // The proxy may have been disabled again in the meantime
+        if !self
+            .proxy_service
+            .transition(&proxy.url, ProxyMode::Replaying, ProxyMode::Enabled)
+            .await?
+        {
+            return Ok(());
+        }
+
+        // Writes buffered right before the proxy went live are left to the dispatcher
+        // if this last pass fails, since the proxy is already receiving writes
+        self.drain(proxy).await?;
+
+        Ok(())
+    }
+
+    // Delivers the outbox entries pending for one proxy in order, stopping at
+    // the first failure so that later writes do not overtake it.
+    // Returns the failed delivery, if any
+    async fn drain(&self, proxy: &Proxy) -> Result<Option<Delivery>, Error> {
+        let url = proxy.url.as_str();
+
+        for entry in self.outbox_service.pending_for(url).await? {
+            let id = entry.id.ok_or_else(|| Error::Generic)?;
+
+            let Some(operation) = entry.operation.for_proxy(proxy) else {
+                self.outbox_service.discard(id, &[url.into()]).await?;
+                continue;
+            };
+
+            let mut turn = self.key_queues.enter(url, &operation.keys());
+            turn.wait().await;
+
+            let delivery = self
+                .deliver(&operation, url, proxy.protocol, &delivery_id(&id, url))
+                .await;
+            self.settle(id, &entry.operation, std::slice::from_ref(&delivery))
+                .await?;
+            drop(turn);
+
+            if !delivery.is_success() {
+                return Ok(Some(delivery));
+            }
+        }
+
+        Ok(None)
+    }
+
+    // Records a delivery round in the outbox and on the breakers,
+    // and moves the deliveries that ran out of attempts to the dead letters.
+    // Deliveries left queued were not attempted, and are left out.
+    // Settled before the turns on the keys are given up, so that the next write
+    // lined up behind sees whether this one is still waiting in the outbox
+    async fn settle(
+        &self,
+        id: ObjectId,
+        operation: &SyncOperation,
+        deliveries: &[Delivery],
+    ) -> Result<(), Error> {
+        let deliveries = deliveries
+            .iter()
+            .filter(|delivery| !delivery.queued)
+            .cloned()
+            .collect::<Vec<_>>();
+
+        let exhausted = self.outbox_service.record(id, &deliveries).await?;
+
+        for delivery in &deliveries {
+            self.proxy_service
+                .record_delivery(
+                    &delivery.url,
+                    delivery.is_success(),
+                    self.breaker.failure_threshold,
+                )
+                .await?;
+        }
+
+        for delivery in exhausted {
+            // Only the part the proxy subscribed to is kept for replaying
+            let operation = match self.proxy_service.get_proxy(&delivery.url).await {
+                Ok(proxy) => operation.for_proxy(&proxy),
+                Err(_) => None,
+            }
+            .unwrap_or_else(|| operation.clone());
+
+            self.dead_letter_service
+                .add_dead_letter(DeadLetter::new(
+                    &delivery.url,
+                    &operation,
+                    &delivery_id(&id, &delivery.url),
+                    delivery.attempts,
+                    delivery.last_error,
+                ))
+                .await?;
+        }
+
+        Ok(())
+    }
+
+    // Changes a proxy, and keeps the writes queued for it when its url changes.
+    // The dispatcher and the writes being queued wait meanwhile, so that none of them
+    // is left behind under the old url and dropped as a write of a removed proxy
+    pub async fn update_proxy(
+        &self,
+        id: &str,
+        request: &UpdateProxyRequest,
+    ) -> Result<Proxy, Error> {
+        let _moving = self.moving.write().await;
+
+        let proxy = self.proxy_service.get_proxy_by_id(id).await?;
+        let updated = self.proxy_service.update_proxy(id, request).await?;
+
+        if updated.url != proxy.url {
+            self.outbox_service
+                .move_deliveries(&proxy.url, &updated.url)
+                .await?;
+            self.dead_letter_service
+                .move_dead_letters(&proxy.url, &updated.url)
+                .await?;
+        }
+
+        Ok(updated)
+    }
+
+    // Forgets the queued and dead-lettered writes of a removed proxy,
+    // returning how many of each were dropped
+    pub async fn purge_proxy(&self, url: &str) -> Result<(u64, u64), Error> {
+        let deliveries = self.outbox_service.remove_deliveries(url).await?;
+        let dead_letters = self.dead_letter_service.delete_dead_letters_of(url).await?;
+        Ok((deliveries, dead_letters))
+    }
+
+    // Reconciles one proxy in the background
+    pub fn spawn_reconcile(&self, proxy: Proxy) {
+        let sync_service = self.clone();
+
+        tokio::spawn(async move {
+            if let Err(e) = sync_service.reconcile(&proxy, false, false).await {
+                eprintln!("Reconcile of {} failed: {e}", proxy.url);
+            }
+        });
+    }
+
+    // Compares the ready proxies (or only the given one) with the hub
+    pub async fn reconcile_all(
+        &self,
+        url: Option<&str>,
+        dry_run: bool,
+        delete_unknown: bool,
+    ) -> Result<Vec<DriftReport>, Error> {
+        let proxies = match url {
+            Some(url) => vec![self.proxy_service.get_proxy(url).await?],
+            None => self.proxy_service.get_proxies().await?,
+        };
+
+        // Proxies that are still bootstrapping are about to receive the full state anyway,
+        // and the ones with a tripped breaker will catch up from the outbox first
+        let tasks = proxies
+            .iter()
+            .filter(|proxy| proxy.is_ready() && proxy.breaker.state == BreakerState::Closed)
+            .map(|proxy| self.reconcile(proxy, dry_run, delete_unknown));
+
+        join_all(tasks).await.into_iter().collect()
+    }
+
+    // Compares the digest of a proxy with the hub's data, and unless this is
+    // a dry run, pushes the current hub value of every key that drifted.
+    // Keys the hub does not know at all are only deleted with `delete_unknown`
+    pub async fn reconcile(
+        &self,
+        proxy: &Proxy,
+        dry_run: bool,
+        delete_unknown: bool,
+    ) -> Result<DriftReport, Error> {
+        let url = proxy.url.as_str();
+        let mut report = DriftReport {
+            url: url.into(),
+            ..Default::default()
+        };
+
+        let Digest { mut keys } = match self.fetch_digest(url).await {
+            Ok(digest) => digest,
+            Err(e) => {
+                report.error = Some(e);
+                return Ok(report);
+            }
+        };
+
+        // Keys that are not meant for the proxy count as missing from the hub
+        let mut routed_away = vec![];
+        let mut snapshot = self.data_service.snapshot().await?;
+        while let Some(data) = snapshot.try_next().await? {
+            if !data.routes_to(proxy) {
+                if keys.remove(&data.key).is_some() {
+                    routed_away.push(data.key);
+                }
+                continue;
+            }
+
+            let Data { key, value, .. } = data;
+            match keys.remove(&key) {
+                None => report.missing.push(key),
+                Some(hash) if hash != hash_value(&value) => report.stale.push(key),
+                Some(_) => {}
+            }
+        }
+        // Whatever the hub does not know is left over. These keys may have been
+        // synced before the hub kept its own copy of the data, so they are kept
+        // unless asked otherwise
+        let unknown = keys.into_keys().collect::<Vec<_>>();
+        report.extra = routed_away.iter().chain(&unknown).cloned().collect();
+
+        // Writes still in the outbox will bring these keys up to date on their own,
+        // and pushing them now could be overtaken by an older queued write
+        let pending = self
+            .outbox_service
+            .pending_for(url)
+            .await?
+            .iter()
+            .flat_map(|entry| entry.operation.keys())
+            .collect::<HashSet<_>>();
+
+        let drifted = report
+            .missing
+            .iter()
+            .chain(&report.stale)
+            .chain(&routed_away)
+            .chain(unknown.iter().filter(|_| delete_unknown))
+            .cloned()
+            .collect::<Vec<_>>();
+
+        let (skipped, to_repair): (Vec<_>, Vec<_>) =
+            drifted.into_iter().partition(|key| pending.contains(key));
+        report.skipped = skipped;
+
+        if dry_run {
+            return Ok(report);
+        }
+
+        for key in to_repair {
+            if self.repair(&key, proxy).await?.is_success() {
+                report.repaired += 1;
+            } else {
+                report.failed += 1;
+            }
+        }
+
+        Ok(report)
+    }
+
+    async fn fetch_digest(&self, url: &str) -> Result<Digest, String> {
+        let response = self
+            .client
+            .get(format!("{url}{DIGEST_PATH}"))
+            .send()
+            .await
+            .map_err(|e| e.to_string())?;
+
+        let status = response.status();
+        if !status.is_success() {
+            return Err(format!("Proxy responded with {status}"));
+        }
+
+        response.json().await.map_err(|e| e.to_string())
+    }
+
+    // Sends the current hub value of a key to one proxy,
+    // or deletes the key there if the hub does not have it for that proxy.
+    // Repairs carry no version, so that the proxy applies them whatever it holds
+    async fn repair(&self, key: &str, proxy: &Proxy) -> Result<Delivery, Error> {
+        // Lined up before reading, so that a write applied after the read
+        // reaches the proxy after the repair
+        let mut turn = self.key_queues.enter(&proxy.url, &[key.into()]);
+        turn.wait().await;
+
+        // The value is read again since it may have changed during the comparison
+        let operation = match self.data_service.get_data(key).await {
+            Ok(data) if data.routes_to(proxy) => SyncOperation::Set(SetDataRequest {
+                _type: data._type,
+                key: data.key,
+                value: data.value,
+                ttl: data.remaining_ttl,
+                precondition: Precondition::default(),
+                version: None,
+                targets: None,
+            }),
+            Ok(_) | Err(Error::DataNotFound) => SyncOperation::Delete(DeleteDataRequest {
+                _type: DataType::Multi,
+                key: key.into(),
+                ttl: None,
+                precondition: Precondition::default(),
+                version: None,
+                targets: None,
+            }),
+            Err(e) => return Err(e),
+        };
+
+        Ok(self
+            .deliver(
+                &operation,
+                &proxy.url,
+                proxy.protocol,
+                &ObjectId::new().to_hex(),
+            )
+            .await)
+    }
+
+    // Sends dead letters to their proxy again. Dead letters of the same proxy are
+    // replayed one after another in their original order, and removed once delivered
+    pub async fn replay(&self, dead_letters: Vec<DeadLetter>) -> Result<DeliveryReport, Error> {
+        let mut by_url: Vec<(String, Vec<DeadLetter>)> = vec![];
+        for dead_letter in dead_letters {
+            match by_url.iter_mut().find(|(url, _)| url == &dead_letter.url) {
+                Some((_, group)) => group.push(dead_letter),
+                None => by_url.push((dead_letter.url.clone(), vec![dead_letter])),
+            }
+        }
+
+        let tasks = by_url.into_iter().map(|(url, group)| async move {
+            // Proxies removed since are sent the oldest version
+            let version = match self.proxy_service.get_proxy(&url).await {
+                Ok(proxy) => proxy.protocol,
+                Err(Error::ProxyNotFound) => ProtocolVersion::V1,
+                Err(e) => return Err(e),
+            };
+
+            let mut deliveries = vec![];
+            for dead_letter in group {
+                // Dead letters from before delivery ids were kept get a new one
+                let id = dead_letter
+                    .delivery_id
+                    .clone()
+                    .unwrap_or_else(|| ObjectId::new().to_hex());
+
+                // Lined up with the writes of the same keys being sent to the proxy
+                let mut turn = self
+                    .key_queues
+                    .enter(&dead_letter.url, &dead_letter.operation.keys());
+                turn.wait().await;
+                let delivery = self
+                    .deliver(&dead_letter.operation, &dead_letter.url, version, &id)
+                    .await;
+                drop(turn);
+                if delivery.is_success() {
+                    self.dead_letter_service
+                        .delete_dead_letter(&dead_letter.id)
+                        .await?;
+                } else {
+                    self.dead_letter_service
+                        .record_failure(&dead_letter.id, &delivery)
+                        .await?;
+                }
+                deliveries.push(delivery);
+            }
+            Ok::<_, Error>(deliveries)
+        });
+
+        let mut deliveries = vec![];
+        for group in join_all(tasks).await {
+            deliveries.extend(group?);
+        }
+
+        Ok(deliveries.into())
+    }
+
+    // Asks a proxy which protocol versions it speaks, and picks the newest one
+    // the hub speaks too, along with the features the proxy supports.
+    // Proxies that cannot tell are sent v1 without any feature
+    pub async fn negotiate(&self, url: &str) -> (ProtocolVersion, Vec<String>) {
+        let response = self
+            .client
+            .get(format!("{url}{CAPABILITIES_PATH}"))
+            .send()
+            .await
+            .and_then(|response| response.error_for_status());
+
+        match response {
+            Ok(response) => response
+                .json::<Capabilities>()
+                .await
+                .map(|capabilities| {
+                    (
+                        ProtocolVersion::negotiate(&capabilities),
+                        capabilities.features,
+                    )
+                })
+                .unwrap_or_default(),
+            Err(_) => (ProtocolVersion::V1, vec![]),
+        }
+    }
+
+    // Takes a turn on the keys of the operation for every proxy
+    fn line_up(&self, operation: &SyncOperation, proxies: &[Proxy]) -> Vec<Turn> {
+        let keys = operation.keys();
+        proxies
+            .iter()
+            .map(|proxy| self.key_queues.enter(&proxy.url, &keys))
+            .collect()
+    }
+
+    pub async fn fan_out(
+        &self,
+        entry: ObjectId,
+        operation: &SyncOperation,
+        proxies: &[Proxy],
+        turns: Vec<Turn>,
+    ) -> (Vec<Delivery>, Vec<Turn>) {
+        // Send the operation to every proxy in parallel, keeping every outcome.
+        // Each proxy only gets the keys it subscribed to, once the writes
+        // lined up before this one on these keys have been delivered to it.
+        // A proxy that an older write of these keys is still waiting for is left
+        // to the dispatcher. The turns are handed back, to be given up once settled
+        let tasks = proxies.iter().zip(turns).filter_map(|(proxy, mut turn)| {
+            let operation = operation.for_proxy(proxy)?;
+            Some(async move {
+                turn.wait().await;
+                let behind = self
+                    .is_behind(entry, &operation, &proxy.url)
+                    .await
+                    .unwrap_or(true);
+                if behind {
+                    return (Delivery::queued(&proxy.url), turn);
+                }
+
+                let id = delivery_id(&entry, &proxy.url);
+                let delivery = self
+                    .deliver(&operation, &proxy.url, proxy.protocol, &id)
+                    .await;
+                (delivery, turn)
+            })
+        });
+
+        join_all(tasks).await.into_iter().unzip()
+    }
+
+    // Sends the operation to one proxy under the given envelope id,
+    // retrying it as long as the retry policy allows
+    pub async fn deliver(
+        &self,
+        operation: &SyncOperation,
+        url: &str,
+        version: ProtocolVersion,
+        id: &str,
+    ) -> Delivery {
+        let mut attempts = 0;
+        loop {
+            attempts += 1;
+            let (delivery, retryable) = self.attempt(operation, url, version, id).await;
+
+            // Stop on success, on a failure that retrying cannot fix,
+            // or once the retry policy has been exhausted
+            if delivery.is_success() || !retryable || attempts >= self.retry.max_attempts {
+                return Delivery {
+                    attempts,
+                    ..delivery
+                };
+            }
+
+            sleep(self.retry.backoff(attempts)).await;
+        }
+    }
+
+    // Sends the operation once, and tells whether the failure (if any) is worth retrying
+    async fn attempt(
+        &self,
+        operation: &SyncOperation,
+        url: &str,
+        version: ProtocolVersion,
+        id: &str,
+    ) -> (Delivery, bool) {
+        let request = self.client.request(
+            operation.method(version),
+            format!("{url}{}", operation.path(version)),
+        );
+
+        let request = match (version, operation) {
+            (ProtocolVersion::V1, SyncOperation::Set(req)) => request.json(req),
+            (ProtocolVersion::V1, SyncOperation::SetMulti(req)) => request.json(req),
+            (ProtocolVersion::V1, SyncOperation::Delete(req)) => request.json(req),
+            (ProtocolVersion::V1, SyncOperation::Update(req)) => request.json(req),
+            (ProtocolVersion::V2, operation) => request.json(&Envelope {
+                id: id.into(),
+                version,
+                timestamp: DateTime::now().timestamp_millis(),
+                origin: self.origin.clone(),
+                operation: operation.clone(),
+            }),
+        };
+
+        let start = Instant::now();
+        let result = request.send().await;
+        let latency_ms = start.elapsed().as_millis() as u64;
+
+        match result {
+            Ok(response) => {
+                let status = response.status();
+                let delivery = Delivery {
+                    url: url.into(),
+                    status: Some(status.as_u16()),
+                    latency_ms,
+                    attempts: 1,
+                    queued: false,
+                    error: (!status.is_success()).then(|| format!("Proxy responded with {status}")),
+                };
+                (delivery, self.retry.is_retryable(status))
+            }
+            // Connection errors and timeouts are always worth another try
+            Err(e) => {
+                let delivery = Delivery {
+                    url: url.into(),
+                    status: None,
+                    latency_ms,
+                    attempts: 1,
+                    queued: false,
+                    error: Some(e.to_string()),
+                };
+                (delivery, true)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use reqwest::{Method, StatusCode};
+    use serde_json::json;
+
+    use crate::{
+        config::{OutboxConfig, RetryPolicy, SyncConfig},
+        models::{
+            data::DataType, data_op::DataOp, operation::SyncOperation, protocol::OPS_FEATURE,
+            proxy::Proxy,
+        },
+        request::data::{
+            precondition::Precondition, set::SetDataRequest, update::UpdateDataRequest,
+        },
+        test_support::{add_proxy, fake_proxy::FakeProxy, test_hub_with},
+    };
+
+    fn set(key: &str, value: &str) -> SyncOperation {
+        SyncOperation::Set(SetDataRequest {
+            _type: DataType::String,
+            key: key.into(),
+            value: json!(value),
+            ttl: None,
+            precondition: Precondition::default(),
+            version: None,
+            targets: None,
+        })
+    }
+
+    #[tokio::test]
+    async fn dispatcher_should_recover_writes_interrupted_halfway() {
+        let fake_proxy = FakeProxy::spawn().await;
+
+        // Held entries are due right away, as if their lease was already over
+        let (test_client, services) = test_hub_with(
+            &SyncConfig::from_env(),
+            &OutboxConfig {
+                lease: Duration::ZERO,
+                ..OutboxConfig::from_env()
+            },
+        )
+        .await;
+        let sync_service = services.sync_service;
+
+        add_proxy(&test_client, &fake_proxy.url).await;
+        sync_service.sync(set("test_recover", "a")).await.unwrap();
+
+        // The hub went down right after applying a write
+        let applied = set("test_recover", "b");
+        sync_service.outbox_service.hold(&applied).await.unwrap();
+        sync_service
+            .data_service
+            .apply(&applied, &Precondition::default(), None)
+            .await
+            .unwrap();
+
+        // and right before applying another one
+        let lost = set("test_recover", "c");
+        sync_service.outbox_service.hold(&lost).await.unwrap();
+
+        assert_eq!(sync_service.dispatch_due().await.unwrap(), 2);
+
+        // The proxy ends up with what the hub holds, and nothing is left in the outbox
+        assert_eq!(fake_proxy.value("test_recover"), Some(json!("b")));
+        assert!(sync_service
+            .outbox_service
+            .pending_for(&fake_proxy.url)
+            .await
+            .unwrap()
+            .is_empty());
+        assert_eq!(sync_service.dispatch_due().await.unwrap(), 0);
+    }
+
+    #[tokio::test]
+    async fn expire_due_should_delete_expired_keys_from_proxies() {
+        let fake_proxy = FakeProxy::spawn().await;
+
+        let (test_client, services) =
+            test_hub_with(&SyncConfig::from_env(), &OutboxConfig::from_env()).await;
+        let sync_service = services.sync_service;
+
+        add_proxy(&test_client, &fake_proxy.url).await;
+        sync_service
+            .sync(SyncOperation::Set(SetDataRequest {
+                _type: DataType::String,
+                key: "test_expire".into(),
+                value: json!("a"),
+                ttl: Some(1),
+                precondition: Precondition::default(),
+                version: None,
+                targets: None,
+            }))
+            .await
+            .unwrap();
+        assert_eq!(fake_proxy.value("test_expire"), Some(json!("a")));
+
+        tokio::time::sleep(Duration::from_millis(1100)).await;
+        assert_eq!(sync_service.expire_due().await.unwrap(), 1);
+
+        let calls = fake_proxy.sync_calls();
+        let delete = calls.last().unwrap();
+        assert_eq!(delete.method, Method::DELETE);
+        assert_eq!(delete.path, "/proxy-sync/v1");
+        assert_eq!(delete.body["key"], json!("test_expire"));
+        assert_eq!(fake_proxy.value("test_expire"), None);
+
+        // The key is only expired once
+        assert_eq!(sync_service.expire_due().await.unwrap(), 0);
+        assert_eq!(fake_proxy.sync_calls().len(), calls.len());
+    }
+
+    #[tokio::test]
+    async fn bootstrap_should_not_count_twice_writes_queued_meanwhile() {
+        let fake_proxy = FakeProxy::spawn().await;
+
+        let (_, services) = test_hub_with(&SyncConfig::from_env(), &OutboxConfig::from_env()).await;
+        let sync_service = services.sync_service;
+
+        let incr = || {
+            SyncOperation::Update(UpdateDataRequest {
+                _type: DataType::Counter,
+                key: "test_bootstrap_hits".into(),
+                op: DataOp::Incr { by: 1 },
+                version: None,
+                result: None,
+                targets: None,
+            })
+        };
+        sync_service.sync(incr()).await.unwrap();
+
+        // The proxy is registered, and the counter goes up again before its bootstrap
+        // reads the snapshot. The change is queued for the proxy meanwhile
+        services
+            .proxy_service
+            .add_proxy(Proxy {
+                features: vec![OPS_FEATURE.into()],
+                ..Proxy::new(&fake_proxy.url)
+            })
+            .await
+            .unwrap();
+        let report = sync_service.sync(incr()).await.unwrap();
+        assert_eq!(report.queued, 1);
+
+        sync_service.bootstrap(&fake_proxy.url).await.unwrap();
+
+        // The snapshot already holds the queued change, which the proxy drops
+        let hub = sync_service
+            .data_service
+            .get_data("test_bootstrap_hits")
+            .await
+            .unwrap();
+        assert_eq!(hub.value, json!(2));
+        assert_eq!(fake_proxy.value("test_bootstrap_hits"), Some(json!(2)));
+    }
+
+    #[tokio::test]
+    async fn writes_should_wait_behind_older_writes_left_in_the_outbox() {
+        let fake_proxy = FakeProxy::spawn().await;
+
+        let (test_client, services) = test_hub_with(
+            &SyncConfig {
+                retry: RetryPolicy {
+                    max_attempts: 1,
+                    ..Default::default()
+                },
+                ..SyncConfig::from_env()
+            },
+            // The queued write is due right away too, as if its lease was already over
+            &OutboxConfig {
+                lease: Duration::ZERO,
+                retry_delay: Duration::ZERO,
+                ..OutboxConfig::from_env()
+            },
+        )
+        .await;
+        let sync_service = services.sync_service;
+
+        add_proxy(&test_client, &fake_proxy.url).await;
+        fake_proxy.fail_times(StatusCode::INTERNAL_SERVER_ERROR, 1);
+
+        let report = sync_service.sync(set("test_behind", "a")).await.unwrap();
+        assert_eq!(report.failed, 1);
+
+        // The proxy is back, but the first write is still waiting for it
+        let report = sync_service.sync(set("test_behind", "b")).await.unwrap();
+        assert_eq!(report.queued, 1);
+        assert_eq!(fake_proxy.sync_calls().len(), 1);
+
+        assert_eq!(sync_service.dispatch_due().await.unwrap(), 2);
+
+        let values = fake_proxy
+            .sync_calls()
+            .iter()
+            .map(|call| call.body["value"].clone())
+            .collect::<Vec<_>>();
+        assert_eq!(values, vec![json!("a"), json!("a"), json!("b")]);
+        assert_eq!(fake_proxy.value("test_behind"), Some(json!("b")));
+    }
+}
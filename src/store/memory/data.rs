@@ -0,0 +1,342 @@
+use std::{collections::BTreeMap, sync::Mutex};
+
+use async_trait::async_trait;
+use futures_util::{
+    stream::{self, BoxStream},
+    StreamExt,
+};
+use mongodb::bson::DateTime;
+use serde_json::Value;
+
+use crate::{
+    config::DataConfig,
+    error::Error,
+    models::data::{Data, DataPage, DataType},
+    request::data::{
+        keys::KeysFilter, precondition::Precondition, target::TargetSelector,
+        update::UpdateDataRequest,
+    },
+    store::DataStore,
+};
+
+// A key as it is kept, deleted keys stay behind as tombstones for a while
+struct Entry {
+    data: Data,
+    // Unix timestamp in milliseconds at which the key was deleted
+    deleted_at: Option<i64>,
+}
+
+impl Entry {
+    fn new(key: &str, _type: DataType, targets: Option<&TargetSelector>, now: i64) -> Self {
+        Self {
+            data: Data {
+                key: key.into(),
+                _type,
+                value: Value::Null,
+                ttl: None,
+                expires_at: None,
+                remaining_ttl: None,
+                version: 0,
+                updated_at: now,
+                targets: targets.cloned(),
+            },
+            deleted_at: None,
+        }
+    }
+
+    // Whether the key exists and has not expired yet
+    fn is_live(&self, now: i64) -> bool {
+        self.deleted_at.is_none()
+            && self
+                .data
+                .expires_at
+                .is_none_or(|expires_at| expires_at > now)
+    }
+
+    fn touch(&mut self, now: i64) {
+        self.data.version += 1;
+        self.data.updated_at = now;
+    }
+
+    // Turns the key into a tombstone, keeping its version
+    fn delete(&mut self, now: i64) {
+        self.deleted_at = Some(now);
+        self.data.value = Value::Null;
+        self.data.ttl = None;
+        self.data.expires_at = None;
+        self.touch(now);
+    }
+}
+
+pub struct MemoryDataStore {
+    keys: Mutex<BTreeMap<String, Entry>>,
+    // Milliseconds a tombstone is kept for
+    tombstone_ttl: i64,
+}
+
+impl MemoryDataStore {
+    pub fn new(config: &DataConfig) -> Self {
+        Self {
+            keys: Mutex::new(BTreeMap::new()),
+            tombstone_ttl: config.tombstone_ttl.as_millis() as i64,
+        }
+    }
+}
+
+#[async_trait]
+impl DataStore for MemoryDataStore {
+    async fn set_data(
+        &self,
+        _type: DataType,
+        key: &str,
+        value: &Value,
+        ttl: Option<i64>,
+        precondition: &Precondition,
+        targets: Option<&TargetSelector>,
+    ) -> Result<Option<Data>, Error> {
+        let now = DateTime::now().timestamp_millis();
+        let mut keys = self.keys.lock().map_err(|_| Error::Generic)?;
+
+        // A key that is deleted or expired counts as absent
+        let live = keys.get(key).filter(|entry| entry.is_live(now));
+        let holds = match precondition {
+            Precondition {
+                if_version: Some(version),
+                ..
+            } => live.is_some_and(|entry| entry.data.version == *version),
+            Precondition {
+                if_absent: true, ..
+            } => live.is_none(),
+            _ => true,
+        };
+        if !holds {
+            return Err(Error::VersionConflict);
+        }
+
+        let entry = keys
+            .entry(key.into())
+            .or_insert_with(|| Entry::new(key, _type, targets, now));
+        entry.data._type = _type;
+        entry.data.value = value.clone();
+        entry.data.ttl = ttl;
+        entry.data.expires_at = ttl.map(|ttl| now + ttl * 1000);
+        entry.data.targets = targets.cloned();
+        entry.deleted_at = None;
+        entry.touch(now);
+
+        Ok(Some(entry.data.clone()))
+    }
+
+    async fn delete_data(
+        &self,
+        key: &str,
+        ttl: Option<i64>,
+        precondition: &Precondition,
+    ) -> Result<Option<Data>, Error> {
+        let now = DateTime::now().timestamp_millis();
+        let mut keys = self.keys.lock().map_err(|_| Error::Generic)?;
+
+        let found = keys.get_mut(key).filter(|entry| {
+            entry.is_live(now)
+                && precondition
+                    .if_version
+                    .is_none_or(|version| entry.data.version == version)
+        });
+
+        if precondition.if_absent {
+            return match found {
+                None => Ok(None),
+                Some(_) => Err(Error::VersionConflict),
+            };
+        }
+
+        let Some(entry) = found else {
+            return match precondition.if_version {
+                Some(_) => Err(Error::VersionConflict),
+                // Deleting a key that does not exist does nothing
+                None => Ok(None),
+            };
+        };
+
+        match ttl {
+            // A delete with a ttl only schedules the key to expire
+            Some(ttl) if ttl > 0 => {
+                entry.data.ttl = Some(ttl);
+                entry.data.expires_at = Some(now + ttl * 1000);
+                entry.touch(now);
+            }
+            _ => entry.delete(now),
+        }
+
+        Ok(Some(entry.data.clone()))
+    }
+
+    async fn update_data(
+        &self,
+        UpdateDataRequest { _type, key, op, .. }: &UpdateDataRequest,
+        targets: Option<&TargetSelector>,
+    ) -> Result<Option<Data>, Error> {
+        let now = DateTime::now().timestamp_millis();
+        let mut keys = self.keys.lock().map_err(|_| Error::Generic)?;
+
+        // A deleted or expired key starts over from an empty value of the new type
+        let current = keys.get(key).filter(|entry| entry.is_live(now));
+        if current.is_some_and(|entry| entry.data._type != *_type) {
+            return Err(Error::WrongDataType);
+        }
+        let revived = current.is_none();
+        let Some(value) = op
+            .apply(current.map(|entry| &entry.data.value))
+            .map_err(|_| Error::WrongDataType)?
+        else {
+            return Ok(None);
+        };
+
+        let entry = keys
+            .entry(key.clone())
+            .or_insert_with(|| Entry::new(key, *_type, targets, now));
+        if revived {
+            entry.data._type = *_type;
+            entry.data.ttl = None;
+            entry.data.expires_at = None;
+            entry.deleted_at = None;
+        }
+        entry.data.value = value;
+        entry.touch(now);
+
+        Ok(Some(entry.data.clone()))
+    }
+
+    async fn get_data(&self, key: &str) -> Result<Option<Data>, Error> {
+        let now = DateTime::now().timestamp_millis();
+        let keys = self.keys.lock().map_err(|_| Error::Generic)?;
+
+        Ok(keys
+            .get(key)
+            .filter(|entry| entry.is_live(now))
+            .map(|entry| entry.data.clone()))
+    }
+
+    async fn get_keys(&self, filter: &KeysFilter) -> Result<DataPage, Error> {
+        let now = DateTime::now().timestamp_millis();
+        let keys = self.keys.lock().map_err(|_| Error::Generic)?;
+
+        let matching = keys
+            .values()
+            .filter(|entry| entry.is_live(now) && filter.matches(&entry.data.key))
+            .collect::<Vec<_>>();
+
+        Ok(DataPage {
+            items: matching
+                .iter()
+                .skip(filter.skip() as usize)
+                .take(filter.limit as usize)
+                .map(|entry| entry.data.clone())
+                .collect(),
+            page: filter.page,
+            limit: filter.limit,
+            total: matching.len() as u64,
+        })
+    }
+
+    async fn snapshot(&self) -> Result<BoxStream<'static, Result<Data, Error>>, Error> {
+        let now = DateTime::now().timestamp_millis();
+        let keys = self.keys.lock().map_err(|_| Error::Generic)?;
+
+        let data = keys
+            .values()
+            .filter(|entry| entry.is_live(now))
+            .map(|entry| Ok(entry.data.clone()))
+            .collect::<Vec<_>>();
+        Ok(stream::iter(data).boxed())
+    }
+
+    async fn next_expired(&self) -> Result<Option<String>, Error> {
+        let now = DateTime::now().timestamp_millis();
+        let mut keys = self.keys.lock().map_err(|_| Error::Generic)?;
+
+        // Forgets the tombstones that are over their own ttl along the way
+        keys.retain(|_, entry| {
+            entry
+                .deleted_at
+                .is_none_or(|deleted_at| deleted_at + self.tombstone_ttl > now)
+        });
+
+        Ok(keys
+            .values()
+            .filter(|entry| entry.deleted_at.is_none() && !entry.is_live(now))
+            .min_by_key(|entry| entry.data.expires_at)
+            .map(|entry| entry.data.key.clone()))
+    }
+
+    async fn take_expired(&self, key: &str) -> Result<Option<Data>, Error> {
+        let now = DateTime::now().timestamp_millis();
+        let mut keys = self.keys.lock().map_err(|_| Error::Generic)?;
+
+        Ok(keys
+            .get_mut(key)
+            .filter(|entry| entry.deleted_at.is_none() && !entry.is_live(now))
+            .map(|entry| {
+                entry.delete(now);
+                entry.data.clone()
+            }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use serde_json::json;
+
+    use crate::{
+        config::DataConfig,
+        error::Error,
+        models::{data::DataType, data_op::DataOp},
+        request::data::{precondition::Precondition, update::UpdateDataRequest},
+        store::DataStore,
+    };
+
+    use super::MemoryDataStore;
+
+    #[tokio::test]
+    async fn memory_store_should_keep_versions_across_deletes() {
+        let store = MemoryDataStore::new(&DataConfig {
+            tombstone_ttl: Duration::from_secs(60),
+        });
+        let incr = UpdateDataRequest {
+            _type: DataType::Counter,
+            key: "hits".into(),
+            op: DataOp::Incr { by: 2 },
+            version: None,
+            result: None,
+            targets: None,
+        };
+
+        let data = store.update_data(&incr, None).await.unwrap().unwrap();
+        assert_eq!((data.value, data.version), (json!(2), 1));
+
+        let data = store
+            .delete_data("hits", None, &Precondition::default())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(data.version, 2);
+        assert!(store.get_data("hits").await.unwrap().is_none());
+
+        // A deleted counter starts over, from the version it was at
+        let data = store.update_data(&incr, None).await.unwrap().unwrap();
+        assert_eq!((data.value, data.version), (json!(2), 3));
+
+        let absent = Precondition {
+            if_absent: true,
+            ..Default::default()
+        };
+        assert!(matches!(
+            store
+                .set_data(DataType::Counter, "hits", &json!(1), None, &absent, None)
+                .await,
+            Err(Error::VersionConflict)
+        ));
+    }
+}
@@ -0,0 +1,89 @@
+use std::sync::Mutex;
+
+use async_trait::async_trait;
+
+use crate::{
+    error::Error,
+    models::{dead_letter::DeadLetter, delivery::Delivery},
+    request::dead_letter::filter::DeadLetterFilter,
+    store::DeadLetterStore,
+};
+
+#[derive(Default)]
+pub struct MemoryDeadLetterStore {
+    dead_letters: Mutex<Vec<DeadLetter>>,
+}
+
+impl MemoryDeadLetterStore {
+    // Removes the dead letters the predicate picks, giving how many there were
+    fn remove(&self, pick: impl Fn(&DeadLetter) -> bool) -> Result<u64, Error> {
+        let mut dead_letters = self.dead_letters.lock().map_err(|_| Error::Generic)?;
+        let before = dead_letters.len();
+        dead_letters.retain(|dead_letter| !pick(dead_letter));
+        Ok((before - dead_letters.len()) as u64)
+    }
+}
+
+#[async_trait]
+impl DeadLetterStore for MemoryDeadLetterStore {
+    async fn get_dead_letters(&self, filter: &DeadLetterFilter) -> Result<Vec<DeadLetter>, Error> {
+        let dead_letters = self.dead_letters.lock().map_err(|_| Error::Generic)?;
+
+        let mut matching = dead_letters
+            .iter()
+            .filter(|dead_letter| filter.matches(dead_letter))
+            .cloned()
+            .collect::<Vec<_>>();
+        matching.sort_by(|a, b| (a.failed_at, &a.id).cmp(&(b.failed_at, &b.id)));
+        Ok(matching)
+    }
+
+    async fn get_dead_letter(&self, id: &str) -> Result<Option<DeadLetter>, Error> {
+        let dead_letters = self.dead_letters.lock().map_err(|_| Error::Generic)?;
+        Ok(dead_letters
+            .iter()
+            .find(|dead_letter| dead_letter.id == id)
+            .cloned())
+    }
+
+    async fn add_dead_letter(&self, dead_letter: DeadLetter) -> Result<(), Error> {
+        let mut dead_letters = self.dead_letters.lock().map_err(|_| Error::Generic)?;
+        dead_letters.push(dead_letter);
+        Ok(())
+    }
+
+    async fn record_failure(&self, id: &str, delivery: &Delivery) -> Result<(), Error> {
+        let mut dead_letters = self.dead_letters.lock().map_err(|_| Error::Generic)?;
+        if let Some(dead_letter) = dead_letters
+            .iter_mut()
+            .find(|dead_letter| dead_letter.id == id)
+        {
+            dead_letter.attempts += delivery.attempts;
+            dead_letter.error = delivery.error.clone();
+        }
+        Ok(())
+    }
+
+    async fn delete_dead_letter(&self, id: &str) -> Result<bool, Error> {
+        Ok(self.remove(|dead_letter| dead_letter.id == id)? > 0)
+    }
+
+    async fn delete_dead_letters(&self, filter: &DeadLetterFilter) -> Result<u64, Error> {
+        self.remove(|dead_letter| filter.matches(dead_letter))
+    }
+
+    async fn move_dead_letters(&self, from: &str, to: &str) -> Result<(), Error> {
+        let mut dead_letters = self.dead_letters.lock().map_err(|_| Error::Generic)?;
+        for dead_letter in dead_letters
+            .iter_mut()
+            .filter(|dead_letter| dead_letter.url == from)
+        {
+            dead_letter.url = to.into();
+        }
+        Ok(())
+    }
+
+    async fn delete_dead_letters_of(&self, url: &str) -> Result<u64, Error> {
+        self.remove(|dead_letter| dead_letter.url == url)
+    }
+}
@@ -0,0 +1,41 @@
+use std::sync::Mutex;
+
+use async_trait::async_trait;
+use mongodb::bson::DateTime;
+
+use crate::{config::HealthConfig, error::Error, models::health::HealthRecord, store::HealthStore};
+
+pub struct MemoryHealthStore {
+    records: Mutex<Vec<HealthRecord>>,
+    // Milliseconds a health check is kept for
+    history_ttl: i64,
+}
+
+impl MemoryHealthStore {
+    pub fn new(config: &HealthConfig) -> Self {
+        Self {
+            records: Mutex::new(vec![]),
+            history_ttl: config.history_ttl.as_millis() as i64,
+        }
+    }
+}
+
+#[async_trait]
+impl HealthStore for MemoryHealthStore {
+    async fn add_records(&self, records: Vec<HealthRecord>) -> Result<(), Error> {
+        let now = DateTime::now().timestamp_millis();
+        let mut kept = self.records.lock().map_err(|_| Error::Generic)?;
+
+        // Old health checks are dropped whenever new ones come in
+        kept.retain(|record| record.created_at.timestamp_millis() + self.history_ttl > now);
+        kept.extend(records);
+        Ok(())
+    }
+
+    async fn delete_history(&self, url: &str) -> Result<u64, Error> {
+        let mut records = self.records.lock().map_err(|_| Error::Generic)?;
+        let before = records.len();
+        records.retain(|record| record.check.url != url);
+        Ok((before - records.len()) as u64)
+    }
+}
@@ -0,0 +1,5 @@
+pub mod data;
+pub mod dead_letter;
+pub mod health;
+pub mod outbox;
+pub mod proxy;
@@ -0,0 +1,158 @@
+use std::{collections::BTreeMap, sync::Mutex};
+
+use async_trait::async_trait;
+use mongodb::bson::{oid::ObjectId, DateTime};
+
+use crate::{
+    error::Error,
+    models::{
+        delivery::Delivery,
+        operation::SyncOperation,
+        outbox::{OutboxDelivery, OutboxEntry},
+    },
+    store::OutboxStore,
+};
+
+// Entries are kept in the order of their ids, which is the order they were written in
+#[derive(Default)]
+pub struct MemoryOutboxStore {
+    entries: Mutex<BTreeMap<ObjectId, OutboxEntry>>,
+}
+
+// No delivery left unacknowledged
+fn is_done(entry: &OutboxEntry) -> bool {
+    entry.deliveries.iter().all(|delivery| delivery.acked)
+}
+
+#[async_trait]
+impl OutboxStore for MemoryOutboxStore {
+    async fn insert(&self, mut entry: OutboxEntry) -> Result<ObjectId, Error> {
+        let id = ObjectId::new();
+        entry.id = Some(id);
+
+        let mut entries = self.entries.lock().map_err(|_| Error::Generic)?;
+        entries.insert(id, entry);
+        Ok(id)
+    }
+
+    async fn release(
+        &self,
+        id: ObjectId,
+        operation: &SyncOperation,
+        deliveries: &[OutboxDelivery],
+    ) -> Result<(), Error> {
+        let mut entries = self.entries.lock().map_err(|_| Error::Generic)?;
+
+        if let Some(entry) = entries.get_mut(&id) {
+            entry.operation = operation.clone();
+            entry.deliveries = deliveries.to_vec();
+            entry.held = false;
+        }
+        Ok(())
+    }
+
+    async fn claim_due(&self, lease_until: DateTime) -> Result<Option<OutboxEntry>, Error> {
+        let now = DateTime::now();
+        let mut entries = self.entries.lock().map_err(|_| Error::Generic)?;
+
+        Ok(entries
+            .values_mut()
+            .find(|entry| entry.next_attempt_at <= now)
+            .map(|entry| {
+                entry.next_attempt_at = lease_until;
+                entry.clone()
+            }))
+    }
+
+    async fn pending_for(&self, url: &str) -> Result<Vec<OutboxEntry>, Error> {
+        let entries = self.entries.lock().map_err(|_| Error::Generic)?;
+
+        Ok(entries
+            .values()
+            .filter(|entry| {
+                entry
+                    .deliveries
+                    .iter()
+                    .any(|delivery| delivery.url == url && !delivery.acked)
+            })
+            .cloned()
+            .collect())
+    }
+
+    async fn record(
+        &self,
+        id: ObjectId,
+        deliveries: &[Delivery],
+        retry_at: DateTime,
+    ) -> Result<Option<OutboxEntry>, Error> {
+        let mut entries = self.entries.lock().map_err(|_| Error::Generic)?;
+        let Some(entry) = entries.get_mut(&id) else {
+            return Ok(None);
+        };
+
+        for Delivery {
+            url,
+            attempts,
+            error,
+            ..
+        } in deliveries
+        {
+            for delivery in entry.deliveries.iter_mut().filter(|d| d.url == *url) {
+                delivery.acked = error.is_none();
+                delivery.last_error = error.clone();
+                delivery.attempts += attempts;
+            }
+        }
+        entry.next_attempt_at = retry_at;
+
+        Ok(Some(entry.clone()))
+    }
+
+    async fn discard(&self, id: ObjectId, urls: &[String]) -> Result<(), Error> {
+        let mut entries = self.entries.lock().map_err(|_| Error::Generic)?;
+
+        if let Some(entry) = entries.get_mut(&id) {
+            entry
+                .deliveries
+                .retain(|delivery| !urls.contains(&delivery.url));
+            if is_done(entry) {
+                entries.remove(&id);
+            }
+        }
+        Ok(())
+    }
+
+    async fn move_deliveries(&self, from: &str, to: &str) -> Result<(), Error> {
+        let mut entries = self.entries.lock().map_err(|_| Error::Generic)?;
+
+        for delivery in entries
+            .values_mut()
+            .flat_map(|entry| entry.deliveries.iter_mut())
+            .filter(|delivery| delivery.url == from)
+        {
+            delivery.url = to.into();
+        }
+        Ok(())
+    }
+
+    async fn remove_deliveries(&self, url: &str) -> Result<u64, Error> {
+        let mut entries = self.entries.lock().map_err(|_| Error::Generic)?;
+
+        let mut removed = 0;
+        for entry in entries.values_mut() {
+            if entry
+                .deliveries
+                .iter()
+                .any(|delivery| delivery.url == url && !delivery.acked)
+            {
+                entry.deliveries.retain(|delivery| delivery.url != url);
+                removed += 1;
+            }
+        }
+
+        // Entries that no other proxy is waiting for
+        entries.retain(|_, entry| !is_done(entry));
+
+        Ok(removed)
+    }
+}
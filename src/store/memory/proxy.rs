@@ -0,0 +1,77 @@
+use std::sync::Mutex;
+
+use async_trait::async_trait;
+
+use crate::{error::Error, models::proxy::Proxy, store::table::ProxyTable};
+
+// Keeps the registry in the process, for tests and throwaway setups
+
+#[derive(Default)]
+pub struct MemoryProxyStore {
+    proxies: Mutex<Vec<Proxy>>,
+}
+
+#[async_trait]
+impl ProxyTable for MemoryProxyStore {
+    async fn read_proxies<R: Send + 'static>(
+        &self,
+        f: impl for<'a> FnOnce(&'a [Proxy]) -> R + Send + 'static,
+    ) -> Result<R, Error> {
+        let proxies = self.proxies.lock().map_err(|_| Error::Generic)?;
+        Ok(f(&proxies))
+    }
+
+    async fn with_proxies<R: Send + 'static>(
+        &self,
+        f: impl for<'a> FnOnce(&'a mut Vec<Proxy>) -> Result<R, Error> + Send + 'static,
+    ) -> Result<R, Error> {
+        let mut proxies = self.proxies.lock().map_err(|_| Error::Generic)?;
+        f(&mut proxies)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        error::Error,
+        models::proxy::{BreakerState, Proxy},
+        store::ProxyStore,
+    };
+
+    use super::MemoryProxyStore;
+
+    #[tokio::test]
+    async fn memory_store_should_keep_urls_unique_and_trip_breakers() {
+        let store = MemoryProxyStore::default();
+
+        store
+            .add_proxy(Proxy::new("http://proxy1:3000"))
+            .await
+            .unwrap();
+        assert!(matches!(
+            store.add_proxy(Proxy::new("http://proxy1:3000")).await,
+            Err(Error::ProxyAlreadyExists)
+        ));
+
+        for _ in 0..3 {
+            store
+                .record_delivery("http://proxy1:3000", false, 3)
+                .await
+                .unwrap();
+        }
+        let proxy = store
+            .get_proxy("http://proxy1:3000")
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(proxy.breaker.state, BreakerState::Open);
+
+        // Only the first claim may start the bootstrap
+        assert!(store.claim_bootstrap(&proxy.url).await.unwrap());
+        assert!(!store.claim_bootstrap(&proxy.url).await.unwrap());
+
+        let proxy = store.delete_proxy(&proxy.url).await.unwrap();
+        assert!(proxy.is_some());
+        assert!(store.get_proxies().await.unwrap().is_empty());
+    }
+}
@@ -0,0 +1,280 @@
+pub mod memory;
+pub mod mongo;
+pub mod sqlite;
+pub mod table;
+
+use std::{sync::Arc, time::Duration};
+
+use async_trait::async_trait;
+use futures_util::stream::BoxStream;
+use mongodb::{
+    bson::{oid::ObjectId, DateTime},
+    Database,
+};
+use serde_json::Value;
+
+use crate::{
+    config::{DataConfig, HealthConfig, StoreBackend, StoreConfig},
+    error::Error,
+    models::{
+        data::{Data, DataPage, DataType},
+        dead_letter::DeadLetter,
+        delivery::Delivery,
+        health::{HealthCheck, HealthRecord},
+        operation::SyncOperation,
+        outbox::{OutboxDelivery, OutboxEntry},
+        proxy::{Bootstrap, Proxy, ProxyMode},
+    },
+    request::{
+        data::{
+            keys::KeysFilter, precondition::Precondition, target::TargetSelector,
+            update::UpdateDataRequest,
+        },
+        dead_letter::filter::DeadLetterFilter,
+        proxy::update::UpdateProxyRequest,
+    },
+};
+
+use self::{
+    memory::{
+        data::MemoryDataStore, dead_letter::MemoryDeadLetterStore, health::MemoryHealthStore,
+        outbox::MemoryOutboxStore, proxy::MemoryProxyStore,
+    },
+    mongo::{
+        data::MongoDataStore, dead_letter::MongoDeadLetterStore, health::MongoHealthStore,
+        outbox::MongoOutboxStore, proxy::MongoProxyStore,
+    },
+    sqlite::SqliteProxyStore,
+};
+
+// Where the proxy registry is kept.
+// Lookups of unknown proxies give None, the caller decides whether that is an error
+
+#[async_trait]
+pub trait ProxyStore: Send + Sync {
+    async fn get_proxies(&self) -> Result<Vec<Proxy>, Error>;
+
+    async fn get_proxy(&self, url: &str) -> Result<Option<Proxy>, Error>;
+
+    async fn get_proxy_by_id(&self, id: &str) -> Result<Option<Proxy>, Error>;
+
+    // Fails with `Error::ProxyAlreadyExists` when the url is taken
+    async fn add_proxy(&self, proxy: Proxy) -> Result<Proxy, Error>;
+
+    // Changes the given fields of a proxy in a single write
+    async fn update_proxy(
+        &self,
+        id: &str,
+        update: &UpdateProxyRequest,
+    ) -> Result<Option<Proxy>, Error>;
+
+    async fn set_mode(&self, id: &str, mode: ProxyMode) -> Result<Option<Proxy>, Error>;
+
+    // Changes the mode of a proxy only if it is still in the expected one
+    async fn transition(&self, url: &str, from: ProxyMode, to: ProxyMode) -> Result<bool, Error>;
+
+    async fn delete_proxy(&self, url: &str) -> Result<Option<Proxy>, Error>;
+
+    async fn delete_proxy_by_id(&self, id: &str) -> Result<Option<Proxy>, Error>;
+
+    async fn update_bootstrap(&self, url: &str, bootstrap: &Bootstrap) -> Result<(), Error>;
+
+    // Marks the bootstrap of a proxy as running unless it already is.
+    // Only one caller gets to do so, and that caller starts the bootstrap
+    async fn claim_bootstrap(&self, url: &str) -> Result<bool, Error>;
+
+    // Stores the latest health check on the proxy
+    async fn record_health(&self, check: &HealthCheck) -> Result<(), Error>;
+
+    // Moves an open breaker to half-open once its cool down is over.
+    // Only one caller gets to do so, and that caller may send the probing request.
+    // A half-open breaker whose probe never finished is handed out again after another cool down
+    async fn half_open(&self, url: &str, cool_down: Duration) -> Result<bool, Error>;
+
+    // Feeds the outcome of a delivery to the breaker of a proxy
+    async fn record_delivery(
+        &self,
+        url: &str,
+        success: bool,
+        failure_threshold: u32,
+    ) -> Result<(), Error>;
+
+    async fn reset_breaker(&self, url: &str) -> Result<Option<Proxy>, Error>;
+}
+
+// Where the synced data is kept. Deleted keys stay behind as tombstones for a while,
+// and together with expired keys they count as absent everywhere
+
+#[async_trait]
+pub trait DataStore: Send + Sync {
+    // Writes the whole value of a key. Fails with `Error::VersionConflict`
+    // when the precondition does not hold
+    async fn set_data(
+        &self,
+        _type: DataType,
+        key: &str,
+        value: &Value,
+        ttl: Option<i64>,
+        precondition: &Precondition,
+        targets: Option<&TargetSelector>,
+    ) -> Result<Option<Data>, Error>;
+
+    // Deletes a key, or only schedules it to expire when given a ttl.
+    // Gives None when there was nothing to delete
+    async fn delete_data(
+        &self,
+        key: &str,
+        ttl: Option<i64>,
+        precondition: &Precondition,
+    ) -> Result<Option<Data>, Error>;
+
+    // Changes the value of a key in place, so that concurrent changes are all kept.
+    // Fails with `Error::WrongDataType` when the key holds another type
+    async fn update_data(
+        &self,
+        request: &UpdateDataRequest,
+        targets: Option<&TargetSelector>,
+    ) -> Result<Option<Data>, Error>;
+
+    async fn get_data(&self, key: &str) -> Result<Option<Data>, Error>;
+
+    async fn get_keys(&self, filter: &KeysFilter) -> Result<DataPage, Error>;
+
+    // Every live key, in key order
+    async fn snapshot(&self) -> Result<BoxStream<'static, Result<Data, Error>>, Error>;
+
+    // The key that expired the longest ago, if any key is over its ttl
+    async fn next_expired(&self) -> Result<Option<String>, Error>;
+
+    // Turns the key into a tombstone if it is still over its ttl, which makes sure
+    // only one worker sends its delete to the proxies. A key written again meanwhile is left
+    async fn take_expired(&self, key: &str) -> Result<Option<Data>, Error>;
+}
+
+// Where the sync operations wait until every proxy has acknowledged them
+
+#[async_trait]
+pub trait OutboxStore: Send + Sync {
+    async fn insert(&self, entry: OutboxEntry) -> Result<ObjectId, Error>;
+
+    // Sets the operation and deliveries of a held entry, and lets it be delivered
+    async fn release(
+        &self,
+        id: ObjectId,
+        operation: &SyncOperation,
+        deliveries: &[OutboxDelivery],
+    ) -> Result<(), Error>;
+
+    // Leases the oldest entry that is due until the given time
+    async fn claim_due(&self, lease_until: DateTime) -> Result<Option<OutboxEntry>, Error>;
+
+    // Every entry still waiting to be delivered to the given proxy, oldest first
+    async fn pending_for(&self, url: &str) -> Result<Vec<OutboxEntry>, Error>;
+
+    // Stores the outcome of a delivery round and releases the entry for a retry
+    // at the given time. Gives the entry as it is now, None if it is gone
+    async fn record(
+        &self,
+        id: ObjectId,
+        deliveries: &[Delivery],
+        retry_at: DateTime,
+    ) -> Result<Option<OutboxEntry>, Error>;
+
+    // Drops the deliveries of an entry to the given proxies,
+    // and the entry itself once no delivery is left unacknowledged
+    async fn discard(&self, id: ObjectId, urls: &[String]) -> Result<(), Error>;
+
+    async fn move_deliveries(&self, from: &str, to: &str) -> Result<(), Error>;
+
+    // Gives the number of entries the proxy was waiting for
+    async fn remove_deliveries(&self, url: &str) -> Result<u64, Error>;
+}
+
+// Where the deliveries that ran out of retries are kept
+
+#[async_trait]
+pub trait DeadLetterStore: Send + Sync {
+    // Oldest failures first, so that replaying keeps the original order
+    async fn get_dead_letters(&self, filter: &DeadLetterFilter) -> Result<Vec<DeadLetter>, Error>;
+
+    async fn get_dead_letter(&self, id: &str) -> Result<Option<DeadLetter>, Error>;
+
+    async fn add_dead_letter(&self, dead_letter: DeadLetter) -> Result<(), Error>;
+
+    // Adds the attempts of a failed replay
+    async fn record_failure(&self, id: &str, delivery: &Delivery) -> Result<(), Error>;
+
+    // Gives whether there was a dead letter to delete
+    async fn delete_dead_letter(&self, id: &str) -> Result<bool, Error>;
+
+    async fn delete_dead_letters(&self, filter: &DeadLetterFilter) -> Result<u64, Error>;
+
+    async fn move_dead_letters(&self, from: &str, to: &str) -> Result<(), Error>;
+
+    async fn delete_dead_letters_of(&self, url: &str) -> Result<u64, Error>;
+}
+
+// Where the past health checks are kept, each one for the history ttl
+
+#[async_trait]
+pub trait HealthStore: Send + Sync {
+    async fn add_records(&self, records: Vec<HealthRecord>) -> Result<(), Error>;
+
+    async fn delete_history(&self, url: &str) -> Result<u64, Error>;
+}
+
+// Every store of the hub
+#[derive(Clone)]
+pub struct Stores {
+    pub proxies: Arc<dyn ProxyStore>,
+    pub data: Arc<dyn DataStore>,
+    pub outbox: Arc<dyn OutboxStore>,
+    pub dead_letters: Arc<dyn DeadLetterStore>,
+    pub health: Arc<dyn HealthStore>,
+}
+
+impl Stores {
+    // The proxy registry in the backend chosen in the config, everything else in mongodb
+    pub async fn init(config: &StoreConfig, database: &Database) -> Self {
+        Self {
+            proxies: init(config, database).await,
+            data: Arc::new(
+                MongoDataStore::init(&database.collection("Data"), &DataConfig::from_env()).await,
+            ),
+            outbox: Arc::new(MongoOutboxStore::init(&database.collection("Outbox"))),
+            dead_letters: Arc::new(MongoDeadLetterStore::init(
+                &database.collection("DeadLetter"),
+            )),
+            health: Arc::new(
+                MongoHealthStore::init(
+                    &database.collection("HealthHistory"),
+                    &HealthConfig::from_env(),
+                )
+                .await,
+            ),
+        }
+    }
+
+    // Everything in the process, so that nothing outlives it
+    pub fn memory() -> Self {
+        Self {
+            proxies: Arc::new(MemoryProxyStore::default()),
+            data: Arc::new(MemoryDataStore::new(&DataConfig::from_env())),
+            outbox: Arc::new(MemoryOutboxStore::default()),
+            dead_letters: Arc::new(MemoryDeadLetterStore::default()),
+            health: Arc::new(MemoryHealthStore::new(&HealthConfig::from_env())),
+        }
+    }
+}
+
+// Opens the registry backend chosen in the config
+pub async fn init(config: &StoreConfig, database: &Database) -> Arc<dyn ProxyStore> {
+    match &config.backend {
+        StoreBackend::Mongo => Arc::new(MongoProxyStore::init(&database.collection("Proxy")).await),
+        StoreBackend::Memory => Arc::new(MemoryProxyStore::default()),
+        StoreBackend::Sqlite => Arc::new(
+            SqliteProxyStore::open(&config.sqlite_path)
+                .expect("Cannot open the SQLite proxy registry"),
+        ),
+    }
+}
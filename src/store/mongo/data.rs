@@ -0,0 +1,403 @@
+use async_trait::async_trait;
+use futures_util::{stream::BoxStream, StreamExt, TryStreamExt};
+use mongodb::{
+    bson::{doc, to_bson, Bson, DateTime, Document},
+    options::{FindOneAndUpdateOptions, FindOneOptions, FindOptions, IndexOptions, ReturnDocument},
+    Collection, IndexModel,
+};
+use serde_json::Value;
+
+use crate::{
+    config::DataConfig,
+    error::Error,
+    helper::mongo::is_duplicate_key,
+    models::{
+        data::{Data, DataPage, DataType},
+        data_op::DataOp,
+    },
+    request::data::{
+        keys::KeysFilter, precondition::Precondition, target::TargetSelector,
+        update::UpdateDataRequest,
+    },
+    store::DataStore,
+};
+
+// Restricts a filter to keys that exist and have not expired yet
+fn live(mut filter: Document) -> Document {
+    filter.insert("deleted", doc! {"$ne": true});
+    filter.insert(
+        "$or",
+        vec![
+            doc! {"expires_at": null},
+            doc! {"expires_at": {"$gt": DateTime::now().timestamp_millis()}},
+        ],
+    );
+    filter
+}
+
+// Turns a key into a tombstone. Its version is kept for a while after it is deleted,
+// so that a key written again goes on from there instead of starting over
+fn tombstone(now: i64) -> Document {
+    doc! {
+        "$set": {
+            "deleted": true,
+            "deleted_at": DateTime::from_millis(now),
+            "value": null,
+            "ttl": null,
+            "expires_at": null,
+            "updated_at": now,
+        },
+        "$inc": {"version": 1},
+    }
+}
+
+fn after() -> FindOneAndUpdateOptions {
+    FindOneAndUpdateOptions::builder()
+        .return_document(ReturnDocument::After)
+        .build()
+}
+
+pub struct MongoDataStore {
+    collection: Collection<Data>,
+}
+
+impl MongoDataStore {
+    pub async fn init(collection: &Collection<Data>, config: &DataConfig) -> Self {
+        // Every key is stored once
+        let index = IndexModel::builder()
+            .keys(doc! {"key": 1})
+            .options(IndexOptions::builder().unique(true).build())
+            .build();
+
+        collection
+            .create_index(index, None)
+            .await
+            .expect("Cannot create index on the Data collection");
+
+        // Lets the expiry worker find the keys that are over their ttl
+        collection
+            .create_index(
+                IndexModel::builder().keys(doc! {"expires_at": 1}).build(),
+                None,
+            )
+            .await
+            .expect("Cannot create index on the Data collection");
+
+        // Tombstones are removed by mongodb itself
+        let index = IndexModel::builder()
+            .keys(doc! {"deleted_at": 1})
+            .options(
+                IndexOptions::builder()
+                    .expire_after(config.tombstone_ttl)
+                    .build(),
+            )
+            .build();
+
+        collection
+            .create_index(index, None)
+            .await
+            .expect("Cannot create index on the Data collection");
+
+        Self {
+            collection: collection.clone(),
+        }
+    }
+}
+
+#[async_trait]
+impl DataStore for MongoDataStore {
+    async fn set_data(
+        &self,
+        _type: DataType,
+        key: &str,
+        value: &Value,
+        ttl: Option<i64>,
+        precondition: &Precondition,
+        targets: Option<&TargetSelector>,
+    ) -> Result<Option<Data>, Error> {
+        let value = to_bson(value).map_err(|_| Error::Generic)?;
+        let targets = to_bson(&targets).map_err(|_| Error::Generic)?;
+        let now = DateTime::now().timestamp_millis();
+
+        let update = doc! {
+            "$set": {
+                "type": _type.as_str(),
+                "value": value,
+                "ttl": ttl,
+                "expires_at": ttl.map(|ttl| now + ttl * 1000),
+                "updated_at": now,
+                "targets": targets,
+                "deleted": false,
+            },
+            "$unset": {"deleted_at": ""},
+            "$inc": {"version": 1},
+        };
+
+        // A key that is deleted or expired counts as absent
+        let (filter, upsert) = match precondition {
+            Precondition {
+                if_version: Some(version),
+                ..
+            } => (live(doc! {"key": key, "version": version}), false),
+            Precondition {
+                if_absent: true, ..
+            } => (
+                doc! {"key": key, "$or": [
+                    {"deleted": true},
+                    {"expires_at": {"$lte": now}},
+                ]},
+                true,
+            ),
+            _ => (doc! {"key": key}, true),
+        };
+
+        // When the key is there but does not match, the upsert hits the unique index on key
+        let mut options = after();
+        options.upsert = Some(upsert);
+
+        let data = self
+            .collection
+            .find_one_and_update(filter, update, options)
+            .await
+            .map_err(|e| {
+                if is_duplicate_key(&e) {
+                    Error::VersionConflict
+                } else {
+                    e.into()
+                }
+            })?;
+
+        match data {
+            Some(data) => Ok(Some(data)),
+            None => Err(Error::VersionConflict),
+        }
+    }
+
+    async fn delete_data(
+        &self,
+        key: &str,
+        ttl: Option<i64>,
+        precondition: &Precondition,
+    ) -> Result<Option<Data>, Error> {
+        let now = DateTime::now().timestamp_millis();
+
+        let mut filter = live(doc! {"key": key});
+        if let Some(version) = precondition.if_version {
+            filter.insert("version", version);
+        }
+
+        if precondition.if_absent {
+            return match self.collection.count_documents(filter, None).await? {
+                0 => Ok(None),
+                _ => Err(Error::VersionConflict),
+            };
+        }
+
+        let update = match ttl {
+            // A delete with a ttl only schedules the key to expire
+            Some(ttl) if ttl > 0 => doc! {
+                "$set": {
+                    "ttl": ttl,
+                    "expires_at": now + ttl * 1000,
+                    "updated_at": now,
+                },
+                "$inc": {"version": 1},
+            },
+            _ => tombstone(now),
+        };
+
+        let data = self
+            .collection
+            .find_one_and_update(filter, update, after())
+            .await?;
+
+        match (data, precondition.if_version) {
+            (None, Some(_)) => Err(Error::VersionConflict),
+            // Deleting a key that does not exist does nothing
+            (data, _) => Ok(data),
+        }
+    }
+
+    // Changes a value in place with the matching mongodb operator, so that
+    // concurrent changes to the same key are all kept
+    async fn update_data(
+        &self,
+        UpdateDataRequest { _type, key, op, .. }: &UpdateDataRequest,
+        targets: Option<&TargetSelector>,
+    ) -> Result<Option<Data>, Error> {
+        let bson = |value| to_bson(value).map_err(|_| Error::Generic);
+
+        // Only the changes that add something create the key when it is missing
+        let (mut update, creates) = match op {
+            DataOp::Incr { by } => (doc! {"$inc": {"value": by}}, true),
+            DataOp::Decr { by } => (doc! {"$inc": {"value": -by}}, true),
+            DataOp::Append { value } => (doc! {"$push": {"value": bson(value)?}}, true),
+            DataOp::Prepend { value } => (
+                doc! {"$push": {"value": {"$each": [bson(value)?], "$position": 0}}},
+                true,
+            ),
+            DataOp::SetAdd { member } => (doc! {"$addToSet": {"value": bson(member)?}}, true),
+            DataOp::SetRemove { member } => (doc! {"$pull": {"value": bson(member)?}}, false),
+            DataOp::HashSet { field, value } => (
+                doc! {"$set": {format!("value.{field}"): bson(value)?}},
+                true,
+            ),
+            DataOp::HashDelete { field } => {
+                (doc! {"$unset": {format!("value.{field}"): ""}}, false)
+            }
+        };
+
+        let now = DateTime::now().timestamp_millis();
+        match update.get_document_mut("$set") {
+            Ok(set) => {
+                set.insert("updated_at", now);
+            }
+            Err(_) => {
+                update.insert("$set", doc! {"updated_at": now});
+            }
+        }
+        match update.get_document_mut("$inc") {
+            Ok(inc) => {
+                inc.insert("version", 1);
+            }
+            Err(_) => {
+                update.insert("$inc", doc! {"version": 1});
+            }
+        }
+        let targets = to_bson(&targets).map_err(|_| Error::Generic)?;
+        update.insert("$setOnInsert", doc! {"targets": targets});
+
+        // A deleted or expired key starts over from an empty value of the new type,
+        // an expired one that is not deleted yet never lets its old value show through
+        if creates {
+            let empty = match _type {
+                DataType::Counter => Bson::Int64(0),
+                DataType::Hash => Bson::Document(doc! {}),
+                _ => Bson::Array(vec![]),
+            };
+            self.collection
+                .update_one(
+                    doc! {"key": key, "$or": [{"deleted": true}, {"expires_at": {"$lte": now}}]},
+                    doc! {
+                        "$set": {"type": _type.as_str(), "value": empty, "deleted": false},
+                        "$unset": {"deleted_at": "", "ttl": "", "expires_at": ""},
+                    },
+                    None,
+                )
+                .await?;
+        }
+
+        // The type is part of the filter, so a key of another type is never changed:
+        // it either matches nothing or makes the upsert hit the unique index on key
+        let mut options = after();
+        options.upsert = Some(creates);
+
+        let data = self
+            .collection
+            .find_one_and_update(
+                live(doc! {"key": key, "type": _type.as_str()}),
+                update,
+                options,
+            )
+            .await
+            .map_err(|e| {
+                if is_duplicate_key(&e) {
+                    Error::WrongDataType
+                } else {
+                    e.into()
+                }
+            })?;
+
+        if data.is_none()
+            && self
+                .collection
+                .count_documents(live(doc! {"key": key}), None)
+                .await?
+                > 0
+        {
+            return Err(Error::WrongDataType);
+        }
+
+        Ok(data)
+    }
+
+    async fn get_data(&self, key: &str) -> Result<Option<Data>, Error> {
+        let data = self
+            .collection
+            .find_one(live(doc! {"key": key}), None)
+            .await?;
+        Ok(data)
+    }
+
+    async fn get_keys(&self, filter: &KeysFilter) -> Result<DataPage, Error> {
+        let filter_document = live(filter.to_document());
+
+        let total = self
+            .collection
+            .count_documents(filter_document.clone(), None)
+            .await?;
+
+        let options = FindOptions::builder()
+            .sort(doc! {"key": 1})
+            .skip(filter.skip())
+            .limit(filter.limit as i64)
+            .build();
+
+        let items = self
+            .collection
+            .find(filter_document, options)
+            .await?
+            .try_collect()
+            .await?;
+
+        Ok(DataPage {
+            items,
+            page: filter.page,
+            limit: filter.limit,
+            total,
+        })
+    }
+
+    async fn snapshot(&self) -> Result<BoxStream<'static, Result<Data, Error>>, Error> {
+        let options = FindOptions::builder().sort(doc! {"key": 1}).build();
+        let data = self.collection.find(live(doc! {}), options).await?;
+        Ok(data.map_err(Error::from).boxed())
+    }
+
+    async fn next_expired(&self) -> Result<Option<String>, Error> {
+        let now = DateTime::now().timestamp_millis();
+
+        let options = FindOneOptions::builder()
+            .sort(doc! {"expires_at": 1})
+            .build();
+
+        let data = self
+            .collection
+            .find_one(
+                doc! {"expires_at": {"$ne": null, "$lte": now}, "deleted": {"$ne": true}},
+                options,
+            )
+            .await?;
+
+        Ok(data.map(|data| data.key))
+    }
+
+    async fn take_expired(&self, key: &str) -> Result<Option<Data>, Error> {
+        let now = DateTime::now().timestamp_millis();
+
+        let data = self
+            .collection
+            .find_one_and_update(
+                doc! {
+                    "key": key,
+                    "expires_at": {"$ne": null, "$lte": now},
+                    "deleted": {"$ne": true},
+                },
+                tombstone(now),
+                after(),
+            )
+            .await?;
+
+        Ok(data)
+    }
+}
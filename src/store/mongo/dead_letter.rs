@@ -0,0 +1,94 @@
+use async_trait::async_trait;
+use futures_util::TryStreamExt;
+use mongodb::{bson::doc, options::FindOptions, Collection};
+
+use crate::{
+    error::Error,
+    models::{dead_letter::DeadLetter, delivery::Delivery},
+    request::dead_letter::filter::DeadLetterFilter,
+    store::DeadLetterStore,
+};
+
+pub struct MongoDeadLetterStore {
+    collection: Collection<DeadLetter>,
+}
+
+impl MongoDeadLetterStore {
+    pub fn init(collection: &Collection<DeadLetter>) -> Self {
+        Self {
+            collection: collection.clone(),
+        }
+    }
+}
+
+#[async_trait]
+impl DeadLetterStore for MongoDeadLetterStore {
+    async fn get_dead_letters(&self, filter: &DeadLetterFilter) -> Result<Vec<DeadLetter>, Error> {
+        let options = FindOptions::builder()
+            .sort(doc! {"failed_at": 1, "_id": 1})
+            .build();
+
+        let dead_letters = self
+            .collection
+            .find(filter.to_document(), options)
+            .await?
+            .try_collect()
+            .await?;
+
+        Ok(dead_letters)
+    }
+
+    async fn get_dead_letter(&self, id: &str) -> Result<Option<DeadLetter>, Error> {
+        let dead_letter = self.collection.find_one(doc! {"_id": id}, None).await?;
+        Ok(dead_letter)
+    }
+
+    async fn add_dead_letter(&self, dead_letter: DeadLetter) -> Result<(), Error> {
+        self.collection.insert_one(dead_letter, None).await?;
+        Ok(())
+    }
+
+    async fn record_failure(&self, id: &str, delivery: &Delivery) -> Result<(), Error> {
+        self.collection
+            .update_one(
+                doc! {"_id": id},
+                doc! {
+                    "$inc": {"attempts": delivery.attempts},
+                    "$set": {"error": &delivery.error},
+                },
+                None,
+            )
+            .await?;
+        Ok(())
+    }
+
+    async fn delete_dead_letter(&self, id: &str) -> Result<bool, Error> {
+        let deleted = self
+            .collection
+            .delete_one(doc! {"_id": id}, None)
+            .await?
+            .deleted_count;
+        Ok(deleted > 0)
+    }
+
+    async fn delete_dead_letters(&self, filter: &DeadLetterFilter) -> Result<u64, Error> {
+        let deleted = self
+            .collection
+            .delete_many(filter.to_document(), None)
+            .await?
+            .deleted_count;
+        Ok(deleted)
+    }
+
+    async fn move_dead_letters(&self, from: &str, to: &str) -> Result<(), Error> {
+        self.collection
+            .update_many(doc! {"url": from}, doc! {"$set": {"url": to}}, None)
+            .await?;
+        Ok(())
+    }
+
+    async fn delete_dead_letters_of(&self, url: &str) -> Result<u64, Error> {
+        let result = self.collection.delete_many(doc! {"url": url}, None).await?;
+        Ok(result.deleted_count)
+    }
+}
@@ -0,0 +1,44 @@
+use async_trait::async_trait;
+use mongodb::{bson::doc, options::IndexOptions, Collection, IndexModel};
+
+use crate::{config::HealthConfig, error::Error, models::health::HealthRecord, store::HealthStore};
+
+pub struct MongoHealthStore {
+    collection: Collection<HealthRecord>,
+}
+
+impl MongoHealthStore {
+    pub async fn init(collection: &Collection<HealthRecord>, config: &HealthConfig) -> Self {
+        // Old health checks are removed by mongodb itself
+        let index = IndexModel::builder()
+            .keys(doc! {"created_at": 1})
+            .options(
+                IndexOptions::builder()
+                    .expire_after(config.history_ttl)
+                    .build(),
+            )
+            .build();
+
+        collection
+            .create_index(index, None)
+            .await
+            .expect("Cannot create index on the HealthHistory collection");
+
+        Self {
+            collection: collection.clone(),
+        }
+    }
+}
+
+#[async_trait]
+impl HealthStore for MongoHealthStore {
+    async fn add_records(&self, records: Vec<HealthRecord>) -> Result<(), Error> {
+        self.collection.insert_many(records, None).await?;
+        Ok(())
+    }
+
+    async fn delete_history(&self, url: &str) -> Result<u64, Error> {
+        let result = self.collection.delete_many(doc! {"url": url}, None).await?;
+        Ok(result.deleted_count)
+    }
+}
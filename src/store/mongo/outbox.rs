@@ -0,0 +1,186 @@
+use async_trait::async_trait;
+use futures_util::TryStreamExt;
+use mongodb::{
+    bson::{doc, oid::ObjectId, to_bson, DateTime, Document},
+    options::{FindOneAndUpdateOptions, FindOptions, ReturnDocument, UpdateOptions},
+    Collection,
+};
+
+use crate::{
+    error::Error,
+    models::{
+        delivery::Delivery,
+        operation::SyncOperation,
+        outbox::{OutboxDelivery, OutboxEntry},
+    },
+    store::OutboxStore,
+};
+
+pub struct MongoOutboxStore {
+    collection: Collection<OutboxEntry>,
+}
+
+impl MongoOutboxStore {
+    pub fn init(collection: &Collection<OutboxEntry>) -> Self {
+        Self {
+            collection: collection.clone(),
+        }
+    }
+}
+
+#[async_trait]
+impl OutboxStore for MongoOutboxStore {
+    async fn insert(&self, entry: OutboxEntry) -> Result<ObjectId, Error> {
+        self.collection
+            .insert_one(entry, None)
+            .await?
+            .inserted_id
+            .as_object_id()
+            .ok_or_else(|| Error::Generic)
+    }
+
+    async fn release(
+        &self,
+        id: ObjectId,
+        operation: &SyncOperation,
+        deliveries: &[OutboxDelivery],
+    ) -> Result<(), Error> {
+        let operation = to_bson(operation).map_err(|_| Error::Generic)?;
+        let deliveries = to_bson(deliveries).map_err(|_| Error::Generic)?;
+
+        self.collection
+            .update_one(
+                doc! {"_id": id},
+                doc! {"$set": {"operation": operation, "deliveries": deliveries, "held": false}},
+                None,
+            )
+            .await?;
+        Ok(())
+    }
+
+    async fn claim_due(&self, lease_until: DateTime) -> Result<Option<OutboxEntry>, Error> {
+        let options = FindOneAndUpdateOptions::builder()
+            .sort(doc! {"_id": 1})
+            .return_document(ReturnDocument::After)
+            .build();
+
+        let entry = self
+            .collection
+            .find_one_and_update(
+                doc! {"next_attempt_at": {"$lte": DateTime::now()}},
+                doc! {"$set": {"next_attempt_at": lease_until}},
+                options,
+            )
+            .await?;
+
+        Ok(entry)
+    }
+
+    async fn pending_for(&self, url: &str) -> Result<Vec<OutboxEntry>, Error> {
+        let options = FindOptions::builder().sort(doc! {"_id": 1}).build();
+
+        let entries = self
+            .collection
+            .find(
+                doc! {"deliveries": {"$elemMatch": {"url": url, "acked": false}}},
+                options,
+            )
+            .await?
+            .try_collect()
+            .await?;
+
+        Ok(entries)
+    }
+
+    async fn record(
+        &self,
+        id: ObjectId,
+        deliveries: &[Delivery],
+        retry_at: DateTime,
+    ) -> Result<Option<OutboxEntry>, Error> {
+        let mut set = Document::new();
+        let mut inc = Document::new();
+        let mut array_filters = vec![];
+
+        for (
+            i,
+            Delivery {
+                url,
+                attempts,
+                error,
+                ..
+            },
+        ) in deliveries.iter().enumerate()
+        {
+            set.insert(format!("deliveries.$[d{i}].acked"), error.is_none());
+            set.insert(format!("deliveries.$[d{i}].last_error"), error.clone());
+            inc.insert(format!("deliveries.$[d{i}].attempts"), *attempts);
+            array_filters.push(doc! {format!("d{i}.url"): url});
+        }
+        set.insert("next_attempt_at", retry_at);
+
+        let options = FindOneAndUpdateOptions::builder()
+            .array_filters(array_filters)
+            .return_document(ReturnDocument::After)
+            .build();
+
+        let entry = self
+            .collection
+            .find_one_and_update(doc! {"_id": id}, doc! {"$set": set, "$inc": inc}, options)
+            .await?;
+
+        Ok(entry)
+    }
+
+    async fn discard(&self, id: ObjectId, urls: &[String]) -> Result<(), Error> {
+        if !urls.is_empty() {
+            self.collection
+                .update_one(
+                    doc! {"_id": id},
+                    doc! {"$pull": {"deliveries": {"url": {"$in": urls}}}},
+                    None,
+                )
+                .await?;
+        }
+
+        // No delivery left unacknowledged
+        self.collection
+            .delete_one(doc! {"_id": id, "deliveries.acked": {"$ne": false}}, None)
+            .await?;
+
+        Ok(())
+    }
+
+    async fn move_deliveries(&self, from: &str, to: &str) -> Result<(), Error> {
+        let options = UpdateOptions::builder()
+            .array_filters(vec![doc! {"d.url": from}])
+            .build();
+
+        self.collection
+            .update_many(
+                doc! {"deliveries.url": from},
+                doc! {"$set": {"deliveries.$[d].url": to}},
+                options,
+            )
+            .await?;
+        Ok(())
+    }
+
+    async fn remove_deliveries(&self, url: &str) -> Result<u64, Error> {
+        let result = self
+            .collection
+            .update_many(
+                doc! {"deliveries": {"$elemMatch": {"url": url, "acked": false}}},
+                doc! {"$pull": {"deliveries": {"url": url}}},
+                None,
+            )
+            .await?;
+
+        // Entries that no other proxy is waiting for
+        self.collection
+            .delete_many(doc! {"deliveries.acked": {"$ne": false}}, None)
+            .await?;
+
+        Ok(result.modified_count)
+    }
+}
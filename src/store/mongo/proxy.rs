@@ -0,0 +1,350 @@
+use std::{collections::HashMap, time::Duration};
+
+use async_trait::async_trait;
+use futures_util::TryStreamExt;
+use mongodb::{
+    bson::{doc, to_bson, DateTime},
+    options::{FindOneAndUpdateOptions, IndexOptions, ReturnDocument},
+    Collection, IndexModel,
+};
+
+use crate::{
+    error::Error,
+    helper::{id::id_filter, mongo::is_duplicate_key, url::normalize_url},
+    models::{
+        health::{HealthCheck, HealthStatus},
+        proxy::{Bootstrap, BootstrapStatus, Breaker, BreakerState, Proxy, ProxyMode},
+    },
+    request::proxy::update::UpdateProxyRequest,
+    store::ProxyStore,
+};
+
+// Raised by the unique index on url
+fn already_exists(error: mongodb::error::Error) -> Error {
+    if is_duplicate_key(&error) {
+        Error::ProxyAlreadyExists
+    } else {
+        error.into()
+    }
+}
+
+pub struct MongoProxyStore {
+    collection: Collection<Proxy>,
+}
+
+impl MongoProxyStore {
+    pub async fn init(collection: &Collection<Proxy>) -> Self {
+        // Proxies registered before urls were normalized are given the form
+        // requests now look them up with. Two of them ending up under the
+        // same url would leave one unreachable, so it is left to an operator
+        let proxies: Vec<Proxy> = collection
+            .find(None, None)
+            .await
+            .expect("Cannot load the Proxy collection")
+            .try_collect()
+            .await
+            .expect("Cannot load the Proxy collection");
+
+        let mut urls = HashMap::new();
+        for proxy in &proxies {
+            let url = normalize_url(&proxy.url);
+            if let Some(other) = urls.insert(url.clone(), &proxy.url) {
+                panic!(
+                    "Proxies {other} and {} both normalize to {url}, remove one of them",
+                    proxy.url
+                );
+            }
+        }
+
+        for proxy in proxies {
+            let url = normalize_url(&proxy.url);
+            if url != proxy.url {
+                collection
+                    .update_one(id_filter(&proxy.id), doc! {"$set": {"url": url}}, None)
+                    .await
+                    .expect("Cannot normalize the urls of the Proxy collection");
+            }
+        }
+
+        // Every proxy is registered once, even under concurrent requests
+        let index = IndexModel::builder()
+            .keys(doc! {"url": 1})
+            .options(IndexOptions::builder().unique(true).build())
+            .build();
+
+        collection
+            .create_index(index, None)
+            .await
+            .expect("Cannot create index on the Proxy collection");
+
+        Self {
+            collection: collection.clone(),
+        }
+    }
+}
+
+#[async_trait]
+impl ProxyStore for MongoProxyStore {
+    async fn get_proxies(&self) -> Result<Vec<Proxy>, Error> {
+        let proxies = self
+            .collection
+            .find(None, None)
+            .await?
+            .try_collect()
+            .await?;
+        Ok(proxies)
+    }
+
+    async fn get_proxy(&self, url: &str) -> Result<Option<Proxy>, Error> {
+        let proxy = self.collection.find_one(doc! {"url": url}, None).await?;
+        Ok(proxy)
+    }
+
+    async fn get_proxy_by_id(&self, id: &str) -> Result<Option<Proxy>, Error> {
+        let proxy = self.collection.find_one(id_filter(id), None).await?;
+        Ok(proxy)
+    }
+
+    async fn add_proxy(&self, proxy: Proxy) -> Result<Proxy, Error> {
+        // Add the proxy into the database and gets its ID
+        let new_proxy_id = self
+            .collection
+            .insert_one(proxy, None)
+            .await
+            .map_err(already_exists)?
+            .inserted_id
+            .as_str()
+            .map(String::from)
+            .ok_or_else(|| Error::Generic)?;
+
+        // Get the new proxy
+        let new_proxy = self
+            .collection
+            .find_one(doc! {"_id": new_proxy_id}, None)
+            .await?
+            .ok_or_else(|| Error::CannotCreateProxy)?;
+
+        Ok(new_proxy)
+    }
+
+    // Changes the given fields of a proxy in a single write
+    async fn update_proxy(
+        &self,
+        id: &str,
+        update: &UpdateProxyRequest,
+    ) -> Result<Option<Proxy>, Error> {
+        let fields = update.to_document()?;
+        if fields.is_empty() {
+            return self.get_proxy_by_id(id).await;
+        }
+
+        let options = FindOneAndUpdateOptions::builder()
+            .return_document(ReturnDocument::After)
+            .build();
+
+        self.collection
+            .find_one_and_update(id_filter(id), doc! {"$set": fields}, options)
+            .await
+            .map_err(already_exists)
+    }
+
+    async fn set_mode(&self, id: &str, mode: ProxyMode) -> Result<Option<Proxy>, Error> {
+        let mode = to_bson(&mode).map_err(|_| Error::Generic)?;
+
+        let options = FindOneAndUpdateOptions::builder()
+            .return_document(ReturnDocument::After)
+            .build();
+
+        let proxy = self
+            .collection
+            .find_one_and_update(id_filter(id), doc! {"$set": {"mode": mode}}, options)
+            .await?;
+        Ok(proxy)
+    }
+
+    // Changes the mode of a proxy only if it is still in the expected one
+    async fn transition(&self, url: &str, from: ProxyMode, to: ProxyMode) -> Result<bool, Error> {
+        let from = to_bson(&from).map_err(|_| Error::Generic)?;
+        let to = to_bson(&to).map_err(|_| Error::Generic)?;
+
+        let result = self
+            .collection
+            .update_one(
+                doc! {"url": url, "mode": from},
+                doc! {"$set": {"mode": to}},
+                None,
+            )
+            .await?;
+
+        Ok(result.modified_count == 1)
+    }
+
+    async fn delete_proxy(&self, url: &str) -> Result<Option<Proxy>, Error> {
+        let proxy = self
+            .collection
+            .find_one_and_delete(doc! {"url": url}, None)
+            .await?;
+        Ok(proxy)
+    }
+
+    async fn delete_proxy_by_id(&self, id: &str) -> Result<Option<Proxy>, Error> {
+        let proxy = self
+            .collection
+            .find_one_and_delete(id_filter(id), None)
+            .await?;
+        Ok(proxy)
+    }
+
+    async fn update_bootstrap(&self, url: &str, bootstrap: &Bootstrap) -> Result<(), Error> {
+        let bootstrap = to_bson(bootstrap).map_err(|_| Error::Generic)?;
+
+        self.collection
+            .update_one(
+                doc! {"url": url},
+                doc! {"$set": {"bootstrap": bootstrap}},
+                None,
+            )
+            .await?;
+        Ok(())
+    }
+
+    async fn claim_bootstrap(&self, url: &str) -> Result<bool, Error> {
+        let running = to_bson(&BootstrapStatus::Running).map_err(|_| Error::Generic)?;
+
+        let result = self
+            .collection
+            .update_one(
+                doc! {"url": url, "bootstrap.status": {"$ne": &running}},
+                doc! {"$set": {"bootstrap.status": &running}},
+                None,
+            )
+            .await?;
+
+        Ok(result.modified_count == 1)
+    }
+
+    // Stores the latest health check on the proxy
+    async fn record_health(&self, check: &HealthCheck) -> Result<(), Error> {
+        let last_check = to_bson(check).map_err(|_| Error::Generic)?;
+        let update = match check.status {
+            HealthStatus::Healthy => doc! {"$set": {
+                "status": "healthy",
+                "last_seen": check.checked_at,
+                "consecutive_failures": 0,
+                "last_error": null,
+                "last_check": last_check,
+            }},
+            _ => doc! {
+                "$set": {
+                    "status": "unhealthy",
+                    "last_error": &check.error,
+                    "last_check": last_check,
+                },
+                "$inc": {"consecutive_failures": 1},
+            },
+        };
+
+        self.collection
+            .update_one(doc! {"url": &check.url}, update, None)
+            .await?;
+        Ok(())
+    }
+
+    // Moves an open breaker to half-open once its cool down is over.
+    // Only one caller gets to do so, and that caller may send the probing request.
+    // A half-open breaker whose probe never finished is handed out again after another cool down
+    async fn half_open(&self, url: &str, cool_down: Duration) -> Result<bool, Error> {
+        let now = DateTime::now().timestamp_millis();
+
+        let result = self
+            .collection
+            .update_one(
+                doc! {
+                    "url": url,
+                    "breaker.state": {"$in": ["open", "half_open"]},
+                    "breaker.opened_at": {"$lte": now - cool_down.as_millis() as i64},
+                },
+                doc! {"$set": {"breaker.state": "half_open", "breaker.opened_at": now}},
+                None,
+            )
+            .await?;
+
+        Ok(result.modified_count == 1)
+    }
+
+    // Feeds the outcome of a delivery to the breaker of a proxy
+    async fn record_delivery(
+        &self,
+        url: &str,
+        success: bool,
+        failure_threshold: u32,
+    ) -> Result<(), Error> {
+        if success {
+            // Only proxies with failures on record need to be written to
+            self.collection
+                .update_one(
+                    doc! {"url": url, "breaker.failures": {"$ne": 0}},
+                    doc! {"$set": {"breaker": to_bson(&Breaker::default()).map_err(|_| Error::Generic)?}},
+                    None,
+                )
+                .await?;
+            return Ok(());
+        }
+
+        let options = FindOneAndUpdateOptions::builder()
+            .return_document(ReturnDocument::After)
+            .build();
+
+        let Some(Proxy { breaker, .. }) = self
+            .collection
+            .find_one_and_update(
+                doc! {"url": url},
+                doc! {"$inc": {"breaker.failures": 1}},
+                options,
+            )
+            .await?
+        else {
+            return Ok(());
+        };
+
+        // A failed probe opens the breaker again right away
+        let trips = match breaker.state {
+            BreakerState::Closed => breaker.failures >= failure_threshold,
+            BreakerState::HalfOpen => true,
+            BreakerState::Open => false,
+        };
+
+        if trips {
+            self.collection
+                .update_one(
+                    doc! {"url": url},
+                    doc! {"$set": {
+                        "breaker.state": "open",
+                        "breaker.opened_at": DateTime::now().timestamp_millis(),
+                    }},
+                    None,
+                )
+                .await?;
+        }
+
+        Ok(())
+    }
+
+    async fn reset_breaker(&self, url: &str) -> Result<Option<Proxy>, Error> {
+        let breaker = to_bson(&Breaker::default()).map_err(|_| Error::Generic)?;
+
+        let options = FindOneAndUpdateOptions::builder()
+            .return_document(ReturnDocument::After)
+            .build();
+
+        let proxy = self
+            .collection
+            .find_one_and_update(
+                doc! {"url": url},
+                doc! {"$set": {"breaker": breaker}},
+                options,
+            )
+            .await?;
+        Ok(proxy)
+    }
+}
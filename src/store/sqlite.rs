@@ -0,0 +1,168 @@
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
+
+use async_trait::async_trait;
+use rusqlite::{params, Connection};
+
+use crate::{error::Error, models::proxy::Proxy};
+
+use super::table::ProxyTable;
+
+// Keeps the proxy registry in a SQLite file, so that it survives restarts without
+// a Proxy collection. The data, outbox and history still live in mongodb.
+// Every proxy is one row holding its JSON document
+
+pub struct SqliteProxyStore {
+    connection: Arc<Mutex<Connection>>,
+}
+
+// The rows of the table in the order they were added, as (id, document)
+fn load(connection: &Connection) -> Result<Vec<(String, String)>, Error> {
+    let rows = connection
+        .prepare("SELECT id, document FROM proxy ORDER BY rowid")?
+        .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(rows)
+}
+
+fn parse(rows: &[(String, String)]) -> Result<Vec<Proxy>, Error> {
+    rows.iter()
+        .map(|(_, document)| serde_json::from_str(document).map_err(|_| Error::Generic))
+        .collect()
+}
+
+impl SqliteProxyStore {
+    pub fn open(path: &str) -> Result<Self, Error> {
+        let connection = Connection::open(path)?;
+
+        connection.execute(
+            "CREATE TABLE IF NOT EXISTS proxy (
+                id TEXT PRIMARY KEY,
+                url TEXT NOT NULL UNIQUE,
+                document TEXT NOT NULL
+            )",
+            [],
+        )?;
+
+        Ok(Self {
+            connection: Arc::new(Mutex::new(connection)),
+        })
+    }
+
+    // SQLite blocks, so it is kept off the threads that run the requests
+    async fn blocking<R: Send + 'static>(
+        &self,
+        f: impl FnOnce(&mut Connection) -> Result<R, Error> + Send + 'static,
+    ) -> Result<R, Error> {
+        let connection = self.connection.clone();
+        tokio::task::spawn_blocking(move || {
+            let mut connection = connection.lock().map_err(|_| Error::Generic)?;
+            f(&mut connection)
+        })
+        .await
+        .map_err(|_| Error::Generic)?
+    }
+}
+
+#[async_trait]
+impl ProxyTable for SqliteProxyStore {
+    async fn read_proxies<R: Send + 'static>(
+        &self,
+        f: impl for<'a> FnOnce(&'a [Proxy]) -> R + Send + 'static,
+    ) -> Result<R, Error> {
+        self.blocking(|connection| Ok(f(&parse(&load(connection)?)?)))
+            .await
+    }
+
+    async fn with_proxies<R: Send + 'static>(
+        &self,
+        f: impl for<'a> FnOnce(&'a mut Vec<Proxy>) -> Result<R, Error> + Send + 'static,
+    ) -> Result<R, Error> {
+        self.blocking(|connection| {
+            let transaction = connection.transaction()?;
+
+            let rows = load(&transaction)?;
+            let mut proxies = parse(&rows)?;
+            let result = f(&mut proxies)?;
+
+            // Only the rows that changed are written back
+            let before = rows.into_iter().collect::<HashMap<_, _>>();
+            for id in before.keys() {
+                if !proxies.iter().any(|proxy| &proxy.id == id) {
+                    transaction.execute("DELETE FROM proxy WHERE id = ?1", params![id])?;
+                }
+            }
+
+            for proxy in &proxies {
+                let document = serde_json::to_string(proxy).map_err(|_| Error::Generic)?;
+                if before.get(&proxy.id) != Some(&document) {
+                    transaction.execute(
+                        "INSERT INTO proxy (id, url, document) VALUES (?1, ?2, ?3)
+                        ON CONFLICT (id) DO UPDATE SET url = excluded.url, document = excluded.document",
+                        params![proxy.id, proxy.url, document],
+                    )?;
+                }
+            }
+
+            transaction.commit()?;
+            Ok(result)
+        })
+        .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        models::proxy::{Proxy, ProxyMode},
+        store::ProxyStore,
+    };
+
+    use super::SqliteProxyStore;
+
+    #[tokio::test]
+    async fn sqlite_store_should_persist_changes() {
+        let store = SqliteProxyStore::open(":memory:").unwrap();
+
+        let proxy = store
+            .add_proxy(Proxy::new("http://proxy1:3000"))
+            .await
+            .unwrap();
+
+        assert!(store
+            .transition(&proxy.url, ProxyMode::Enabled, ProxyMode::Maintenance)
+            .await
+            .unwrap());
+        assert!(!store
+            .transition(&proxy.url, ProxyMode::Enabled, ProxyMode::Disabled)
+            .await
+            .unwrap());
+
+        let stored = store.get_proxy_by_id(&proxy.id).await.unwrap().unwrap();
+        assert_eq!(stored.url, "http://proxy1:3000");
+        assert_eq!(stored.mode, ProxyMode::Maintenance);
+
+        // Proxies keep the order they were added in, also once changed
+        for url in ["http://proxy3:3000", "http://proxy2:3000"] {
+            store.add_proxy(Proxy::new(url)).await.unwrap();
+        }
+        store.set_mode(&proxy.id, ProxyMode::Enabled).await.unwrap();
+        let urls = store
+            .get_proxies()
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|proxy| proxy.url)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            urls,
+            [
+                "http://proxy1:3000",
+                "http://proxy3:3000",
+                "http://proxy2:3000"
+            ]
+        );
+    }
+}
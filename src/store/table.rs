@@ -0,0 +1,287 @@
+use std::time::Duration;
+
+use async_trait::async_trait;
+use mongodb::bson::DateTime;
+
+use crate::{
+    error::Error,
+    models::{
+        health::{HealthCheck, HealthStatus},
+        proxy::{Bootstrap, BootstrapStatus, Breaker, BreakerState, Proxy, ProxyMode},
+    },
+    request::proxy::update::UpdateProxyRequest,
+};
+
+use super::ProxyStore;
+
+// Backends that load every proxy, and write them back under a lock when they change.
+
+#[async_trait]
+pub trait ProxyTable: Send + Sync {
+    // Proxies are handed out in the order they were added
+    async fn read_proxies<R: Send + 'static>(
+        &self,
+        f: impl for<'a> FnOnce(&'a [Proxy]) -> R + Send + 'static,
+    ) -> Result<R, Error>;
+
+    // Writes back whatever the change left in the list
+    async fn with_proxies<R: Send + 'static>(
+        &self,
+        f: impl for<'a> FnOnce(&'a mut Vec<Proxy>) -> Result<R, Error> + Send + 'static,
+    ) -> Result<R, Error>;
+}
+
+// Changes the first proxy picked, returning it as it is afterwards.
+// The change may decline by returning false, like an update whose filter did not match
+async fn modify<T: ProxyTable>(
+    table: &T,
+    pick: impl Fn(&Proxy) -> bool + Send + 'static,
+    change: impl FnOnce(&mut Proxy) -> bool + Send + 'static,
+) -> Result<Option<Proxy>, Error> {
+    table
+        .with_proxies(move |proxies| {
+            Ok(proxies
+                .iter_mut()
+                .find(|proxy| pick(proxy))
+                .and_then(|proxy| change(proxy).then(|| proxy.clone())))
+        })
+        .await
+}
+
+async fn remove<T: ProxyTable>(
+    table: &T,
+    pick: impl Fn(&Proxy) -> bool + Send + 'static,
+) -> Result<Option<Proxy>, Error> {
+    table
+        .with_proxies(move |proxies| {
+            Ok(proxies
+                .iter()
+                .position(pick)
+                .map(|index| proxies.remove(index)))
+        })
+        .await
+}
+
+#[async_trait]
+impl<T: ProxyTable> ProxyStore for T {
+    async fn get_proxies(&self) -> Result<Vec<Proxy>, Error> {
+        self.read_proxies(|proxies| proxies.to_vec()).await
+    }
+
+    async fn get_proxy(&self, url: &str) -> Result<Option<Proxy>, Error> {
+        let url = url.to_string();
+        self.read_proxies(move |proxies| proxies.iter().find(|proxy| proxy.url == url).cloned())
+            .await
+    }
+
+    async fn get_proxy_by_id(&self, id: &str) -> Result<Option<Proxy>, Error> {
+        let id = id.to_string();
+        self.read_proxies(move |proxies| proxies.iter().find(|proxy| proxy.id == id).cloned())
+            .await
+    }
+
+    async fn add_proxy(&self, proxy: Proxy) -> Result<Proxy, Error> {
+        self.with_proxies(|proxies| {
+            if proxies.iter().any(|other| other.url == proxy.url) {
+                return Err(Error::ProxyAlreadyExists);
+            }
+            proxies.push(proxy.clone());
+            Ok(proxy)
+        })
+        .await
+    }
+
+    async fn update_proxy(
+        &self,
+        id: &str,
+        update: &UpdateProxyRequest,
+    ) -> Result<Option<Proxy>, Error> {
+        let (id, update) = (id.to_string(), update.clone());
+        self.with_proxies(move |proxies| {
+            if let Some(url) = &update.url {
+                if proxies
+                    .iter()
+                    .any(|other| &other.url == url && other.id != id)
+                {
+                    return Err(Error::ProxyAlreadyExists);
+                }
+            }
+
+            Ok(proxies
+                .iter_mut()
+                .find(|proxy| proxy.id == id)
+                .map(|proxy| {
+                    update.apply(proxy);
+                    proxy.clone()
+                }))
+        })
+        .await
+    }
+
+    async fn set_mode(&self, id: &str, mode: ProxyMode) -> Result<Option<Proxy>, Error> {
+        let id = id.to_string();
+        modify(
+            self,
+            move |proxy| proxy.id == id,
+            move |proxy| {
+                proxy.mode = mode;
+                true
+            },
+        )
+        .await
+    }
+
+    async fn transition(&self, url: &str, from: ProxyMode, to: ProxyMode) -> Result<bool, Error> {
+        let url = url.to_string();
+        let proxy = modify(
+            self,
+            move |proxy| proxy.url == url && proxy.mode == from,
+            move |proxy| {
+                proxy.mode = to;
+                true
+            },
+        )
+        .await?;
+        Ok(proxy.is_some())
+    }
+
+    async fn delete_proxy(&self, url: &str) -> Result<Option<Proxy>, Error> {
+        let url = url.to_string();
+        remove(self, move |proxy| proxy.url == url).await
+    }
+
+    async fn delete_proxy_by_id(&self, id: &str) -> Result<Option<Proxy>, Error> {
+        let id = id.to_string();
+        remove(self, move |proxy| proxy.id == id).await
+    }
+
+    async fn update_bootstrap(&self, url: &str, bootstrap: &Bootstrap) -> Result<(), Error> {
+        let (url, bootstrap) = (url.to_string(), bootstrap.clone());
+        modify(
+            self,
+            move |proxy| proxy.url == url,
+            move |proxy| {
+                proxy.bootstrap = bootstrap;
+                true
+            },
+        )
+        .await?;
+        Ok(())
+    }
+
+    async fn claim_bootstrap(&self, url: &str) -> Result<bool, Error> {
+        let url = url.to_string();
+        let proxy = modify(
+            self,
+            move |proxy| proxy.url == url && proxy.bootstrap.status != BootstrapStatus::Running,
+            |proxy| {
+                proxy.bootstrap.status = BootstrapStatus::Running;
+                true
+            },
+        )
+        .await?;
+        Ok(proxy.is_some())
+    }
+
+    async fn record_health(&self, check: &HealthCheck) -> Result<(), Error> {
+        let check = check.clone();
+        let url = check.url.clone();
+        modify(
+            self,
+            move |proxy| proxy.url == url,
+            move |proxy| {
+                match check.status {
+                    HealthStatus::Healthy => {
+                        proxy.status = HealthStatus::Healthy;
+                        proxy.last_seen = Some(check.checked_at);
+                        proxy.consecutive_failures = 0;
+                        proxy.last_error = None;
+                    }
+                    _ => {
+                        proxy.status = HealthStatus::Unhealthy;
+                        proxy.last_error = check.error.clone();
+                        proxy.consecutive_failures += 1;
+                    }
+                }
+                proxy.last_check = Some(check);
+                true
+            },
+        )
+        .await?;
+        Ok(())
+    }
+
+    async fn half_open(&self, url: &str, cool_down: Duration) -> Result<bool, Error> {
+        let url = url.to_string();
+        let now = DateTime::now().timestamp_millis();
+
+        let proxy = modify(
+            self,
+            move |proxy| proxy.url == url,
+            move |Proxy { breaker, .. }| {
+                let cooled_down = breaker
+                    .opened_at
+                    .is_some_and(|opened_at| opened_at <= now - cool_down.as_millis() as i64);
+
+                if breaker.state == BreakerState::Closed || !cooled_down {
+                    return false;
+                }
+
+                breaker.state = BreakerState::HalfOpen;
+                breaker.opened_at = Some(now);
+                true
+            },
+        )
+        .await?;
+        Ok(proxy.is_some())
+    }
+
+    async fn record_delivery(
+        &self,
+        url: &str,
+        success: bool,
+        failure_threshold: u32,
+    ) -> Result<(), Error> {
+        let url = url.to_string();
+        modify(
+            self,
+            move |proxy| proxy.url == url,
+            move |Proxy { breaker, .. }| {
+                if success {
+                    *breaker = Breaker::default();
+                    return true;
+                }
+
+                breaker.failures += 1;
+
+                // A failed probe opens the breaker again right away
+                let trips = match breaker.state {
+                    BreakerState::Closed => breaker.failures >= failure_threshold,
+                    BreakerState::HalfOpen => true,
+                    BreakerState::Open => false,
+                };
+
+                if trips {
+                    breaker.state = BreakerState::Open;
+                    breaker.opened_at = Some(DateTime::now().timestamp_millis());
+                }
+                true
+            },
+        )
+        .await?;
+        Ok(())
+    }
+
+    async fn reset_breaker(&self, url: &str) -> Result<Option<Proxy>, Error> {
+        let url = url.to_string();
+        modify(
+            self,
+            move |proxy| proxy.url == url,
+            |proxy| {
+                proxy.breaker = Breaker::default();
+                true
+            },
+        )
+        .await
+    }
+}
@@ -0,0 +1,299 @@
+use std::{
+    collections::BTreeMap,
+    net::{SocketAddr, TcpListener},
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use axum::{
+    extract::State,
+    http::{Method, StatusCode, Uri},
+    response::{IntoResponse, Response},
+    Json, Router,
+};
+use serde_json::{json, Value};
+use tokio::time::sleep;
+
+use crate::models::{
+    data_op::DataOp,
+    digest::{hash_value, Digest, DIGEST_PATH},
+};
+
+// A proxy speaking the proxy-sync protocol, served in-process on a random port.
+// It records every call it gets and can be told to misbehave
+
+#[derive(Clone, Debug)]
+pub struct RecordedCall {
+    pub method: Method,
+    pub path: String,
+    pub body: Value,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub enum Behavior {
+    Healthy,
+    // Answers every sync call with this status, while staying healthy
+    Fail(StatusCode),
+    // Answers that many sync calls with this status, then turns healthy
+    FailTimes(StatusCode, u32),
+    // Waits this long before answering anything
+    Delay(Duration),
+    // Answers everything, health checks included, with a 503
+    Down,
+}
+
+struct FakeState {
+    behavior: Behavior,
+    calls: Vec<RecordedCall>,
+    // What the proxy holds, served back through its digest
+    keys: BTreeMap<String, Value>,
+    // Version of the last write of every key, deleted keys included
+    versions: BTreeMap<String, i64>,
+}
+
+impl FakeState {
+    // Whether the key is already at the version of a write or past it.
+    // Writes without a version always apply, the same as on real proxies
+    fn is_stale(&self, key: &str, version: Option<i64>) -> bool {
+        version
+            .zip(self.versions.get(key))
+            .is_some_and(|(version, held)| version <= *held)
+    }
+
+    // Takes the version of a write, unless it is stale
+    fn advance(&mut self, key: &str, version: Option<i64>) -> bool {
+        if self.is_stale(key, version) {
+            return false;
+        }
+        match version {
+            Some(version) => self.versions.insert(key.into(), version),
+            None => self.versions.remove(key),
+        };
+        true
+    }
+}
+
+#[derive(Clone)]
+pub struct FakeProxy {
+    pub url: String,
+    state: Arc<Mutex<FakeState>>,
+}
+
+impl FakeProxy {
+    pub async fn spawn() -> Self {
+        let state = Arc::new(Mutex::new(FakeState {
+            behavior: Behavior::Healthy,
+            calls: vec![],
+            keys: BTreeMap::new(),
+            versions: BTreeMap::new(),
+        }));
+
+        let listener = TcpListener::bind(SocketAddr::from(([127, 0, 0, 1], 0)))
+            .expect("Cannot bind a port for the fake proxy");
+        let url = format!(
+            "http://{}",
+            listener
+                .local_addr()
+                .expect("Cannot read the fake proxy address")
+        );
+
+        let router = Router::new().fallback(handle).with_state(state.clone());
+        let server = axum::Server::from_tcp(listener)
+            .expect("Cannot serve the fake proxy")
+            .serve(router.into_make_service());
+        tokio::spawn(server);
+
+        Self { url, state }
+    }
+
+    pub async fn spawn_many(count: usize) -> Vec<Self> {
+        let mut proxies = vec![];
+        for _ in 0..count {
+            proxies.push(Self::spawn().await);
+        }
+        proxies
+    }
+
+    pub fn behave(&self, behavior: Behavior) {
+        self.state.lock().unwrap().behavior = behavior;
+    }
+
+    pub fn fail_with(&self, status: StatusCode) {
+        self.behave(Behavior::Fail(status));
+    }
+
+    pub fn fail_times(&self, status: StatusCode, times: u32) {
+        self.behave(Behavior::FailTimes(status, times));
+    }
+
+    pub fn delay(&self, delay: Duration) {
+        self.behave(Behavior::Delay(delay));
+    }
+
+    pub fn go_down(&self) {
+        self.behave(Behavior::Down);
+    }
+
+    pub fn recover(&self) {
+        self.behave(Behavior::Healthy);
+    }
+
+    pub fn calls(&self) -> Vec<RecordedCall> {
+        self.state.lock().unwrap().calls.clone()
+    }
+
+    // The calls of the proxy-sync protocol, leaving out health checks and digests
+    pub fn sync_calls(&self) -> Vec<RecordedCall> {
+        self.calls()
+            .into_iter()
+            .filter(|call| call.path.starts_with("/proxy-sync/v1") && call.path != DIGEST_PATH)
+            .collect()
+    }
+
+    pub fn value(&self, key: &str) -> Option<Value> {
+        self.state.lock().unwrap().keys.get(key).cloned()
+    }
+}
+
+async fn handle(
+    State(state): State<Arc<Mutex<FakeState>>>,
+    method: Method,
+    uri: Uri,
+    body: Option<Json<Value>>,
+) -> Response {
+    let path = uri.path().to_string();
+    let body = body.map(|Json(body)| body).unwrap_or(Value::Null);
+
+    let behavior = {
+        let mut state = state.lock().unwrap();
+        state.calls.push(RecordedCall {
+            method: method.clone(),
+            path: path.clone(),
+            body: body.clone(),
+        });
+
+        let behavior = state.behavior;
+        if let Behavior::FailTimes(status, times) = behavior {
+            if path.starts_with("/proxy-sync/v1") {
+                state.behavior = match times {
+                    0 | 1 => Behavior::Healthy,
+                    _ => Behavior::FailTimes(status, times - 1),
+                };
+            }
+        }
+        behavior
+    };
+
+    match behavior {
+        Behavior::Down => return StatusCode::SERVICE_UNAVAILABLE.into_response(),
+        Behavior::Delay(delay) => sleep(delay).await,
+        Behavior::Fail(status) | Behavior::FailTimes(status, 1..)
+            if path.starts_with("/proxy-sync/v1") =>
+        {
+            return status.into_response()
+        }
+        _ => {}
+    }
+
+    let mut state = state.lock().unwrap();
+    match (method, path.as_str()) {
+        (Method::GET, "/health") => Json(json!({ "status": "ok" })).into_response(),
+        (Method::GET, DIGEST_PATH) => Json(Digest {
+            keys: state
+                .keys
+                .iter()
+                .map(|(key, value)| (key.clone(), hash_value(value)))
+                .collect(),
+        })
+        .into_response(),
+        (Method::POST, "/proxy-sync/v1") => {
+            if let (Some(key), Some(value)) = (body["key"].as_str(), body.get("value")) {
+                if state.advance(key, body["version"].as_i64()) {
+                    state.keys.insert(key.into(), value.clone());
+                }
+            }
+            StatusCode::OK.into_response()
+        }
+        (Method::POST, "/proxy-sync/v1/multi") => {
+            if let Some(data) = body["data"].as_object() {
+                for (key, value) in data {
+                    if state.advance(key, body["versions"][key].as_i64()) {
+                        state.keys.insert(key.clone(), value.clone());
+                    }
+                }
+            }
+            StatusCode::OK.into_response()
+        }
+        (Method::PATCH, "/proxy-sync/v1") => {
+            let (Some(key), Ok(op)) = (
+                body["key"].as_str(),
+                serde_json::from_value::<DataOp>(body.clone()),
+            ) else {
+                return StatusCode::BAD_REQUEST.into_response();
+            };
+            if state.is_stale(key, body["version"].as_i64()) {
+                return StatusCode::OK.into_response();
+            }
+            match op.apply(state.keys.get(key)) {
+                Ok(Some(value)) => {
+                    state.advance(key, body["version"].as_i64());
+                    state.keys.insert(key.into(), value);
+                }
+                Ok(None) => {}
+                Err(_) => return StatusCode::CONFLICT.into_response(),
+            }
+            StatusCode::OK.into_response()
+        }
+        (Method::DELETE, "/proxy-sync/v1") => {
+            if let Some(key) = body["key"].as_str() {
+                if state.advance(key, body["version"].as_i64()) {
+                    state.keys.remove(key);
+                }
+            }
+            StatusCode::OK.into_response()
+        }
+        _ => StatusCode::NOT_FOUND.into_response(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use reqwest::{Client, StatusCode};
+    use serde_json::json;
+
+    use super::FakeProxy;
+
+    #[tokio::test]
+    async fn fake_proxy_should_record_and_fail_on_demand() {
+        let fake_proxy = FakeProxy::spawn().await;
+        let client = Client::new();
+
+        let response = client
+            .post(format!("{}/proxy-sync/v1", fake_proxy.url))
+            .json(&json!({ "type": "String", "key": "flag", "value": true }))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(fake_proxy.value("flag"), Some(json!(true)));
+
+        fake_proxy.fail_with(StatusCode::INTERNAL_SERVER_ERROR);
+
+        let response = client
+            .delete(format!("{}/proxy-sync/v1", fake_proxy.url))
+            .json(&json!({ "type": "String", "key": "flag" }))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::INTERNAL_SERVER_ERROR);
+        assert_eq!(fake_proxy.value("flag"), Some(json!(true)));
+
+        let response = client
+            .get(format!("{}/health", fake_proxy.url))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(fake_proxy.sync_calls().len(), 2);
+    }
+}
@@ -0,0 +1,61 @@
+pub mod fake_proxy;
+
+use std::time::Duration;
+
+use axum_test_helper::TestClient;
+use reqwest::Client;
+use serde_json::json;
+use tokio::time::sleep;
+
+use crate::{
+    config::{OutboxConfig, SyncConfig},
+    controller::routes,
+    store::Stores,
+    web::Web,
+    Services,
+};
+
+// A hub whose stores all live in memory, so that every test starts from scratch
+// and nothing outside the process is needed
+pub async fn test_hub() -> TestClient {
+    let service = Services::with_stores(&Stores::memory(), Client::new());
+
+    TestClient::new(routes(service))
+}
+
+// Same as test_hub, with the given settings. The services are handed out too,
+// so that tests can run the work of the background workers themselves
+pub async fn test_hub_with(
+    sync_config: &SyncConfig,
+    outbox_config: &OutboxConfig,
+) -> (TestClient, Services) {
+    let service =
+        Services::with_config(&Stores::memory(), Client::new(), sync_config, outbox_config);
+
+    (TestClient::new(routes(service.clone())), service)
+}
+
+// Registers a proxy and waits until its bootstrap is over,
+// so that the writes made afterwards are sent to it right away
+pub async fn add_proxy(test_client: &TestClient, url: &str) {
+    test_client
+        .post("/proxy/create")
+        .json(&json!({ "url": url }))
+        .send()
+        .await;
+
+    for _ in 0..100 {
+        let Web { data, .. } = test_client
+            .get(&format!("/proxy/lookup?url={url}"))
+            .send()
+            .await
+            .json()
+            .await;
+        if data["bootstrap"]["status"] == "completed" {
+            return;
+        }
+        sleep(Duration::from_millis(10)).await;
+    }
+
+    panic!("The bootstrap of {url} did not finish");
+}
@@ -6,6 +6,8 @@ use axum::{
 use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
 
+use crate::models::delivery::DeliveryReport;
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Web {
     pub code: String,
@@ -41,6 +43,23 @@ impl Web {
             .into_response()
     }
 
+    pub fn multi_status<'a>(
+        message: impl ToString,
+        error: impl ToString,
+        data: impl Serialize + Deserialize<'a>,
+    ) -> Response {
+        (
+            StatusCode::MULTI_STATUS,
+            Json(Web {
+                code: StatusCode::MULTI_STATUS.to_string(),
+                message: message.to_string(),
+                data: json!(&data),
+                error: error.to_string(),
+            }),
+        )
+            .into_response()
+    }
+
     pub fn unauthorized(message: impl ToString, error: impl ToString) -> Response {
         (
             StatusCode::UNAUTHORIZED,
@@ -118,4 +137,49 @@ impl Web {
         )
             .into_response()
     }
+
+    pub fn bad_gateway<'a>(
+        message: impl ToString,
+        error: impl ToString,
+        data: impl Serialize + Deserialize<'a>,
+    ) -> Response {
+        (
+            StatusCode::BAD_GATEWAY,
+            Json(Web {
+                code: StatusCode::BAD_GATEWAY.to_string(),
+                message: message.to_string(),
+                data: json!(&data),
+                error: error.to_string(),
+            }),
+        )
+            .into_response()
+    }
+
+    // Picks the response status from the outcome of a fan-out:
+    // 200 when every proxy succeeded, 207 when only some did, 502 when none did
+    pub fn report(message: impl ToString, report: DeliveryReport) -> Response {
+        let failed_urls = report
+            .deliveries
+            .iter()
+            .filter(|delivery| !delivery.is_success())
+            .map(|delivery| delivery.url.as_str())
+            .collect::<Vec<_>>()
+            .join(", ");
+
+        match (report.delivered, report.failed) {
+            (_, 0) => Web::ok(message, report),
+            (0, _) => Web::bad_gateway(
+                "Request to proxies error",
+                format!("No proxy received the request. Failed proxies: {failed_urls}."),
+                report,
+            ),
+            (_, failed) => Web::multi_status(
+                "Request delivered to some proxies",
+                format!(
+                    "{failed} proxies did not receive the request. Failed proxies: {failed_urls}."
+                ),
+                report,
+            ),
+        }
+    }
 }
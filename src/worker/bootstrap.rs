@@ -0,0 +1,36 @@
+use crate::{
+    error::Error,
+    models::proxy::{BootstrapStatus, Proxy, ProxyMode},
+    service::{proxy::ProxyService, sync::SyncService},
+};
+
+// Startup task that restarts the bootstraps which were still
+// pending or running when the hub went down, and the replays
+// of proxies that were being re-enabled
+pub async fn resume(proxy_service: ProxyService, sync_service: SyncService) {
+    async fn unfinished(proxy_service: &ProxyService) -> Result<Vec<Proxy>, Error> {
+        let mut proxies = proxy_service.get_proxies().await?;
+        proxies.retain(|proxy| {
+            matches!(
+                proxy.bootstrap.status,
+                BootstrapStatus::Pending | BootstrapStatus::Running
+            ) || proxy.mode == ProxyMode::Replaying
+        });
+        Ok(proxies)
+    }
+
+    match unfinished(&proxy_service).await {
+        Ok(proxies) => proxies.into_iter().for_each(|proxy| {
+            if matches!(
+                proxy.bootstrap.status,
+                BootstrapStatus::Pending | BootstrapStatus::Running
+            ) {
+                sync_service.spawn_bootstrap(&proxy.url);
+            }
+            if proxy.mode == ProxyMode::Replaying {
+                sync_service.spawn_replay(proxy);
+            }
+        }),
+        Err(e) => eprintln!("Cannot resume bootstraps and replays: {e}"),
+    }
+}
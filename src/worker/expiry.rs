@@ -0,0 +1,14 @@
+use tokio::time::sleep;
+
+use crate::{config::ExpiryConfig, service::sync::SyncService};
+
+// Background task that deletes the expired keys from the proxies
+pub async fn expire(sync_service: SyncService, config: ExpiryConfig) {
+    loop {
+        sleep(config.interval).await;
+
+        if let Err(e) = sync_service.expire_due().await {
+            eprintln!("Expiry failed: {e}");
+        }
+    }
+}
@@ -0,0 +1,13 @@
+use tokio::time::sleep;
+
+use crate::{config::HealthConfig, service::health::HealthService};
+
+// Background task that keeps the health of every proxy up to date
+pub async fn monitor(health_service: HealthService, config: HealthConfig) {
+    loop {
+        if let Err(e) = health_service.check_all().await {
+            eprintln!("Health check failed: {e}");
+        }
+        sleep(config.interval).await;
+    }
+}
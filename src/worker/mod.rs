@@ -0,0 +1,5 @@
+pub mod bootstrap;
+pub mod expiry;
+pub mod health;
+pub mod outbox;
+pub mod reconcile;
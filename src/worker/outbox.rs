@@ -0,0 +1,14 @@
+use tokio::time::sleep;
+
+use crate::{config::OutboxConfig, service::sync::SyncService};
+
+// Background task that keeps delivering pending outbox entries,
+// including the ones left over from before a restart
+pub async fn dispatch(sync_service: SyncService, config: OutboxConfig) {
+    loop {
+        if let Err(e) = sync_service.dispatch_due().await {
+            eprintln!("Outbox dispatch failed: {e}");
+        }
+        sleep(config.dispatch_interval).await;
+    }
+}
@@ -0,0 +1,39 @@
+use tokio::time::sleep;
+
+use crate::{config::ReconcileConfig, service::sync::SyncService};
+
+// Background task that periodically compares every proxy with the hub
+// and pushes the keys that drifted
+pub async fn reconcile(sync_service: SyncService, config: ReconcileConfig) {
+    loop {
+        sleep(config.interval).await;
+
+        match sync_service
+            .reconcile_all(None, false, config.delete_unknown)
+            .await
+        {
+            Ok(reports) => {
+                for report in reports
+                    .iter()
+                    .filter(|r| r.has_drift() || r.error.is_some())
+                {
+                    eprintln!(
+                        "Drift on {}: {} missing, {} stale, {} extra, {} repaired, {} failed{}",
+                        report.url,
+                        report.missing.len(),
+                        report.stale.len(),
+                        report.extra.len(),
+                        report.repaired,
+                        report.failed,
+                        report
+                            .error
+                            .as_ref()
+                            .map(|e| format!(", error: {e}"))
+                            .unwrap_or_default()
+                    );
+                }
+            }
+            Err(e) => eprintln!("Reconcile failed: {e}"),
+        }
+    }
+}